@@ -1,17 +1,20 @@
 use crate::{
-    io_utils, Chunk, PerformanceCounters, Region, RegionError, CHUNKS_PER_REGION,
-    COMPRESSION_TYPE_GZIP, COMPRESSION_TYPE_LZ4, COMPRESSION_TYPE_NONE, COMPRESSION_TYPE_ZLIB,
-    EXTERNAL_FILE_COMPRESSION_TYPE, REGION_DIMENSION, SECTOR_SIZE,
+    format::DetectedFormat, io_utils, Chunk, ChunkIssue, ChunkIssueReason, PerformanceCounters,
+    ReadMode, ReadReport, Region, RegionError, ValidationCheck, ValidationReport,
+    CHUNKS_PER_REGION, COMPRESSION_TYPE_GZIP, COMPRESSION_TYPE_LZ4, COMPRESSION_TYPE_NONE,
+    COMPRESSION_TYPE_ZLIB, EXTERNAL_FILE_FLAG, REGION_DIMENSION, SECTOR_SIZE,
 };
 use anyhow::{Context, Result};
 use flate2::{
     read::{GzDecoder, ZlibDecoder},
-    write::ZlibEncoder,
+    write::{GzEncoder, ZlibEncoder},
     Compression,
 };
-use std::io::{Read, Write};
-use std::path::Path;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use xxhash_rust::xxh32::xxh32;
 
 /// Anvil chunk location entry (4 bytes)
 #[repr(C, packed)]
@@ -39,7 +42,7 @@ impl ChunkLocation {
         }
     }
 
-    fn to_bytes(&self) -> [u8; Self::SIZE] {
+    fn to_bytes(self) -> [u8; Self::SIZE] {
         [
             self.offset[0],
             self.offset[1],
@@ -84,7 +87,7 @@ impl ChunkDataHeader {
         }
     }
 
-    fn to_bytes(&self) -> [u8; Self::SIZE] {
+    fn to_bytes(self) -> [u8; Self::SIZE] {
         let mut bytes = [0u8; Self::SIZE];
         bytes[0..4].copy_from_slice(&self.length.to_be_bytes());
         bytes[4] = self.compression_type;
@@ -156,35 +159,183 @@ fn decompress_lz4_block_stream(mut data: &[u8]) -> Result<Vec<u8>> {
     }
 }
 
-pub fn read_anvil_region<P: AsRef<Path>>(
-    path: P,
-    counters: Option<Arc<PerformanceCounters>>,
-) -> Result<Region> {
-    let path = path.as_ref();
+/// Writes `data` in the `LZ4BlockOutputStream` framing used by vanilla's LZ4
+/// region compression, including the end-of-stream marker.
+fn compress_lz4_block_stream(data: &[u8]) -> Vec<u8> {
+    const MAGIC: &[u8; 8] = b"LZ4Block";
+    const BLOCK_SIZE: usize = 64 * 1024;
+    const METHOD_RAW: u8 = 0x10;
+    const METHOD_LZ4: u8 = 0x20;
+    // Level field of the token, log2(BLOCK_SIZE) - 10
+    const COMPRESSION_LEVEL: u8 = 6;
+    const CHECKSUM_SEED: u32 = 0x9747b28c;
+
+    let mut out = Vec::with_capacity(data.len() / 2 + 64);
+    for block in data.chunks(BLOCK_SIZE) {
+        let compressed = lz4_flex::block::compress(block);
+        let (method, payload) = if compressed.len() < block.len() {
+            (METHOD_LZ4, &compressed[..])
+        } else {
+            (METHOD_RAW, block)
+        };
+        // lz4-java masks its streaming xxhash32 checksum to 28 bits
+        let checksum = xxh32(block, CHECKSUM_SEED) & 0x0fff_ffff;
+
+        out.extend_from_slice(MAGIC);
+        out.push(method | COMPRESSION_LEVEL);
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(block.len() as u32).to_le_bytes());
+        out.extend_from_slice(&checksum.to_le_bytes());
+        out.extend_from_slice(payload);
+    }
 
-    let filename = path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .context("Invalid filename")?;
-    let (region_x, region_z) = Region::parse_filename(filename)?;
+    out.extend_from_slice(MAGIC);
+    out.push(METHOD_RAW | COMPRESSION_LEVEL);
+    out.extend_from_slice(&[0u8; 12]);
+    out
+}
+
+fn decompress_chunk(compression_type: u8, payload: &[u8], x: i32, z: i32) -> Result<Vec<u8>> {
+    let decompressed = match compression_type {
+        COMPRESSION_TYPE_ZLIB => {
+            let mut decoder = ZlibDecoder::new(payload);
+            let mut decompressed = Vec::new();
+            decoder
+                .read_to_end(&mut decompressed)
+                .context("Failed to decompress zlib chunk")?;
+            decompressed
+        }
+        COMPRESSION_TYPE_GZIP => {
+            let mut decoder = GzDecoder::new(payload);
+            let mut decompressed = Vec::new();
+            decoder
+                .read_to_end(&mut decompressed)
+                .context("Failed to decompress gzip chunk")?;
+            decompressed
+        }
+        COMPRESSION_TYPE_NONE => payload.to_vec(),
+        COMPRESSION_TYPE_LZ4 => decompress_lz4_block_stream(payload)?,
+        _ => {
+            return Err(RegionError::UnsupportedCompression {
+                compression_type,
+                x,
+                z,
+            }
+            .into());
+        }
+    };
+    Ok(decompressed)
+}
 
-    let mmap = io_utils::mmap_file(path)?;
-    let file_size = mmap.len();
+fn is_known_compression(compression_type: u8) -> bool {
+    matches!(
+        compression_type,
+        COMPRESSION_TYPE_GZIP
+            | COMPRESSION_TYPE_ZLIB
+            | COMPRESSION_TYPE_NONE
+            | COMPRESSION_TYPE_LZ4
+    )
+}
 
-    if let Some(ref counters) = counters {
-        counters.add_bytes_read(file_size as u64);
+/// Decodes the chunk stored in the sectors `chunk_data`, or describes why it cannot be.
+/// External `.mcc` chunks are looked up in `source_dir` and reported missing without one.
+/// A length field larger than the sectors fails strict reads; lenient ones clamp
+/// it to the sectors, as vanilla does, and return the clamp alongside the data.
+fn decode_chunk_sectors(
+    chunk_data: &[u8],
+    source_dir: Option<&Path>,
+    chunk_x: i32,
+    chunk_z: i32,
+    mode: ReadMode,
+) -> std::result::Result<(Vec<u8>, Option<ChunkIssueReason>), ChunkIssueReason> {
+    let header = ChunkDataHeader::from_bytes(&chunk_data[..ChunkDataHeader::SIZE]);
+    let compressed_data = &chunk_data[ChunkDataHeader::SIZE..];
+
+    if header.compression_type & EXTERNAL_FILE_FLAG != 0 {
+        let compression_type = header.compression_type & !EXTERNAL_FILE_FLAG;
+        if !is_known_compression(compression_type) {
+            return Err(ChunkIssueReason::UnsupportedCompression {
+                compression_type: header.compression_type,
+            });
+        }
+
+        let external_name = format!("c.{}.{}.mcc", chunk_x, chunk_z);
+        let Some(source_dir) = source_dir else {
+            return Err(ChunkIssueReason::MissingExternalFile {
+                path: PathBuf::from(external_name),
+            });
+        };
+        let external_path = source_dir.join(external_name);
+        let external_mmap = io_utils::mmap_file(&external_path).map_err(|_| {
+            ChunkIssueReason::MissingExternalFile {
+                path: external_path.clone(),
+            }
+        })?;
+
+        return decompress_chunk(compression_type, &external_mmap, chunk_x, chunk_z)
+            .map(|nbt_data| (nbt_data, None))
+            .map_err(|e| ChunkIssueReason::DecompressionFailed {
+                reason: format!("{:#}", e),
+            });
     }
 
-    if file_size < SECTOR_SIZE * 2 {
-        return Err(RegionError::InvalidFormat.into());
+    if !is_known_compression(header.compression_type) {
+        return Err(ChunkIssueReason::UnsupportedCompression {
+            compression_type: header.compression_type,
+        });
     }
 
+    let oversized = (header.length as usize).saturating_sub(1) > compressed_data.len();
+    if header.length == 0 || (oversized && mode == ReadMode::Strict) {
+        return Err(ChunkIssueReason::InvalidLength {
+            length: header.length,
+        });
+    }
+    let data_length = std::cmp::min(header.length as usize - 1, compressed_data.len());
+    let payload = &compressed_data[..data_length];
+    let clamped = oversized.then(|| ChunkIssueReason::LengthClamped {
+        length: header.length,
+        available: (data_length + 1) as u32,
+    });
+
+    decompress_chunk(header.compression_type, payload, chunk_x, chunk_z)
+        .map(|nbt_data| (nbt_data, clamped))
+        .map_err(|e| ChunkIssueReason::DecompressionFailed {
+            reason: format!("{:#}", e),
+        })
+}
+
+fn parse_anvil(
+    data: &[u8],
+    region_x: i32,
+    region_z: i32,
+    source_dir: Option<&Path>,
+    mode: ReadMode,
+) -> Result<(Region, ReadReport)> {
+    let mut report = ReadReport::default();
+
+    // Vanilla leaves zero-byte and partially written files behind; read what is there
+    let header_data;
+    let data = if data.len() < SECTOR_SIZE * 2 {
+        if mode == ReadMode::Strict {
+            return Err(RegionError::InvalidFormat.into());
+        }
+        report.truncated_header = true;
+        let mut padded = data.to_vec();
+        padded.resize(SECTOR_SIZE * 2, 0);
+        header_data = padded;
+        &header_data[..]
+    } else {
+        data
+    };
+    let file_size = data.len();
+
     // Parse chunk locations (first 4KB)
     let mut chunk_locations = Vec::with_capacity(CHUNKS_PER_REGION);
     for i in 0..CHUNKS_PER_REGION {
         let start = i * ChunkLocation::SIZE;
         let end = start + ChunkLocation::SIZE;
-        let location = ChunkLocation::from_bytes(&mmap[start..end]);
+        let location = ChunkLocation::from_bytes(&data[start..end]);
         chunk_locations.push(location);
     }
 
@@ -192,143 +343,477 @@ pub fn read_anvil_region<P: AsRef<Path>>(
     let mut timestamps = Vec::with_capacity(CHUNKS_PER_REGION);
     for i in 0..CHUNKS_PER_REGION {
         let start = SECTOR_SIZE + i * 4;
-        let _end = start + 4;
         let timestamp = u32::from_be_bytes([
-            mmap[start],
-            mmap[start + 1],
-            mmap[start + 2],
-            mmap[start + 3],
+            data[start],
+            data[start + 1],
+            data[start + 2],
+            data[start + 3],
         ]);
         timestamps.push(timestamp);
     }
 
     let mut region = Region::new(region_x, region_z);
-    region.mtime = std::fs::metadata(path)?
-        .modified()?
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_secs();
     region.timestamps.copy_from_slice(&timestamps);
 
-    let source_dir = path.parent().unwrap_or_else(|| Path::new("."));
-
-    let mut chunks_loaded = 0u64;
     for (i, location) in chunk_locations.iter().enumerate() {
         if location.is_empty() {
             continue;
         }
 
+        let chunk_x = region_x * REGION_DIMENSION as i32 + (i % REGION_DIMENSION) as i32;
+        let chunk_z = region_z * REGION_DIMENSION as i32 + (i / REGION_DIMENSION) as i32;
+
         let sector_offset = location.get_offset() as usize;
         let sector_count = location.sector_count as usize;
 
-        if sector_offset == 0 || sector_count == 0 {
-            continue;
-        }
-
         let chunk_start = sector_offset * SECTOR_SIZE;
         let chunk_end = chunk_start + sector_count * SECTOR_SIZE;
 
-        if chunk_end > file_size {
-            continue;
+        let result = if sector_offset < 2 || sector_count == 0 || chunk_end > file_size {
+            Err(ChunkIssueReason::SectorOutOfBounds {
+                sector_offset: sector_offset as u32,
+                sector_count: sector_count as u8,
+            })
+        } else {
+            decode_chunk_sectors(
+                &data[chunk_start..chunk_end],
+                source_dir,
+                chunk_x,
+                chunk_z,
+                mode,
+            )
+        };
+
+        match result {
+            Ok((nbt_data, clamped)) => {
+                let chunk = Chunk::new(nbt_data, chunk_x, chunk_z);
+                region.set_chunk(i, chunk, timestamps[i]);
+                report.chunks_loaded += 1;
+                if let Some(reason) = clamped {
+                    report.issues.push(ChunkIssue {
+                        index: i,
+                        x: chunk_x,
+                        z: chunk_z,
+                        reason,
+                    });
+                }
+            }
+            Err(reason) => {
+                let issue = ChunkIssue {
+                    index: i,
+                    x: chunk_x,
+                    z: chunk_z,
+                    reason,
+                };
+                if mode == ReadMode::Strict {
+                    return Err(issue.into_error().into());
+                }
+                report.issues.push(issue);
+            }
         }
+    }
 
-        let chunk_data = &mmap[chunk_start..chunk_end];
+    Ok((region, report))
+}
+
+/// Sanity-checks the location table in `header` (the first two sectors of a
+/// file of `file_len` bytes). Entries that point into the header mean the file
+/// is not Anvil; entries past the end are tolerated as long as most fit.
+pub(crate) fn detect_anvil(header: &[u8], file_len: u64) -> DetectedFormat {
+    if header.len() < SECTOR_SIZE * 2 {
+        return DetectedFormat::Unknown;
+    }
 
-        if chunk_data.len() < ChunkDataHeader::SIZE {
+    let file_sectors = file_len.div_ceil(SECTOR_SIZE as u64);
+    let mut chunk_count = 0;
+    let mut out_of_bounds_chunks = 0;
+    for i in 0..CHUNKS_PER_REGION {
+        let start = i * ChunkLocation::SIZE;
+        let location = ChunkLocation::from_bytes(&header[start..start + ChunkLocation::SIZE]);
+        if location.is_empty() {
             continue;
         }
+        let offset = location.get_offset() as u64;
+        if offset < 2 || location.sector_count == 0 {
+            return DetectedFormat::Unknown;
+        }
+        chunk_count += 1;
+        if offset + location.sector_count as u64 > file_sectors {
+            out_of_bounds_chunks += 1;
+        }
+    }
 
-        let header = ChunkDataHeader::from_bytes(&chunk_data[..ChunkDataHeader::SIZE]);
-        let compressed_data = &chunk_data[ChunkDataHeader::SIZE..];
+    if out_of_bounds_chunks * 2 > chunk_count {
+        return DetectedFormat::Unknown;
+    }
+    DetectedFormat::Anvil {
+        chunk_count,
+        out_of_bounds_chunks,
+    }
+}
 
-        let chunk_x = region_x * REGION_DIMENSION as i32 + (i % REGION_DIMENSION) as i32;
-        let chunk_z = region_z * REGION_DIMENSION as i32 + (i / REGION_DIMENSION) as i32;
+pub fn read_anvil_region<P: AsRef<Path>>(
+    path: P,
+    counters: Option<Arc<PerformanceCounters>>,
+) -> Result<Region> {
+    let path = path.as_ref();
 
-        let data_length = std::cmp::min(
-            (header.length as usize).saturating_sub(1),
-            compressed_data.len(),
-        );
-        let payload = &compressed_data[..data_length];
-
-        let nbt_data = match header.compression_type {
-            COMPRESSION_TYPE_ZLIB => {
-                let mut decoder = ZlibDecoder::new(payload);
-                let mut decompressed = Vec::new();
-                decoder
-                    .read_to_end(&mut decompressed)
-                    .context("Failed to decompress zlib chunk")?;
-                decompressed
-            }
-            COMPRESSION_TYPE_GZIP => {
-                let mut decoder = GzDecoder::new(payload);
-                let mut decompressed = Vec::new();
-                decoder
-                    .read_to_end(&mut decompressed)
-                    .context("Failed to decompress gzip chunk")?;
-                decompressed
-            }
-            COMPRESSION_TYPE_NONE => payload.to_vec(),
-            COMPRESSION_TYPE_LZ4 => decompress_lz4_block_stream(payload)?,
-            EXTERNAL_FILE_COMPRESSION_TYPE => {
-                let external_path = source_dir.join(format!("c.{}.{}.mcc", chunk_x, chunk_z));
-                let external_mmap = io_utils::mmap_file(&external_path).with_context(|| {
-                    format!("Failed to read external file: {:?}", external_path)
-                })?;
-
-                let mut decoder = ZlibDecoder::new(&external_mmap[..]);
-                let mut decompressed = Vec::new();
-                decoder
-                    .read_to_end(&mut decompressed)
-                    .context("Failed to decompress external chunk")?;
-                decompressed
-            }
-            _ => {
-                return Err(RegionError::UnsupportedCompression {
-                    compression_type: header.compression_type,
-                    x: chunk_x,
-                    z: chunk_z,
-                }
-                .into());
-            }
-        };
+    if std::fs::metadata(path)?.len() < (SECTOR_SIZE * 2) as u64 {
+        return Err(RegionError::InvalidFormat.into());
+    }
 
-        let chunk = Chunk::new(nbt_data, chunk_x, chunk_z);
-        region.set_chunk(i, chunk, timestamps[i]);
-        chunks_loaded += 1;
+    let (region, report) = read_anvil_region_with_report(path, ReadMode::Lenient, counters)?;
+    reject_payload_errors(report)?;
+
+    Ok(region)
+}
+
+/// Chunks whose sectors run past EOF are dropped and oversized length fields
+/// clamped, but a chunk that is present and cannot be decoded fails the whole region.
+fn reject_payload_errors(report: ReadReport) -> Result<()> {
+    if let Some(issue) = report
+        .issues
+        .into_iter()
+        .find(|issue| issue.reason.is_payload_error())
+    {
+        return Err(issue.into_error().into());
     }
+    Ok(())
+}
 
-    if let Some(ref counters) = counters {
-        counters.add_file();
-        counters.add_chunks(chunks_loaded);
+/// Reads an Anvil region held in memory. External `.mcc` chunks cannot be
+/// resolved without a directory and fail the read.
+pub fn read_anvil_region_from_bytes(data: &[u8], region_x: i32, region_z: i32) -> Result<Region> {
+    if data.len() < SECTOR_SIZE * 2 {
+        return Err(RegionError::InvalidFormat.into());
     }
 
+    let (region, report) = parse_anvil(data, region_x, region_z, None, ReadMode::Lenient)?;
+    reject_payload_errors(report)?;
+
     Ok(region)
 }
 
-pub fn write_anvil_region<P: AsRef<Path>>(
+/// Reads an Anvil region from a stream, e.g. an archive entry or a download.
+pub fn read_anvil_region_from_reader<R: Read>(
+    mut reader: R,
+    region_x: i32,
+    region_z: i32,
+) -> Result<Region> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    read_anvil_region_from_bytes(&data, region_x, region_z)
+}
+
+/// Reads an Anvil region and reports every chunk that could not be loaded.
+///
+/// In [`ReadMode::Lenient`] damaged chunks are skipped and empty or truncated
+/// files yield an empty region; in [`ReadMode::Strict`] the first anomaly is an error.
+/// A length field past the allocated sectors fails strict reads, while lenient
+/// ones clamp it, load the chunk and report [`ChunkIssueReason::LengthClamped`].
+pub fn read_anvil_region_with_report<P: AsRef<Path>>(
     path: P,
-    region: &Region,
-    compression_level: u32,
+    mode: ReadMode,
     counters: Option<Arc<PerformanceCounters>>,
-) -> Result<()> {
+) -> Result<(Region, ReadReport)> {
     let path = path.as_ref();
-    let destination_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("Invalid filename")?;
+    let (region_x, region_z) = Region::parse_filename(filename)?;
+
+    // Zero-byte files cannot be mapped
+    let file_size = std::fs::metadata(path)?.len();
+    let mmap = if file_size > 0 {
+        Some(io_utils::mmap_file(path)?)
+    } else {
+        None
+    };
+    let data = mmap.as_deref().unwrap_or(&[]);
+
+    if let Some(ref counters) = counters {
+        counters.add_bytes_read(file_size);
+    }
+
+    let source_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let (mut region, report) = parse_anvil(data, region_x, region_z, Some(source_dir), mode)?;
+    region.mtime = std::fs::metadata(path)?
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+
+    if let Some(ref counters) = counters {
+        counters.add_file();
+        counters.add_chunks(report.chunks_loaded as u64);
+    }
 
+    Ok((region, report))
+}
+
+/// Checks the location table and chunk headers of an Anvil file: sectors that
+/// overlap or lie outside the file, bad length fields and unknown compression
+/// types. Payloads are not decompressed.
+pub fn validate_anvil_file<P: AsRef<Path>>(path: P) -> Result<ValidationReport> {
+    let path = path.as_ref();
+    // Zero-byte files cannot be mapped
+    let mmap = if std::fs::metadata(path)?.len() > 0 {
+        Some(io_utils::mmap_file(path)?)
+    } else {
+        None
+    };
+    Ok(validate_anvil_data(mmap.as_deref().unwrap_or(&[])))
+}
+
+fn validate_anvil_data(data: &[u8]) -> ValidationReport {
+    let mut report = ValidationReport {
+        file_size: data.len() as u64,
+        ..Default::default()
+    };
+
+    if data.len() < SECTOR_SIZE * 2 {
+        report.push(
+            0,
+            None,
+            ValidationCheck::FileTooShort {
+                size: data.len() as u64,
+                minimum: (SECTOR_SIZE * 2) as u64,
+            },
+        );
+        return report;
+    }
+
+    let mut sector_owners: Vec<Option<usize>> = vec![None; data.len().div_ceil(SECTOR_SIZE)];
+    for i in 0..CHUNKS_PER_REGION {
+        let entry_offset = i * ChunkLocation::SIZE;
+        let location =
+            ChunkLocation::from_bytes(&data[entry_offset..entry_offset + ChunkLocation::SIZE]);
+        if location.is_empty() {
+            continue;
+        }
+
+        let sector_offset = location.get_offset() as usize;
+        let sector_count = location.sector_count as usize;
+        let chunk_start = sector_offset * SECTOR_SIZE;
+        let chunk_end = chunk_start + sector_count * SECTOR_SIZE;
+        if sector_offset < 2 || sector_count == 0 || chunk_end > data.len() {
+            report.push(
+                entry_offset as u64,
+                Some(i),
+                ValidationCheck::SectorOutOfBounds {
+                    sector_offset: sector_offset as u32,
+                    sector_count: sector_count as u8,
+                },
+            );
+            continue;
+        }
+
+        let mut overlaps = None;
+        for owner in &mut sector_owners[sector_offset..sector_offset + sector_count] {
+            match owner {
+                Some(other) => overlaps = overlaps.or(Some(*other)),
+                None => *owner = Some(i),
+            }
+        }
+        if let Some(other) = overlaps {
+            report.push(
+                entry_offset as u64,
+                Some(i),
+                ValidationCheck::SectorOverlap { other },
+            );
+        }
+
+        let header = ChunkDataHeader::from_bytes(&data[chunk_start..]);
+        let compression_type = header.compression_type & !EXTERNAL_FILE_FLAG;
+        if !is_known_compression(compression_type) {
+            report.push(
+                (chunk_start + 4) as u64,
+                Some(i),
+                ValidationCheck::UnsupportedCompression {
+                    compression_type: header.compression_type,
+                },
+            );
+        }
+
+        // External chunks keep their payload in a .mcc file, so the length is not checked
+        let available = sector_count * SECTOR_SIZE - ChunkDataHeader::SIZE;
+        if header.compression_type & EXTERNAL_FILE_FLAG == 0
+            && (header.length == 0 || header.length as usize - 1 > available)
+        {
+            report.push(
+                chunk_start as u64,
+                Some(i),
+                ValidationCheck::InvalidLength {
+                    length: header.length,
+                },
+            );
+        }
+    }
+
+    report
+}
+
+/// How one chunk is stored in an `.mca` file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnvilChunkStorage {
+    /// Compression type, without [`EXTERNAL_FILE_FLAG`]
+    pub compression_type: u8,
+    /// Whether the payload is in a `c.<x>.<z>.mcc` file
+    pub external: bool,
+    /// Size of the compressed payload, taken from the `.mcc` file for external chunks
+    pub compressed_size: u64,
+}
+
+/// Reads the location table of the `.mca` file at `path` and the header of
+/// every chunk in it without decompressing anything. Empty slots and entries
+/// pointing outside the file are `None`.
+pub fn read_anvil_chunk_storage<P: AsRef<Path>>(path: P) -> Result<Vec<Option<AnvilChunkStorage>>> {
+    let path = path.as_ref();
+    let mut storage = vec![None; CHUNKS_PER_REGION];
+    if std::fs::metadata(path)?.len() < (SECTOR_SIZE * 2) as u64 {
+        return Ok(storage);
+    }
+
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("Invalid filename")?;
+    let (region_x, region_z) = Region::parse_filename(filename)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+    let data = io_utils::mmap_file(path)?;
+
+    for (i, slot) in storage.iter_mut().enumerate() {
+        let location = ChunkLocation::from_bytes(&data[i * ChunkLocation::SIZE..]);
+        let chunk_start = location.get_offset() as usize * SECTOR_SIZE;
+        if location.is_empty()
+            || location.get_offset() < 2
+            || chunk_start + ChunkDataHeader::SIZE > data.len()
+        {
+            continue;
+        }
+
+        let header =
+            ChunkDataHeader::from_bytes(&data[chunk_start..chunk_start + ChunkDataHeader::SIZE]);
+        let external = header.compression_type & EXTERNAL_FILE_FLAG != 0;
+        let compressed_size = if external {
+            let chunk_x = region_x * REGION_DIMENSION as i32 + (i % REGION_DIMENSION) as i32;
+            let chunk_z = region_z * REGION_DIMENSION as i32 + (i / REGION_DIMENSION) as i32;
+            std::fs::metadata(directory.join(format!("c.{}.{}.mcc", chunk_x, chunk_z)))
+                .map(|metadata| metadata.len())
+                .unwrap_or(0)
+        } else {
+            header.length.saturating_sub(1) as u64
+        };
+        *slot = Some(AnvilChunkStorage {
+            compression_type: header.compression_type & !EXTERNAL_FILE_FLAG,
+            external,
+            compressed_size,
+        });
+    }
+
+    Ok(storage)
+}
+
+/// Per-chunk compression used when writing Anvil regions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkCompression {
+    Gzip,
+    Zlib,
+    None,
+    Lz4,
+}
+
+impl ChunkCompression {
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            ChunkCompression::Gzip => COMPRESSION_TYPE_GZIP,
+            ChunkCompression::Zlib => COMPRESSION_TYPE_ZLIB,
+            ChunkCompression::None => COMPRESSION_TYPE_NONE,
+            ChunkCompression::Lz4 => COMPRESSION_TYPE_LZ4,
+        }
+    }
+
+    /// The compression of an Anvil compression type byte, without [`EXTERNAL_FILE_FLAG`].
+    pub fn from_u8(compression_type: u8) -> Option<Self> {
+        match compression_type {
+            COMPRESSION_TYPE_GZIP => Some(ChunkCompression::Gzip),
+            COMPRESSION_TYPE_ZLIB => Some(ChunkCompression::Zlib),
+            COMPRESSION_TYPE_NONE => Some(ChunkCompression::None),
+            COMPRESSION_TYPE_LZ4 => Some(ChunkCompression::Lz4),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AnvilWriteOptions {
+    pub compression: ChunkCompression,
+    /// zlib/gzip level (0-9). Ignored for `None` and `Lz4`.
+    pub compression_level: u32,
+}
+
+impl AnvilWriteOptions {
+    pub fn new(compression: ChunkCompression, compression_level: u32) -> Self {
+        Self {
+            compression,
+            compression_level,
+        }
+    }
+}
+
+impl Default for AnvilWriteOptions {
+    fn default() -> Self {
+        Self::new(ChunkCompression::Zlib, 6)
+    }
+}
+
+fn compress_chunk(data: &[u8], options: &AnvilWriteOptions) -> Result<Vec<u8>> {
+    let compressed = match options.compression {
+        ChunkCompression::Zlib => {
+            let mut encoder =
+                ZlibEncoder::new(Vec::new(), Compression::new(options.compression_level));
+            encoder
+                .write_all(data)
+                .context("Failed to write chunk data to compressor")?;
+            encoder.finish().context("Failed to compress chunk data")?
+        }
+        ChunkCompression::Gzip => {
+            let mut encoder =
+                GzEncoder::new(Vec::new(), Compression::new(options.compression_level));
+            encoder
+                .write_all(data)
+                .context("Failed to write chunk data to compressor")?;
+            encoder.finish().context("Failed to compress chunk data")?
+        }
+        ChunkCompression::None => data.to_vec(),
+        ChunkCompression::Lz4 => compress_lz4_block_stream(data),
+    };
+    Ok(compressed)
+}
+
+/// Builds a complete Anvil file. Chunks that do not fit in 255 sectors are
+/// written to `c.<x>.<z>.mcc` files in `external_dir`, or rejected if it is `None`.
+fn build_anvil_file(
+    region: &Region,
+    options: &AnvilWriteOptions,
+    external_dir: Option<&Path>,
+) -> Result<Vec<u8>> {
     let mut chunk_locations = Vec::with_capacity(CHUNKS_PER_REGION);
     let mut sector_data = Vec::new();
     let mut current_sector = 2;
 
     for i in 0..CHUNKS_PER_REGION {
         if let Some(chunk) = region.get_chunk(i) {
-            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(compression_level));
-            encoder
-                .write_all(chunk.as_slice())
-                .context("Failed to write chunk data to compressor")?;
-            let compressed = encoder.finish().context("Failed to compress chunk data")?;
+            let compressed = compress_chunk(chunk.as_slice(), options)?;
 
             let data_size = ChunkDataHeader::SIZE + compressed.len();
-            let sectors_needed = (data_size + SECTOR_SIZE - 1) / SECTOR_SIZE;
+            let sectors_needed = data_size.div_ceil(SECTOR_SIZE);
 
             if sectors_needed > 255 {
+                let Some(destination_dir) = external_dir else {
+                    return Err(RegionError::InvalidFormat.into());
+                };
+
                 let chunk_x =
                     region.region_x * REGION_DIMENSION as i32 + (i % REGION_DIMENSION) as i32;
                 let chunk_z =
@@ -338,7 +823,8 @@ pub fn write_anvil_region<P: AsRef<Path>>(
                 io_utils::atomic_write(&external_path, &compressed)?;
                 io_utils::set_mtime(&external_path, region.mtime)?;
 
-                let header = ChunkDataHeader::new(1, EXTERNAL_FILE_COMPRESSION_TYPE);
+                let header =
+                    ChunkDataHeader::new(1, EXTERNAL_FILE_FLAG | options.compression.as_u8());
                 let mut sector_chunk = Vec::with_capacity(SECTOR_SIZE);
                 sector_chunk.extend_from_slice(&header.to_bytes());
                 sector_chunk.resize(SECTOR_SIZE, 0); // Pad to sector boundary
@@ -348,7 +834,7 @@ pub fn write_anvil_region<P: AsRef<Path>>(
                 current_sector += 1;
             } else {
                 let header =
-                    ChunkDataHeader::new(compressed.len() as u32 + 1, COMPRESSION_TYPE_ZLIB);
+                    ChunkDataHeader::new(compressed.len() as u32 + 1, options.compression.as_u8());
                 let mut sector_chunk = Vec::with_capacity(sectors_needed * SECTOR_SIZE);
 
                 sector_chunk.extend_from_slice(&header.to_bytes());
@@ -383,6 +869,30 @@ pub fn write_anvil_region<P: AsRef<Path>>(
 
     file_data.extend_from_slice(&sector_data);
 
+    Ok(file_data)
+}
+
+pub fn write_anvil_region<P: AsRef<Path>>(
+    path: P,
+    region: &Region,
+    compression_level: u32,
+    counters: Option<Arc<PerformanceCounters>>,
+) -> Result<()> {
+    let options = AnvilWriteOptions::new(ChunkCompression::Zlib, compression_level);
+    write_anvil_region_with_options(path, region, &options, counters)
+}
+
+pub fn write_anvil_region_with_options<P: AsRef<Path>>(
+    path: P,
+    region: &Region,
+    options: &AnvilWriteOptions,
+    counters: Option<Arc<PerformanceCounters>>,
+) -> Result<()> {
+    let path = path.as_ref();
+    let destination_dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    let file_data = build_anvil_file(region, options, Some(destination_dir))?;
+
     io_utils::atomic_write(path, &file_data)?;
 
     io_utils::set_mtime(path, region.mtime)?;
@@ -397,57 +907,380 @@ pub fn write_anvil_region<P: AsRef<Path>>(
 }
 
 pub fn region_to_anvil_bytes(region: &Region, compression_level: u32) -> Result<Vec<u8>> {
-    let mut chunk_locations = Vec::with_capacity(CHUNKS_PER_REGION);
-    let mut sector_data = Vec::new();
-    let mut current_sector = 2;
+    let options = AnvilWriteOptions::new(ChunkCompression::Zlib, compression_level);
+    region_to_anvil_bytes_with_options(region, &options)
+}
 
-    for i in 0..CHUNKS_PER_REGION {
-        if let Some(chunk) = region.get_chunk(i) {
-            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(compression_level));
-            encoder
-                .write_all(chunk.as_slice())
-                .context("Failed to write chunk data to compressor")?;
-            let compressed = encoder.finish().context("Failed to compress chunk data")?;
+/// In-memory variant of [`write_anvil_region_with_options`]. Chunks too large
+/// for an `.mca` sector run are rejected, since there is no directory for `.mcc` files.
+pub fn region_to_anvil_bytes_with_options(
+    region: &Region,
+    options: &AnvilWriteOptions,
+) -> Result<Vec<u8>> {
+    build_anvil_file(region, options, None)
+}
 
-            let data_size = ChunkDataHeader::SIZE + compressed.len();
-            let sectors_needed = (data_size + SECTOR_SIZE - 1) / SECTOR_SIZE;
+/// Streaming variant of [`region_to_anvil_bytes_with_options`].
+pub fn write_anvil_region_to_writer<W: Write>(
+    mut writer: W,
+    region: &Region,
+    options: &AnvilWriteOptions,
+) -> Result<()> {
+    let file_data = build_anvil_file(region, options, None)?;
+    writer.write_all(&file_data)?;
+    writer.flush()?;
+    Ok(())
+}
 
-            if sectors_needed > 255 {
-                return Err(RegionError::InvalidFormat.into());
+/// Tracks which 4 KiB sectors of an Anvil file are in use.
+struct SectorBitmap {
+    bits: Vec<u64>,
+}
+
+impl SectorBitmap {
+    fn new() -> Self {
+        Self { bits: Vec::new() }
+    }
+
+    fn is_used(&self, sector: usize) -> bool {
+        self.bits
+            .get(sector / 64)
+            .is_some_and(|word| word & (1 << (sector % 64)) != 0)
+    }
+
+    fn set_range(&mut self, start: usize, count: usize, used: bool) {
+        let needed_words = (start + count).div_ceil(64);
+        if self.bits.len() < needed_words {
+            self.bits.resize(needed_words, 0);
+        }
+        for sector in start..start + count {
+            if used {
+                self.bits[sector / 64] |= 1 << (sector % 64);
+            } else {
+                self.bits[sector / 64] &= !(1 << (sector % 64));
             }
+        }
+    }
+
+    /// First-fit search for `count` consecutive free sectors.
+    fn allocate(&mut self, count: usize) -> usize {
+        let mut start = 2;
+        while let Some(used) = (start..start + count).find(|&sector| self.is_used(sector)) {
+            start = used + 1;
+        }
+        self.set_range(start, count, true);
+        start
+    }
+
+    /// One past the last used sector.
+    fn end(&self) -> usize {
+        for (word_index, word) in self.bits.iter().enumerate().rev() {
+            if *word != 0 {
+                return word_index * 64 + 64 - word.leading_zeros() as usize;
+            }
+        }
+        0
+    }
+}
+
+/// Handle for editing single chunks of an existing `.mca` file in place,
+/// like vanilla's `RegionFile`.
+///
+/// New data is always written to freshly allocated sectors and synced before the
+/// location table is updated, so an interrupted write leaves the previous chunk intact.
+/// Location entries that point outside the file or overlap an earlier entry are
+/// cleared when the file is opened.
+pub struct AnvilRegionFile {
+    file: File,
+    directory: PathBuf,
+    region_x: i32,
+    region_z: i32,
+    locations: Vec<ChunkLocation>,
+    timestamps: Vec<u32>,
+    sectors: SectorBitmap,
+    options: AnvilWriteOptions,
+}
+
+impl AnvilRegionFile {
+    /// Opens `path` for in-place editing, creating an empty region if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+
+        let filename = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .context("Invalid filename")?;
+        let (region_x, region_z) = Region::parse_filename(filename)?;
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        // Vanilla leaves zero-byte and truncated files behind; pad them to a full header
+        let mut file_size = file.metadata()?.len() as usize;
+        if file_size < SECTOR_SIZE * 2 {
+            file.set_len((SECTOR_SIZE * 2) as u64)?;
+            file_size = SECTOR_SIZE * 2;
+        }
+
+        let mut header = vec![0u8; SECTOR_SIZE * 2];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+
+        let total_sectors = file_size.div_ceil(SECTOR_SIZE);
+        let mut sectors = SectorBitmap::new();
+        sectors.set_range(0, 2, true);
+
+        let mut locations = Vec::with_capacity(CHUNKS_PER_REGION);
+        let mut timestamps = Vec::with_capacity(CHUNKS_PER_REGION);
+        let mut stale = Vec::new();
+        for i in 0..CHUNKS_PER_REGION {
+            let mut location = ChunkLocation::from_bytes(&header[i * ChunkLocation::SIZE..]);
+            let offset = location.get_offset() as usize;
+            let count = location.sector_count as usize;
+
+            // Entries pointing into the header, past EOF or into sectors an
+            // earlier entry already claims are dropped
+            if !location.is_empty() {
+                if offset < 2
+                    || count == 0
+                    || offset + count > total_sectors
+                    || (offset..offset + count).any(|sector| sectors.is_used(sector))
+                {
+                    location = ChunkLocation::new(0, 0);
+                    stale.push(i);
+                } else {
+                    sectors.set_range(offset, count, true);
+                }
+            }
+            locations.push(location);
+
+            let start = SECTOR_SIZE + i * 4;
+            timestamps.push(u32::from_be_bytes([
+                header[start],
+                header[start + 1],
+                header[start + 2],
+                header[start + 3],
+            ]));
+        }
+
+        let mut region_file = Self {
+            file,
+            directory: path
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .to_path_buf(),
+            region_x,
+            region_z,
+            locations,
+            timestamps,
+            sectors,
+            options: AnvilWriteOptions::default(),
+        };
+
+        // Clear dropped entries on disk too, before their sectors can be reallocated
+        if !stale.is_empty() {
+            for index in stale {
+                region_file.write_header_entry(index, ChunkLocation::new(0, 0), 0)?;
+            }
+            region_file.file.sync_data()?;
+        }
+
+        Ok(region_file)
+    }
+
+    pub fn set_write_options(&mut self, options: AnvilWriteOptions) {
+        self.options = options;
+    }
+
+    #[inline]
+    pub fn region_x(&self) -> i32 {
+        self.region_x
+    }
+
+    #[inline]
+    pub fn region_z(&self) -> i32 {
+        self.region_z
+    }
+
+    #[inline]
+    pub fn has_chunk(&self, index: usize) -> bool {
+        self.locations
+            .get(index)
+            .is_some_and(|location| !location.is_empty())
+    }
+
+    /// The timestamp of local `index`, or `None` if the index is out of range.
+    #[inline]
+    pub fn timestamp(&self, index: usize) -> Option<u32> {
+        self.timestamps.get(index).copied()
+    }
 
-            let header = ChunkDataHeader::new(compressed.len() as u32 + 1, COMPRESSION_TYPE_ZLIB);
-            let mut sector_chunk = Vec::with_capacity(sectors_needed * SECTOR_SIZE);
+    fn check_index(index: usize) -> Result<()> {
+        if index >= CHUNKS_PER_REGION {
+            return Err(RegionError::ChunkIndexOutOfRange { index }.into());
+        }
+        Ok(())
+    }
 
+    fn chunk_coords(&self, index: usize) -> (i32, i32) {
+        (
+            self.region_x * REGION_DIMENSION as i32 + (index % REGION_DIMENSION) as i32,
+            self.region_z * REGION_DIMENSION as i32 + (index / REGION_DIMENSION) as i32,
+        )
+    }
+
+    fn external_path(&self, index: usize) -> PathBuf {
+        let (chunk_x, chunk_z) = self.chunk_coords(index);
+        self.directory
+            .join(format!("c.{}.{}.mcc", chunk_x, chunk_z))
+    }
+
+    /// Reads the chunk at local `index`, or `None` if the slot is empty or
+    /// the index is out of range.
+    pub fn read_chunk(&mut self, index: usize) -> Result<Option<Chunk>> {
+        let Some(&location) = self.locations.get(index) else {
+            return Ok(None);
+        };
+        if location.is_empty() {
+            return Ok(None);
+        }
+
+        let (chunk_x, chunk_z) = self.chunk_coords(index);
+        let mut sector_data = vec![0u8; location.sector_count as usize * SECTOR_SIZE];
+        self.file.seek(SeekFrom::Start(
+            location.get_offset() as u64 * SECTOR_SIZE as u64,
+        ))?;
+        self.file.read_exact(&mut sector_data)?;
+
+        let header = ChunkDataHeader::from_bytes(&sector_data[..ChunkDataHeader::SIZE]);
+        let nbt_data = if header.compression_type & EXTERNAL_FILE_FLAG != 0 {
+            let external_path = self.external_path(index);
+            let external_mmap = io_utils::mmap_file(&external_path)
+                .with_context(|| format!("Failed to read external file: {:?}", external_path))?;
+            decompress_chunk(
+                header.compression_type & !EXTERNAL_FILE_FLAG,
+                &external_mmap,
+                chunk_x,
+                chunk_z,
+            )?
+        } else {
+            let data_length = std::cmp::min(
+                (header.length as usize).saturating_sub(1),
+                sector_data.len() - ChunkDataHeader::SIZE,
+            );
+            let payload = &sector_data[ChunkDataHeader::SIZE..ChunkDataHeader::SIZE + data_length];
+            decompress_chunk(header.compression_type, payload, chunk_x, chunk_z)?
+        };
+
+        Ok(Some(Chunk::new(nbt_data, chunk_x, chunk_z)))
+    }
+
+    /// Compresses and stores `data` at local `index`, replacing any existing chunk.
+    pub fn write_chunk(&mut self, index: usize, data: &[u8], timestamp: u32) -> Result<()> {
+        Self::check_index(index)?;
+        let compressed = compress_chunk(data, &self.options)?;
+        let sectors_needed = (ChunkDataHeader::SIZE + compressed.len()).div_ceil(SECTOR_SIZE);
+
+        let mut sector_chunk;
+        let external = sectors_needed > 255;
+        if external {
+            io_utils::atomic_write(self.external_path(index), &compressed)?;
+
+            let header =
+                ChunkDataHeader::new(1, EXTERNAL_FILE_FLAG | self.options.compression.as_u8());
+            sector_chunk = Vec::with_capacity(SECTOR_SIZE);
+            sector_chunk.extend_from_slice(&header.to_bytes());
+        } else {
+            let header = ChunkDataHeader::new(
+                compressed.len() as u32 + 1,
+                self.options.compression.as_u8(),
+            );
+            sector_chunk = Vec::with_capacity(sectors_needed * SECTOR_SIZE);
             sector_chunk.extend_from_slice(&header.to_bytes());
             sector_chunk.extend_from_slice(&compressed);
+        }
+        let sector_count = sector_chunk.len().div_ceil(SECTOR_SIZE);
+        sector_chunk.resize(sector_count * SECTOR_SIZE, 0);
+
+        let start = self.sectors.allocate(sector_count);
+        self.file
+            .seek(SeekFrom::Start((start * SECTOR_SIZE) as u64))?;
+        self.file.write_all(&sector_chunk)?;
+        self.file.sync_data()?;
+
+        let previous = self.locations[index];
+        self.write_header_entry(
+            index,
+            ChunkLocation::new(start as u32, sector_count as u8),
+            timestamp,
+        )?;
+        self.release(previous);
+
+        if !external {
+            self.remove_external_file(index)?;
+        }
 
-            let padding = sectors_needed * SECTOR_SIZE - sector_chunk.len();
-            sector_chunk.resize(sector_chunk.len() + padding, 0);
+        Ok(())
+    }
 
-            chunk_locations.push(ChunkLocation::new(
-                current_sector as u32,
-                sectors_needed as u8,
-            ));
-            sector_data.extend_from_slice(&sector_chunk);
-            current_sector += sectors_needed;
-        } else {
-            chunk_locations.push(ChunkLocation::new(0, 0));
+    /// Removes the chunk at local `index` and frees its sectors.
+    pub fn delete_chunk(&mut self, index: usize) -> Result<()> {
+        Self::check_index(index)?;
+        let previous = self.locations[index];
+        if previous.is_empty() {
+            return Ok(());
         }
+
+        self.write_header_entry(index, ChunkLocation::new(0, 0), 0)?;
+        self.release(previous);
+        self.remove_external_file(index)
     }
 
-    let mut file_data = Vec::with_capacity(SECTOR_SIZE * 2 + sector_data.len());
+    fn write_header_entry(
+        &mut self,
+        index: usize,
+        location: ChunkLocation,
+        timestamp: u32,
+    ) -> Result<()> {
+        self.file
+            .seek(SeekFrom::Start((index * ChunkLocation::SIZE) as u64))?;
+        self.file.write_all(&location.to_bytes())?;
+        self.file
+            .seek(SeekFrom::Start((SECTOR_SIZE + index * 4) as u64))?;
+        self.file.write_all(&timestamp.to_be_bytes())?;
+
+        self.locations[index] = location;
+        self.timestamps[index] = timestamp;
+        Ok(())
+    }
 
-    for location in &chunk_locations {
-        file_data.extend_from_slice(&location.to_bytes());
+    fn release(&mut self, location: ChunkLocation) {
+        if !location.is_empty() {
+            self.sectors.set_range(
+                location.get_offset() as usize,
+                location.sector_count as usize,
+                false,
+            );
+        }
     }
-    file_data.resize(SECTOR_SIZE, 0);
 
-    for &timestamp in &region.timestamps {
-        file_data.extend_from_slice(&timestamp.to_be_bytes());
+    fn remove_external_file(&self, index: usize) -> Result<()> {
+        match std::fs::remove_file(self.external_path(index)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
     }
-    file_data.resize(SECTOR_SIZE * 2, 0);
-    file_data.extend_from_slice(&sector_data);
 
-    Ok(file_data)
+    /// Truncates trailing free sectors and syncs the file to disk.
+    pub fn flush(&mut self) -> Result<()> {
+        let end = self.sectors.end().max(2);
+        self.file.set_len((end * SECTOR_SIZE) as u64)?;
+        self.file.sync_all()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,252 @@
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use linear_region_tools::{
+    format::FormatRegistry,
+    histogram::{count_chunk_blocks, BlockHistogram},
+    selection::{ChunkSelection, Overlap},
+    world::{find_region_directories, RegionKind},
+    CHUNKS_PER_REGION,
+};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GroupBy {
+    /// One histogram for everything scanned
+    All,
+    Dimension,
+    Region,
+    /// Ranges of --y-step blocks
+    Y,
+}
+
+#[derive(Parser)]
+#[command(
+    name = "block_histogram",
+    about = "Count blocks by type.",
+    long_about = "Count the blocks in the section palettes of Anvil and Linear region files, \
+                  grouped per dimension, per region or per Y range"
+)]
+struct Args {
+    /// A region folder, or a world folder with --world
+    path: PathBuf,
+    /// How to group the counts
+    #[arg(short, long, value_enum, default_value_t = GroupBy::All)]
+    group_by: GroupBy,
+    /// Height of each Y range with `--group-by y`
+    #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(i32).range(1..))]
+    y_step: i32,
+    /// Only report these blocks, e.g. `minecraft:hopper`; may be repeated
+    #[arg(short, long)]
+    block: Vec<String>,
+    /// Only count chunks in this selection
+    #[arg(short, long)]
+    select: Option<ChunkSelection>,
+    /// Count the region folders of every dimension
+    #[arg(long)]
+    world: bool,
+    /// Write `group,block,count` rows as CSV instead of a table
+    #[arg(long)]
+    csv: bool,
+    #[arg(short, long, default_value_t = num_cpus::get())]
+    threads: usize,
+}
+
+/// A region file and, with --world, the dimension it belongs to.
+struct HistogramJob {
+    dimension: Option<String>,
+    region_x: i32,
+    region_z: i32,
+    path: PathBuf,
+}
+
+/// A histogram's label and, with `--group-by y`, the lowest Y of its range.
+type GroupKey = (String, Option<i32>);
+type Histograms = BTreeMap<GroupKey, BlockHistogram>;
+
+fn group_name((label, min_y): &GroupKey, y_step: i32) -> String {
+    match min_y {
+        Some(min_y) => format!("{} y {}..{}", label, min_y, min_y + y_step - 1),
+        None => label.clone(),
+    }
+}
+
+fn histogram_job(
+    job: &HistogramJob,
+    args: &Args,
+    registry: &FormatRegistry,
+    chunk_errors: &AtomicU64,
+) -> Result<Histograms> {
+    let Some(format) = registry.detect_file(&job.path)? else {
+        bail!("{} is not a recognised region file", job.path.display());
+    };
+    let region = format
+        .read(&job.path, None)
+        .with_context(|| format!("Failed to read {} as {}", job.path.display(), format.name()))?;
+
+    let mut histograms = Histograms::new();
+    let dimension = job.dimension.as_deref().unwrap_or("all");
+    let label = match args.group_by {
+        GroupBy::All => "all".to_string(),
+        GroupBy::Dimension | GroupBy::Y => dimension.to_string(),
+        GroupBy::Region => match &job.dimension {
+            Some(dimension) => format!("{} r.{}.{}", dimension, job.region_x, job.region_z),
+            None => format!("r.{}.{}", job.region_x, job.region_z),
+        },
+    };
+
+    for index in 0..CHUNKS_PER_REGION {
+        let Some(chunk) = region.get_chunk(index) else {
+            continue;
+        };
+        if args
+            .select
+            .as_ref()
+            .is_some_and(|select| !select.contains(chunk.x, chunk.z))
+        {
+            continue;
+        }
+        let result = chunk.parse_nbt().and_then(|nbt| {
+            count_chunk_blocks(&nbt, |y, block, count| {
+                if !args.block.is_empty() && !args.block.iter().any(|b| b == block) {
+                    return;
+                }
+                let min_y =
+                    (args.group_by == GroupBy::Y).then(|| y.div_euclid(args.y_step) * args.y_step);
+                histograms
+                    .entry((label.clone(), min_y))
+                    .or_default()
+                    .add(block, count);
+            })
+        });
+        if let Err(e) = result {
+            eprintln!(
+                "Error counting chunk ({}, {}) in {}: {:#}",
+                chunk.x,
+                chunk.z,
+                job.path.display(),
+                e
+            );
+            chunk_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    Ok(histograms)
+}
+
+fn merge_histograms(mut left: Histograms, right: Histograms) -> Histograms {
+    for (group, histogram) in right {
+        left.entry(group).or_default().merge(&histogram);
+    }
+    left
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .context("Failed to initialize thread pool")?;
+
+    let dirs: Vec<(Option<String>, PathBuf)> = if args.world {
+        find_region_directories(&args.path)?
+            .into_iter()
+            .filter(|directory| directory.kind == RegionKind::Region)
+            .map(|directory| {
+                let dir = args.path.join(&directory.relative_path);
+                (Some(directory.dimension), dir)
+            })
+            .collect()
+    } else {
+        vec![(None, args.path.clone())]
+    };
+
+    let registry = FormatRegistry::default();
+    let mut jobs = Vec::new();
+    for (dimension, dir) in dirs {
+        for ((region_x, region_z), path) in registry.region_files(&dir)? {
+            if args
+                .select
+                .as_ref()
+                .is_some_and(|select| select.region_overlap(region_x, region_z) == Overlap::None)
+            {
+                continue;
+            }
+            jobs.push(HistogramJob {
+                dimension: dimension.clone(),
+                region_x,
+                region_z,
+                path,
+            });
+        }
+    }
+    if jobs.is_empty() {
+        bail!("No region files found in {}", args.path.display());
+    }
+
+    let progress = ProgressBar::new(jobs.len() as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:.cyan}] {pos}/{len} ({percent}%) {msg}")?
+            .progress_chars("#>-"),
+    );
+
+    let file_errors = AtomicU64::new(0);
+    let chunk_errors = AtomicU64::new(0);
+    let histograms = jobs
+        .par_iter()
+        .map(|job| {
+            let histograms =
+                histogram_job(job, &args, &registry, &chunk_errors).unwrap_or_else(|e| {
+                    progress.println(format!("Error reading {}: {:#}", job.path.display(), e));
+                    file_errors.fetch_add(1, Ordering::Relaxed);
+                    Histograms::new()
+                });
+            progress.inc(1);
+            histograms
+        })
+        .reduce(Histograms::new, merge_histograms);
+    progress.finish_and_clear();
+
+    if args.csv {
+        let mut writer = csv::Writer::from_writer(io::stdout());
+        writer.write_record(["group", "block", "count"])?;
+        for (group, histogram) in &histograms {
+            let group = group_name(group, args.y_step);
+            for (block, count) in histogram.sorted() {
+                writer.write_record([group.as_str(), block, &count.to_string()])?;
+            }
+        }
+        writer.flush()?;
+    } else {
+        for (group, histogram) in &histograms {
+            println!(
+                "{} ({} blocks)",
+                group_name(group, args.y_step),
+                histogram.total()
+            );
+            for (block, count) in histogram.sorted() {
+                println!("  {:>14}  {}", count, block);
+            }
+        }
+    }
+
+    eprintln!();
+    eprintln!("Histogram Summary:");
+    eprintln!("Region files read: {}", jobs.len());
+    eprintln!(
+        "Chunks that could not be counted: {}",
+        chunk_errors.load(Ordering::Relaxed)
+    );
+    let file_errors = file_errors.load(Ordering::Relaxed);
+    eprintln!("Errors: {}", file_errors);
+
+    if file_errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
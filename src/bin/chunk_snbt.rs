@@ -0,0 +1,255 @@
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use linear_region_tools::{
+    anvil::{read_anvil_chunk_storage, AnvilRegionFile, AnvilWriteOptions, ChunkCompression},
+    format::{
+        detect_region_format, rewrite_region_file, DetectedFormat, FormatRegistry, WriteOptions,
+    },
+    nbt,
+    snbt::{from_snbt, to_snbt},
+    Chunk, Region, REGION_DIMENSION,
+};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Parser)]
+#[command(
+    name = "chunk_snbt",
+    about = "Export a chunk as SNBT and import it back.",
+    long_about = "Export one chunk of an Anvil or Linear region file as SNBT text for inspection \
+                  or hand editing, and import the edited text back into the same file"
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write a chunk's NBT as SNBT
+    Export {
+        /// Region file holding the chunk
+        region: PathBuf,
+        /// Chunk coordinates as X,Z
+        #[arg(value_parser = parse_chunk, allow_hyphen_values = true)]
+        chunk: (i32, i32),
+        /// File to write instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Replace a chunk with NBT read from SNBT, keeping its timestamp
+    Import {
+        /// Region file holding the chunk
+        region: PathBuf,
+        /// Chunk coordinates as X,Z
+        #[arg(value_parser = parse_chunk, allow_hyphen_values = true)]
+        chunk: (i32, i32),
+        /// SNBT file, or `-` for standard input
+        input: PathBuf,
+        /// Copy the region file to `<file>.bak` before writing
+        #[arg(short, long)]
+        backup: bool,
+        #[arg(short, long, default_value_t = 6)]
+        compression_level: i32,
+        /// Check the SNBT and show what would change without writing anything
+        #[arg(short, long)]
+        dry_run: bool,
+    },
+}
+
+fn parse_chunk(s: &str) -> Result<(i32, i32), String> {
+    let (x, z) = s
+        .split_once(',')
+        .ok_or_else(|| format!("expected X,Z, found `{}`", s))?;
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<i32>()
+            .map_err(|e| format!("invalid coordinate `{}`: {}", value.trim(), e))
+    };
+    Ok((parse(x)?, parse(z)?))
+}
+
+fn read_region(registry: &FormatRegistry, path: &Path) -> Result<Region> {
+    let Some(format) = registry.detect_file(path)? else {
+        bail!("{} is not a recognised region file", path.display());
+    };
+    format
+        .read(path, None)
+        .with_context(|| format!("Failed to read {} as {}", path.display(), format.name()))
+}
+
+/// The slot of chunk (`x`, `z`) in `region`, if the chunk belongs to it.
+fn chunk_index(region: &Region, path: &Path, (x, z): (i32, i32)) -> Result<usize> {
+    if x >> 5 != region.region_x || z >> 5 != region.region_z {
+        bail!(
+            "Chunk ({}, {}) is not in {}, which holds region ({}, {})",
+            x,
+            z,
+            path.display(),
+            region.region_x,
+            region.region_z
+        );
+    }
+    Ok((z & 31) as usize * REGION_DIMENSION + (x & 31) as usize)
+}
+
+/// The chunk coordinates recorded in the NBT: `xPos`/`zPos` of region
+/// chunks or `Position` of entity chunks.
+fn recorded_position(value: &nbt::Value) -> Option<(i32, i32)> {
+    if let Some(nbt::Value::IntArray(position)) = nbt::chunk_tag(value, "Position")
+        && let [x, z] = position[..]
+    {
+        return Some((x, z));
+    }
+    match (
+        nbt::chunk_tag(value, "xPos")?,
+        nbt::chunk_tag(value, "zPos")?,
+    ) {
+        (nbt::Value::Int(x), nbt::Value::Int(z)) => Some((*x, *z)),
+        _ => None,
+    }
+}
+
+fn export(region_path: &Path, chunk: (i32, i32), output: Option<&Path>) -> Result<()> {
+    let registry = FormatRegistry::default();
+    let region = read_region(&registry, region_path)?;
+    let index = chunk_index(&region, region_path, chunk)?;
+    let Some(stored) = region.get_chunk(index) else {
+        bail!(
+            "No chunk at ({}, {}) in {}",
+            chunk.0,
+            chunk.1,
+            region_path.display()
+        );
+    };
+
+    let mut text = to_snbt(&stored.parse_nbt()?);
+    text.push('\n');
+    match output {
+        Some(path) => std::fs::write(path, text)
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        None => io::stdout().write_all(text.as_bytes())?,
+    }
+    Ok(())
+}
+
+fn import(
+    region_path: &Path,
+    chunk: (i32, i32),
+    input: &Path,
+    backup: bool,
+    compression_level: i32,
+    dry_run: bool,
+) -> Result<()> {
+    let text = if input == Path::new("-") {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        std::fs::read_to_string(input)
+            .with_context(|| format!("Failed to read {}", input.display()))?
+    };
+    let value = from_snbt(&text).with_context(|| format!("Invalid SNBT in {}", input.display()))?;
+    if !matches!(value, nbt::Value::Compound(_)) {
+        bail!("The SNBT must be a compound");
+    }
+    if let Some(position) = recorded_position(&value)
+        && position != chunk
+    {
+        bail!(
+            "The SNBT is for chunk ({}, {}), not ({}, {}); use relocate_chunks to move chunks",
+            position.0,
+            position.1,
+            chunk.0,
+            chunk.1
+        );
+    }
+
+    let registry = FormatRegistry::default();
+    let Some(format) = registry.detect_file(region_path)? else {
+        bail!("{} is not a recognised region file", region_path.display());
+    };
+    let mut region = read_region(&registry, region_path)?;
+    let index = chunk_index(&region, region_path, chunk)?;
+    let new_chunk = Chunk::from_nbt(&value, chunk.0, chunk.1)?;
+
+    let old_chunk = region.get_chunk(index);
+    if old_chunk.and_then(|old| old.parse_nbt().ok()).as_ref() == Some(&value) {
+        println!("Chunk ({}, {}) is unchanged", chunk.0, chunk.1);
+        return Ok(());
+    }
+    match old_chunk {
+        Some(old) => println!(
+            "Chunk ({}, {}): {} -> {} bytes of NBT",
+            chunk.0,
+            chunk.1,
+            old.size(),
+            new_chunk.size()
+        ),
+        None => println!(
+            "Chunk ({}, {}): new, {} bytes of NBT",
+            chunk.0,
+            chunk.1,
+            new_chunk.size()
+        ),
+    }
+    let timestamp = match old_chunk {
+        Some(_) => region.timestamps[index],
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as u32,
+    };
+    if dry_run {
+        return Ok(());
+    }
+
+    if backup {
+        let backup_path = PathBuf::from(format!("{}.bak", region_path.display()));
+        std::fs::copy(region_path, &backup_path)
+            .with_context(|| format!("Failed to back up to {}", backup_path.display()))?;
+    }
+    if matches!(
+        detect_region_format(region_path)?,
+        DetectedFormat::Anvil { .. }
+    ) {
+        // Only the edited slot changes, in the compression the chunk had
+        let compression = read_anvil_chunk_storage(region_path)?[index]
+            .and_then(|storage| ChunkCompression::from_u8(storage.compression_type))
+            .unwrap_or(ChunkCompression::Zlib);
+        let mut file = AnvilRegionFile::open(region_path)?;
+        file.set_write_options(AnvilWriteOptions::new(
+            compression,
+            compression_level.clamp(0, 9) as u32,
+        ));
+        file.write_chunk(index, new_chunk.as_slice(), timestamp)?;
+        file.flush()?;
+    } else {
+        let options = WriteOptions {
+            compression_level,
+            ..WriteOptions::default()
+        };
+        region.set_chunk(index, new_chunk, timestamp);
+        rewrite_region_file(region_path, &region, format, &[], &options)?;
+    }
+    println!("Wrote {}", region_path.display());
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    match args.command {
+        Command::Export {
+            region,
+            chunk,
+            output,
+        } => export(&region, chunk, output.as_deref()),
+        Command::Import {
+            region,
+            chunk,
+            input,
+            backup,
+            compression_level,
+            dry_run,
+        } => import(&region, chunk, &input, backup, compression_level, dry_run),
+    }
+}
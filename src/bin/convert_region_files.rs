@@ -1,23 +1,41 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::{Parser, ValueEnum};
 use indicatif::{ProgressBar, ProgressStyle};
 use linear_region_tools::{
-    anvil::{read_anvil_region, write_anvil_region},
-    linear::{read_linear_region, write_linear_region, LinearVersion},
+    anvil::ChunkCompression,
+    format::{FormatRegistry, RegionFormat, WriteOptions},
+    io_utils,
+    journal::Journal,
+    selection::{ChunkSelection, Overlap},
+    verify::compare_regions,
+    world::find_region_directories,
+    ReadMode, Region, LINEAR_V2_DEFAULT_GRID_SIZE, REGION_DIMENSION,
 };
 use rayon::prelude::*;
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 
-#[derive(Debug, Clone, ValueEnum)]
-enum ConversionMode {
-    Mca2linearv1,
-    Linearv12mca,
-    Mca2linearv2,
-    Linearv2mca,
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum AnvilCompression {
+    Zlib,
+    Gzip,
+    None,
+    Lz4,
+}
+
+impl From<AnvilCompression> for ChunkCompression {
+    fn from(compression: AnvilCompression) -> Self {
+        match compression {
+            AnvilCompression::Zlib => ChunkCompression::Zlib,
+            AnvilCompression::Gzip => ChunkCompression::Gzip,
+            AnvilCompression::None => ChunkCompression::None,
+            AnvilCompression::Lz4 => ChunkCompression::Lz4,
+        }
+    }
 }
 
 #[derive(Parser)]
@@ -27,31 +45,89 @@ enum ConversionMode {
     long_about = "Convert region files between Anvil (.mca) and Linear (.linear) formats"
 )]
 struct Args {
-    conversion_mode: ConversionMode,
+    /// `<from>2<to>` using the format names mca, linearv1 and linearv2,
+    /// e.g. mca2linearv2 or linearv12linearv2
+    conversion_mode: String,
     source_dir: PathBuf,
-    destination_dir: PathBuf,
+    #[arg(required_unless_present = "in_place")]
+    destination_dir: Option<PathBuf>,
     #[arg(short, long, default_value_t = num_cpus::get())]
     threads: usize,
     #[arg(short, long, default_value_t = 6)]
     compression_level: i32,
     #[arg(short, long)]
     log: bool,
-    #[arg(long)]
+    #[arg(long, conflicts_with = "in_place")]
     skip_existing: bool,
+    /// Re-read every written file and compare it chunk by chunk with the source
     #[arg(long)]
     verify: bool,
+    /// With --verify, also check that every written chunk's NBT parses
+    #[arg(long, requires = "verify")]
+    verify_nbt: bool,
+    /// LinearV2 bucket grid size (1, 2, 4, 8, 16 or 32)
+    #[arg(long, default_value_t = LINEAR_V2_DEFAULT_GRID_SIZE)]
+    grid_size: u8,
+    /// Per-chunk compression when writing Anvil files
+    #[arg(long, value_enum, default_value_t = AnvilCompression::Zlib)]
+    anvil_compression: AnvilCompression,
+    /// Skip damaged MCA chunks instead of failing the whole file. Not allowed
+    /// with --in-place, which would delete the skipped chunks with the source
+    #[arg(long, conflicts_with = "in_place")]
+    lenient: bool,
+    /// Treat the source and destination as world folders and convert the
+    /// region, entities and poi folders of every dimension
+    #[arg(long)]
+    world: bool,
+    /// Skip files the journal records as converted whose source and output
+    /// are unchanged, and redo everything else
+    #[arg(long)]
+    resume: bool,
+    /// Conversion journal [default: <DESTINATION_DIR>/.conversion_journal]
+    #[arg(long)]
+    journal: Option<PathBuf>,
+    /// Write each converted file next to its source, verify it and only then
+    /// remove the source
+    #[arg(long, conflicts_with = "destination_dir")]
+    in_place: bool,
+    /// With --in-place, move sources here instead of deleting them
+    #[arg(long, requires = "in_place")]
+    archive_dir: Option<PathBuf>,
+    /// Only convert chunks in this selection, e.g. `regions(0, 0, -1, 0)`;
+    /// region files without selected chunks are skipped
+    #[arg(long, conflicts_with = "in_place")]
+    select: Option<ChunkSelection>,
+    /// Only convert chunks in the selections listed in this file
+    #[arg(long, conflicts_with_all = ["in_place", "select"])]
+    select_file: Option<PathBuf>,
+}
+
+impl Args {
+    fn destination_dir(&self) -> &Path {
+        self.destination_dir.as_deref().unwrap_or(&self.source_dir)
+    }
+
+    fn verify(&self) -> bool {
+        self.verify || self.in_place
+    }
 }
 
 struct ConversionStats {
     converted: AtomicU64,
+    skipped: AtomicU64,
     errors: AtomicU64,
+    mismatched_files: AtomicU64,
+    replaced: AtomicU64,
 }
 
 impl ConversionStats {
     fn new() -> Self {
         Self {
             converted: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
             errors: AtomicU64::new(0),
+            mismatched_files: AtomicU64::new(0),
+            replaced: AtomicU64::new(0),
         }
     }
 
@@ -59,72 +135,197 @@ impl ConversionStats {
         self.converted.fetch_add(count, Ordering::Relaxed);
     }
 
+    fn add_skipped(&self, count: u64) {
+        self.skipped.fetch_add(count, Ordering::Relaxed);
+    }
+
     fn add_errors(&self, count: u64) {
         self.errors.fetch_add(count, Ordering::Relaxed);
     }
-}
 
-fn get_output_filename(mode: &ConversionMode, source_filename: &str) -> String {
-    match mode {
-        ConversionMode::Mca2linearv1 => source_filename.replace(".mca", ".linear"),
-        ConversionMode::Linearv12mca => source_filename.replace(".linear", ".mca"),
-        ConversionMode::Mca2linearv2 => source_filename.replace(".mca", ".linear"),
-        ConversionMode::Linearv2mca => source_filename.replace(".linear", ".mca"),
+    fn add_mismatched_files(&self, count: u64) {
+        self.mismatched_files.fetch_add(count, Ordering::Relaxed);
     }
-}
 
-fn is_valid_source_file(mode: &ConversionMode, filename: &str) -> bool {
-    match mode {
-        ConversionMode::Mca2linearv1 | ConversionMode::Mca2linearv2 => filename.ends_with(".mca"),
-        ConversionMode::Linearv12mca | ConversionMode::Linearv2mca => filename.ends_with(".linear"),
+    fn add_replaced(&self, count: u64) {
+        self.replaced.fetch_add(count, Ordering::Relaxed);
     }
 }
 
-fn get_linear_version(mode: &ConversionMode) -> LinearVersion {
-    match mode {
-        ConversionMode::Mca2linearv1 | ConversionMode::Linearv12mca => LinearVersion::V1,
-        ConversionMode::Mca2linearv2 | ConversionMode::Linearv2mca => LinearVersion::V2,
+/// Source and destination formats of a conversion.
+struct Conversion<'a> {
+    registry: &'a FormatRegistry,
+    from: &'a dyn RegionFormat,
+    to: &'a dyn RegionFormat,
+}
+
+fn get_output_filename(conversion: &Conversion, source_path: &Path) -> PathBuf {
+    PathBuf::from(source_path.file_name().unwrap_or_default())
+        .with_extension(conversion.to.extension())
+}
+
+fn is_valid_source_file(conversion: &Conversion, filename: &str) -> bool {
+    filename.ends_with(&format!(".{}", conversion.from.extension()))
+}
+
+/// Pairs every convertible file in `source_dir` with its path in `dest_dir`.
+fn collect_jobs(
+    conversion: &Conversion,
+    source_dir: &Path,
+    dest_dir: &Path,
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let jobs = fs::read_dir(source_dir)?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let filename = e.file_name().to_string_lossy().to_string();
+            if !is_valid_source_file(conversion, &filename) {
+                return None;
+            }
+            let dest_filename = get_output_filename(conversion, &e.path());
+            Some((e.path(), dest_dir.join(dest_filename)))
+        })
+        .collect();
+    Ok(jobs)
+}
+
+/// Picks the reader for `source_path` from its contents.
+fn source_format<'a>(
+    source_path: &Path,
+    conversion: &Conversion<'a>,
+) -> Result<&'a dyn RegionFormat> {
+    match conversion.registry.detect_file(source_path)? {
+        Some(format) => Ok(format),
+        None => bail!("{} is not a recognised region file", source_path.display()),
     }
 }
 
 fn convert_file(
-    mode: &ConversionMode,
     source_path: &Path,
     dest_path: &Path,
-    compression_level: i32,
-    skip_existing: bool,
-    verify: bool,
-) -> Result<()> {
-    if skip_existing && dest_path.exists() {
-        return Ok(());
+    args: &Args,
+    conversion: &Conversion,
+    write_options: &WriteOptions,
+    selection: Option<&ChunkSelection>,
+    progress: &ProgressBar,
+) -> Result<usize> {
+    if args.skip_existing && dest_path.exists() {
+        return Ok(0);
     }
 
-    let linear_version = get_linear_version(mode);
+    let from = source_format(source_path, conversion)?;
+    if from.name() != conversion.from.name() {
+        progress.println(format!(
+            "{} is {}, not {}; reading it as {}",
+            source_path.display(),
+            from.name(),
+            conversion.from.name(),
+            from.name()
+        ));
+    }
 
-    match mode {
-        ConversionMode::Mca2linearv1 | ConversionMode::Mca2linearv2 => {
-            let region = read_anvil_region(source_path, None)?;
-            if verify {
-                for i in 0..1024 {
-                    let _ = region.get_chunk(i);
-                }
+    let mut region = if args.lenient {
+        let (region, report) = from.read_with_report(source_path, ReadMode::Lenient, None)?;
+        for issue in &report.issues {
+            let action = if issue.reason.is_recovered() {
+                "Recovered"
+            } else {
+                "Skipped"
+            };
+            progress.println(format!(
+                "{} chunk ({}, {}) in {}: {}",
+                action,
+                issue.x,
+                issue.z,
+                source_path.display(),
+                issue.reason
+            ));
+        }
+        region
+    } else {
+        from.read(source_path, None)?
+    };
+    if let Some(selection) = selection {
+        region.retain_chunks(|chunk| selection.contains(chunk.x, chunk.z));
+    }
+    conversion
+        .to
+        .write(dest_path, &region, write_options, None)?;
+
+    if !args.verify() {
+        return Ok(0);
+    }
+
+    let written = conversion.to.read(dest_path, None)?;
+    let mismatches = compare_regions(&region, &written, args.verify_nbt);
+    for mismatch in &mismatches {
+        progress.println(format!(
+            "Verification failed for chunk ({}, {}) in {}: {}",
+            mismatch.x,
+            mismatch.z,
+            dest_path.display(),
+            mismatch.kind
+        ));
+    }
+
+    Ok(mismatches.len())
+}
+
+/// The `c.<x>.<z>.mcc` files holding oversized chunks of the Anvil region at
+/// `source_path`.
+fn external_chunk_files(source_path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(filename)) = (source_path.parent(), source_path.file_name()) else {
+        return Vec::new();
+    };
+    let Ok((region_x, region_z)) = Region::parse_filename(&filename.to_string_lossy()) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    for local_z in 0..REGION_DIMENSION as i32 {
+        for local_x in 0..REGION_DIMENSION as i32 {
+            let chunk_x = region_x * REGION_DIMENSION as i32 + local_x;
+            let chunk_z = region_z * REGION_DIMENSION as i32 + local_z;
+            let path = dir.join(format!("c.{}.{}.mcc", chunk_x, chunk_z));
+            if path.is_file() {
+                files.push(path);
             }
-            write_linear_region(dest_path, &region, compression_level, linear_version, None)?;
         }
-        ConversionMode::Linearv12mca | ConversionMode::Linearv2mca => {
-            let region = read_linear_region(source_path, None)?;
-            if verify {
-                for i in 0..1024 {
-                    let _ = region.get_chunk(i);
+    }
+    files
+}
+
+/// Deletes a source that has been converted in place, or moves it to
+/// `--archive-dir` under its path relative to the source directory. Anvil
+/// sources take their external chunk files with them.
+fn retire_source(source_path: &Path, args: &Args) -> Result<()> {
+    let mut files = vec![source_path.to_path_buf()];
+    if source_path.extension().is_some_and(|ext| ext == "mca") {
+        files.extend(external_chunk_files(source_path));
+    }
+
+    for file in files {
+        match &args.archive_dir {
+            Some(archive_dir) => {
+                let relative = file.strip_prefix(&args.source_dir).unwrap_or(&file);
+                let archived = archive_dir.join(relative);
+                if let Some(parent) = archived.parent() {
+                    fs::create_dir_all(parent)?;
                 }
+                io_utils::move_file(&file, &archived)?;
             }
-            write_anvil_region(dest_path, &region, compression_level as u32, None)?;
+            None => fs::remove_file(&file)?,
         }
     }
-
     Ok(())
 }
 
+/// Removes an in-place output that failed, so the folder keeps only the source.
+fn discard_output(dest_path: &Path, args: &Args) {
+    if args.in_place {
+        let _ = fs::remove_file(dest_path);
+    }
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
 
@@ -136,21 +337,110 @@ fn main() -> Result<()> {
         std::process::exit(1);
     }
 
-    fs::create_dir_all(&args.destination_dir)?;
+    let registry = FormatRegistry::default();
+    let Some((from, to)) = registry.parse_conversion(&args.conversion_mode) else {
+        let names: Vec<_> = registry.formats().map(|f| f.name()).collect();
+        eprintln!(
+            "Unknown conversion mode: {} (expected <from>2<to> with formats {})",
+            args.conversion_mode,
+            names.join(", ")
+        );
+        std::process::exit(1);
+    };
+    let conversion = Conversion {
+        registry: &registry,
+        from,
+        to,
+    };
 
-    let source_files: Vec<_> = fs::read_dir(&args.source_dir)?
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            let filename = e.file_name().to_string_lossy().to_string();
-            is_valid_source_file(&args.conversion_mode, &filename)
-        })
-        .collect();
+    if args.in_place && from.extension() == to.extension() {
+        eprintln!(
+            "--in-place needs formats with different file extensions, but {} and {} both use .{}",
+            from.name(),
+            to.name(),
+            to.extension()
+        );
+        std::process::exit(1);
+    }
+
+    let destination_dir = args.destination_dir();
+    fs::create_dir_all(destination_dir)?;
+
+    let selection = match &args.select_file {
+        Some(select_file) => Some(ChunkSelection::from_file(select_file)?),
+        None => args.select.clone(),
+    };
+
+    let mut source_files = if args.world {
+        let directories = find_region_directories(&args.source_dir)?;
+        println!(
+            "Found {} region directories in {}",
+            directories.len(),
+            args.source_dir.display()
+        );
+
+        let mut jobs = Vec::new();
+        for directory in &directories {
+            let dest_dir = destination_dir.join(&directory.relative_path);
+            fs::create_dir_all(&dest_dir)?;
+            jobs.extend(collect_jobs(
+                &conversion,
+                &args.source_dir.join(&directory.relative_path),
+                &dest_dir,
+            )?);
+        }
+        jobs
+    } else {
+        collect_jobs(&conversion, &args.source_dir, destination_dir)?
+    };
+
+    if let Some(selection) = &selection {
+        source_files.retain(|(source_path, _)| {
+            let filename = source_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            Region::parse_filename(&filename).map_or(true, |(x, z)| {
+                selection.region_overlap(x, z) != Overlap::None
+            })
+        });
+    }
 
     if source_files.is_empty() {
         eprintln!("No source files found");
         std::process::exit(1);
     }
 
+    // Only a resumed run can be sure the temp files are its own leftovers
+    if args.resume {
+        let dest_dirs: BTreeSet<_> = source_files
+            .iter()
+            .filter_map(|(_, dest_path)| dest_path.parent())
+            .collect();
+        let mut stray_temp_files = 0;
+        for dir in dest_dirs {
+            stray_temp_files += io_utils::remove_temp_files(dir)?;
+        }
+        if stray_temp_files > 0 {
+            println!(
+                "Removed {} temporary files left by an earlier run",
+                stray_temp_files
+            );
+        }
+    }
+
+    let journal_path = args
+        .journal
+        .clone()
+        .unwrap_or_else(|| destination_dir.join(".conversion_journal"));
+    let journal = Journal::open(&journal_path, args.resume)?;
+
+    let write_options = WriteOptions {
+        compression_level: args.compression_level,
+        anvil_compression: args.anvil_compression.into(),
+        grid_size: args.grid_size,
+    };
+
     let stats = Arc::new(ConversionStats::new());
     let start = Instant::now();
     let progress = ProgressBar::new(source_files.len() as u64);
@@ -160,40 +450,105 @@ fn main() -> Result<()> {
             .progress_chars("#>-"),
     );
 
-    source_files.par_iter().for_each(|source_entry| {
-        let source_filename = source_entry.file_name().to_string_lossy().to_string();
-        let dest_filename = get_output_filename(&args.conversion_mode, &source_filename);
-        let source_path = source_entry.path();
-        let dest_path = args.destination_dir.join(&dest_filename);
-
-        progress.inc(1);
-
-        match convert_file(
-            &args.conversion_mode,
-            &source_path,
-            &dest_path,
-            args.compression_level,
-            args.skip_existing,
-            args.verify,
-        ) {
-            Ok(_) => stats.add_converted(1),
-            Err(e) => {
-                progress.println(format!("Error converting {}: {:#}", source_filename, e));
-                stats.add_errors(1);
+    source_files
+        .par_iter()
+        .for_each(|(source_path, dest_path)| {
+            progress.inc(1);
+
+            let retire = |stats: &ConversionStats| {
+                if !args.in_place {
+                    return;
+                }
+                match retire_source(source_path, &args) {
+                    Ok(()) => stats.add_replaced(1),
+                    Err(e) => {
+                        progress.println(format!(
+                            "Error removing {} after conversion: {:#}",
+                            source_path.display(),
+                            e
+                        ));
+                        stats.add_errors(1);
+                    }
+                }
+            };
+
+            if args.resume && journal.is_complete(source_path, dest_path) {
+                stats.add_skipped(1);
+                // An earlier run may have stopped between verifying and retiring
+                retire(&stats);
+                return;
             }
-        }
-    });
+
+            let journal_result = journal.mark_in_progress(source_path).and_then(|_| {
+                match convert_file(
+                    source_path,
+                    dest_path,
+                    &args,
+                    &conversion,
+                    &write_options,
+                    selection.as_ref(),
+                    &progress,
+                ) {
+                    Ok(mismatches) => {
+                        stats.add_converted(1);
+                        if mismatches > 0 {
+                            stats.add_mismatched_files(1);
+                            discard_output(dest_path, &args);
+                            journal.mark_failed(source_path)
+                        } else {
+                            journal.mark_completed(source_path, dest_path)?;
+                            retire(&stats);
+                            Ok(())
+                        }
+                    }
+                    Err(e) => {
+                        progress.println(format!(
+                            "Error converting {}: {:#}",
+                            source_path.display(),
+                            e
+                        ));
+                        stats.add_errors(1);
+                        discard_output(dest_path, &args);
+                        journal.mark_failed(source_path)
+                    }
+                }
+            });
+            if let Err(e) = journal_result {
+                progress.println(format!(
+                    "Error updating journal for {}: {:#}",
+                    source_path.display(),
+                    e
+                ));
+            }
+        });
 
     progress.finish();
 
     let duration = start.elapsed();
     let converted = stats.converted.load(Ordering::Relaxed);
+    let skipped = stats.skipped.load(Ordering::Relaxed);
     let errors = stats.errors.load(Ordering::Relaxed);
+    let mismatched_files = stats.mismatched_files.load(Ordering::Relaxed);
+    let replaced = stats.replaced.load(Ordering::Relaxed);
 
     println!();
     println!("Conversion Summary:");
     println!("Files converted: {}", converted);
+    if args.resume {
+        println!("Files already converted: {}", skipped);
+    }
     println!("Errors: {}", errors);
+    if args.in_place {
+        let action = if args.archive_dir.is_some() {
+            "archived"
+        } else {
+            "removed"
+        };
+        println!("Source files {}: {}", action, replaced);
+    }
+    if args.verify() {
+        println!("Files failing verification: {}", mismatched_files);
+    }
     println!("Total time: {:?}", duration);
 
     if duration.as_secs() > 0 {
@@ -203,5 +558,12 @@ fn main() -> Result<()> {
         );
     }
 
+    if mismatched_files > 0 {
+        std::process::exit(2);
+    }
+    if errors > 0 {
+        std::process::exit(1);
+    }
+
     Ok(())
 }
@@ -0,0 +1,169 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use linear_region_tools::{
+    format::{rewrite_region_file, FormatRegistry, WriteOptions},
+    selection::{ChunkSelection, Overlap},
+    world::find_region_directories,
+};
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Parser)]
+#[command(
+    name = "delete_chunks",
+    about = "Delete a selection of chunks from region files.",
+    long_about = "Remove the chunks in a chunk selection from Anvil and Linear region files, \
+                  deleting region files that end up empty"
+)]
+struct Args {
+    /// A region folder, or a world folder with --world
+    path: PathBuf,
+    /// Chunks to delete, e.g. `circle(0, 0, 512) & !regions(0, 0)`
+    #[arg(short, long, required_unless_present = "select_file")]
+    select: Option<ChunkSelection>,
+    /// Delete the chunks in the selections listed in this file, one per line
+    #[arg(long, conflicts_with = "select")]
+    select_file: Option<PathBuf>,
+    /// Delete from the region, entities and poi folders of every dimension
+    #[arg(long)]
+    world: bool,
+    #[arg(short, long, default_value_t = num_cpus::get())]
+    threads: usize,
+    #[arg(short, long, default_value_t = 6)]
+    compression_level: i32,
+    /// Report what would be deleted without writing anything
+    #[arg(short, long)]
+    dry_run: bool,
+}
+
+#[derive(Default)]
+struct DeleteOutcome {
+    chunks_deleted: usize,
+    bytes_freed: u64,
+    file_deleted: bool,
+}
+
+fn delete_from_file(
+    path: &Path,
+    registry: &FormatRegistry,
+    selection: &ChunkSelection,
+    write_options: &WriteOptions,
+    dry_run: bool,
+) -> Result<DeleteOutcome> {
+    let Some(format) = registry.detect_file(path)? else {
+        bail!("{} is not a recognised region file", path.display());
+    };
+    let mut region = format.read(path, None)?;
+    let removed = region.retain_chunks(|chunk| !selection.contains(chunk.x, chunk.z));
+    let mut outcome = DeleteOutcome {
+        chunks_deleted: removed.len(),
+        ..Default::default()
+    };
+    if dry_run || removed.is_empty() {
+        return Ok(outcome);
+    }
+
+    outcome.bytes_freed = rewrite_region_file(path, &region, format, &removed, write_options)?;
+    outcome.file_deleted = region.chunk_count() == 0;
+    Ok(outcome)
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .context("Failed to initialize thread pool")?;
+
+    let selection = match (&args.select_file, &args.select) {
+        (Some(select_file), _) => ChunkSelection::from_file(select_file)?,
+        (None, Some(select)) => select.clone(),
+        (None, None) => bail!("No chunk selection given"),
+    };
+
+    let dirs = if args.world {
+        find_region_directories(&args.path)?
+            .into_iter()
+            .map(|directory| args.path.join(directory.relative_path))
+            .collect()
+    } else {
+        vec![args.path.clone()]
+    };
+
+    let registry = FormatRegistry::default();
+    let mut files = Vec::new();
+    for dir in &dirs {
+        for ((x, z), path) in registry.region_files(dir)? {
+            if selection.region_overlap(x, z) != Overlap::None {
+                files.push(path);
+            }
+        }
+    }
+    if files.is_empty() {
+        println!("No region files overlap the selection");
+        return Ok(());
+    }
+
+    if args.dry_run {
+        println!("DRY RUN MODE - No files will be modified");
+    }
+
+    let write_options = WriteOptions {
+        compression_level: args.compression_level,
+        ..Default::default()
+    };
+    let chunks_deleted = AtomicU64::new(0);
+    let files_deleted = AtomicU64::new(0);
+    let bytes_freed = AtomicU64::new(0);
+    let errors = AtomicU64::new(0);
+
+    let progress = ProgressBar::new(files.len() as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:.cyan}] {pos}/{len} ({percent}%) {msg}")?
+            .progress_chars("#>-"),
+    );
+
+    files.par_iter().for_each(|path| {
+        match delete_from_file(path, &registry, &selection, &write_options, args.dry_run) {
+            Ok(outcome) => {
+                chunks_deleted.fetch_add(outcome.chunks_deleted as u64, Ordering::Relaxed);
+                bytes_freed.fetch_add(outcome.bytes_freed, Ordering::Relaxed);
+                if outcome.file_deleted {
+                    files_deleted.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            Err(e) => {
+                progress.println(format!("Error processing {}: {:#}", path.display(), e));
+                errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        progress.inc(1);
+    });
+    progress.finish();
+
+    println!();
+    println!("Delete Summary:");
+    println!("Region files processed: {}", files.len());
+    println!("Chunks deleted: {}", chunks_deleted.load(Ordering::Relaxed));
+    println!(
+        "Region files deleted: {}",
+        files_deleted.load(Ordering::Relaxed)
+    );
+    if !args.dry_run {
+        println!(
+            "Disk space saved: {:.1} MiB",
+            bytes_freed.load(Ordering::Relaxed) as f64 / (1024.0 * 1024.0)
+        );
+    }
+    let errors = errors.load(Ordering::Relaxed);
+    println!("Errors: {}", errors);
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use linear_region_tools::{
+    diff::{diff_regions, ChunkDiff, ChunkDiffKind},
+    format::FormatRegistry,
+    world::find_region_directories,
+    Region,
+};
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(
+    name = "diff_region_files",
+    about = "List the chunks that differ between two regions, region folders or worlds.",
+    long_about = "Compare two region files of any format chunk by chunk and list added, removed \
+                  and changed chunks. Exits with status 1 when there are differences"
+)]
+struct Args {
+    old: PathBuf,
+    new: PathBuf,
+    /// Compare chunks as NBT and list the tags that changed
+    #[arg(long)]
+    nbt: bool,
+    /// With --nbt, how many levels of compounds to descend into before
+    /// reporting a tag as changed
+    #[arg(long, default_value_t = 3, requires = "nbt")]
+    nbt_depth: usize,
+    /// Treat the inputs as world folders and compare the region, entities and
+    /// poi folders of every dimension
+    #[arg(long)]
+    world: bool,
+    #[arg(short, long, default_value_t = num_cpus::get())]
+    threads: usize,
+}
+
+/// One region present in either input, labelled by its path relative to the inputs.
+struct DiffJob {
+    label: PathBuf,
+    old: Option<PathBuf>,
+    new: Option<PathBuf>,
+}
+
+fn read_region(registry: &FormatRegistry, path: &Path) -> Result<Region> {
+    let Some(format) = registry.detect_file(path)? else {
+        bail!("{} is not a recognised region file", path.display());
+    };
+    format
+        .read(path, None)
+        .with_context(|| format!("Failed to read {} as {}", path.display(), format.name()))
+}
+
+fn collect_jobs(
+    registry: &FormatRegistry,
+    old_dir: &Path,
+    new_dir: &Path,
+    relative_path: &Path,
+    jobs: &mut Vec<DiffJob>,
+) -> Result<()> {
+    let mut old = registry.region_files(old_dir)?;
+    let mut new = registry.region_files(new_dir)?;
+    let mut coords: Vec<_> = old.keys().chain(new.keys()).copied().collect();
+    coords.sort_unstable();
+    coords.dedup();
+
+    for (region_x, region_z) in coords {
+        jobs.push(DiffJob {
+            label: relative_path.join(format!("r.{}.{}", region_x, region_z)),
+            old: old.remove(&(region_x, region_z)),
+            new: new.remove(&(region_x, region_z)),
+        });
+    }
+    Ok(())
+}
+
+fn diff_job(job: &DiffJob, registry: &FormatRegistry, args: &Args) -> Result<Vec<ChunkDiff>> {
+    let read = |path: &Option<PathBuf>| {
+        path.as_ref()
+            .map(|path| read_region(registry, path))
+            .transpose()
+    };
+    let (old, new) = match (read(&job.old)?, read(&job.new)?) {
+        (Some(old), Some(new)) => (old, new),
+        (Some(old), None) => {
+            let new = Region::new(old.region_x, old.region_z);
+            (old, new)
+        }
+        (None, Some(new)) => (Region::new(new.region_x, new.region_z), new),
+        (None, None) => unreachable!("a diff job has at least one input"),
+    };
+    Ok(diff_regions(&old, &new, args.nbt.then_some(args.nbt_depth)))
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .context("Failed to initialize thread pool")?;
+
+    let registry = FormatRegistry::default();
+
+    let mut jobs = Vec::new();
+    if args.old.is_file() && args.new.is_file() {
+        jobs.push(DiffJob {
+            label: args.new.clone(),
+            old: Some(args.old.clone()),
+            new: Some(args.new.clone()),
+        });
+    } else if !args.old.is_dir() || !args.new.is_dir() {
+        bail!("OLD and NEW must both be region files or both be folders");
+    } else if args.world {
+        let mut relative_paths: Vec<_> = find_region_directories(&args.old)?
+            .into_iter()
+            .chain(find_region_directories(&args.new)?)
+            .map(|directory| directory.relative_path)
+            .collect();
+        relative_paths.sort();
+        relative_paths.dedup();
+
+        for relative_path in &relative_paths {
+            collect_jobs(
+                &registry,
+                &args.old.join(relative_path),
+                &args.new.join(relative_path),
+                relative_path,
+                &mut jobs,
+            )?;
+        }
+    } else {
+        collect_jobs(&registry, &args.old, &args.new, Path::new(""), &mut jobs)?;
+    }
+
+    let results: Vec<_> = jobs
+        .par_iter()
+        .map(|job| diff_job(job, &registry, &args))
+        .collect();
+
+    let (mut added, mut removed, mut changed, mut touched, mut errors) = (0, 0, 0, 0, 0);
+    for (job, result) in jobs.iter().zip(results) {
+        let diffs = match result {
+            Ok(diffs) => diffs,
+            Err(e) => {
+                eprintln!("Error comparing {}: {:#}", job.label.display(), e);
+                errors += 1;
+                continue;
+            }
+        };
+        for diff in &diffs {
+            match diff.kind {
+                ChunkDiffKind::Added => added += 1,
+                ChunkDiffKind::Removed => removed += 1,
+                ChunkDiffKind::Changed { .. } => changed += 1,
+                ChunkDiffKind::Touched => touched += 1,
+            }
+            println!("{}: {}", job.label.display(), diff);
+        }
+    }
+
+    println!();
+    println!("Diff Summary:");
+    println!("Regions compared: {}", jobs.len());
+    println!("Chunks added: {}", added);
+    println!("Chunks removed: {}", removed);
+    println!("Chunks changed: {}", changed);
+    println!("Chunks with only a new timestamp: {}", touched);
+    println!("Errors: {}", errors);
+
+    if errors > 0 {
+        std::process::exit(2);
+    }
+    if added + removed + changed + touched > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use linear_region_tools::{
+    export::{export_region_file, plan_export, prepare_output},
+    format::{FormatRegistry, WriteOptions},
+    selection::{ChunkBox, ChunkSelection},
+    world::RegionKind,
+    LINEAR_V2_DEFAULT_GRID_SIZE,
+};
+use rayon::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Parser)]
+#[command(
+    name = "export_area",
+    about = "Export part of a world into a new world.",
+    long_about = "Copy the chunks in a chunk selection, with their entities and POI, from every \
+                  dimension of a world into a new world folder, optionally in another format"
+)]
+struct Args {
+    /// World folder to export from
+    world: PathBuf,
+    /// New world folder; must not exist or be empty
+    output: PathBuf,
+    /// Chunks to export, e.g. `circle(0, 0, 512)`
+    #[arg(short, long, required_unless_present_any = ["select_file", "blocks"])]
+    select: Option<ChunkSelection>,
+    /// Export the chunks in the selections listed in this file, one per line
+    #[arg(long, conflicts_with = "select")]
+    select_file: Option<PathBuf>,
+    /// Export the chunks overlapping a box of block coordinates, as `X1,Z1,X2,Z2`
+    #[arg(
+        short,
+        long,
+        value_parser = parse_block_box,
+        allow_hyphen_values = true,
+        conflicts_with_all = ["select", "select_file"]
+    )]
+    blocks: Option<ChunkBox>,
+    /// Only export these dimensions, e.g. `minecraft:overworld`; may be repeated
+    #[arg(long)]
+    dimension: Vec<String>,
+    /// Output format (mca, linearv1 or linearv2) [default: the format of each source file]
+    #[arg(short, long)]
+    format: Option<String>,
+    #[arg(short, long, default_value_t = num_cpus::get())]
+    threads: usize,
+    #[arg(short, long, default_value_t = 6)]
+    compression_level: i32,
+    /// LinearV2 bucket grid size (1, 2, 4, 8, 16 or 32)
+    #[arg(long, default_value_t = LINEAR_V2_DEFAULT_GRID_SIZE)]
+    grid_size: u8,
+}
+
+fn parse_block_box(s: &str) -> Result<ChunkBox, String> {
+    let coords = s
+        .split(',')
+        .map(|value| {
+            value
+                .trim()
+                .parse::<i32>()
+                .map_err(|e| format!("invalid coordinate `{}`: {}", value.trim(), e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match coords[..] {
+        [x1, z1, x2, z2] => Ok(ChunkBox::from_blocks(x1, z1, x2, z2)),
+        _ => Err(format!("expected X1,Z1,X2,Z2, found `{}`", s)),
+    }
+}
+
+#[derive(Default)]
+struct ExportStats {
+    files_written: AtomicU64,
+    chunks: AtomicU64,
+    entity_chunks: AtomicU64,
+    poi_chunks: AtomicU64,
+    errors: AtomicU64,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .context("Failed to initialize thread pool")?;
+
+    let selection = match (&args.select_file, &args.select, &args.blocks) {
+        (Some(select_file), _, _) => ChunkSelection::from_file(select_file)?,
+        (None, Some(select), _) => select.clone(),
+        (None, None, Some(blocks)) => ChunkSelection::Box(*blocks),
+        (None, None, None) => bail!("No chunk selection given"),
+    };
+
+    let registry = FormatRegistry::default();
+    let output_format = match &args.format {
+        Some(name) => match registry.get(name) {
+            Some(format) => Some(format),
+            None => bail!("Unknown format: {}", name),
+        },
+        None => None,
+    };
+    let write_options = WriteOptions {
+        compression_level: args.compression_level,
+        grid_size: args.grid_size,
+        ..Default::default()
+    };
+
+    if args.output.exists() && fs::read_dir(&args.output)?.next().is_some() {
+        bail!("{} already exists and is not empty", args.output.display());
+    }
+
+    let jobs = plan_export(
+        &args.world,
+        &args.output,
+        &registry,
+        &selection,
+        &args.dimension,
+    )?;
+    if jobs.is_empty() {
+        eprintln!("No region files overlap the selection");
+        std::process::exit(1);
+    }
+
+    prepare_output(&args.world, &args.output, &jobs)?;
+
+    let progress = ProgressBar::new(jobs.len() as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:.cyan}] {pos}/{len} ({percent}%) {msg}")?
+            .progress_chars("#>-"),
+    );
+
+    let stats = ExportStats::default();
+    jobs.par_iter().for_each(|job| {
+        match export_region_file(job, &registry, output_format, &selection, &write_options) {
+            Ok(0) => {}
+            Ok(chunks) => {
+                let counter = match job.kind {
+                    RegionKind::Region => &stats.chunks,
+                    RegionKind::Entities => &stats.entity_chunks,
+                    RegionKind::Poi => &stats.poi_chunks,
+                };
+                counter.fetch_add(chunks as u64, Ordering::Relaxed);
+                stats.files_written.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => {
+                progress.println(format!("Error exporting {}: {:#}", job.source.display(), e));
+                stats.errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        progress.inc(1);
+    });
+    progress.finish();
+
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    println!();
+    println!("Export Summary:");
+    println!("Region files read: {}", jobs.len());
+    println!("Region files written: {}", load(&stats.files_written));
+    println!("Chunks exported: {}", load(&stats.chunks));
+    println!("Entity chunks exported: {}", load(&stats.entity_chunks));
+    println!("POI chunks exported: {}", load(&stats.poi_chunks));
+    println!("Errors: {}", load(&stats.errors));
+
+    if load(&stats.errors) > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
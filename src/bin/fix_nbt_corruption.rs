@@ -87,7 +87,7 @@ fn main() -> Result<()> {
     for entry in fs::read_dir(&args.input)? {
         let entry = entry?;
         let path = entry.path();
-        if path.is_file() && path.extension().map_or(false, |ext| ext == extension) {
+        if path.is_file() && path.extension().is_some_and(|ext| ext == extension) {
             files.push(path);
         }
     }
@@ -135,13 +135,10 @@ fn main() -> Result<()> {
                 }
             }
         })
-        .reduce(
-            || FixStats::default(),
-            |mut acc, stats| {
-                acc.merge(&stats);
-                acc
-            },
-        );
+        .reduce(FixStats::default, |mut acc, stats| {
+            acc.merge(&stats);
+            acc
+        });
 
     progress.finish_with_message("Complete!");
 
@@ -157,8 +154,10 @@ fn main() -> Result<()> {
 }
 
 fn fix_region_file(file_path: &Path, args: &Args) -> Result<FixStats> {
-    let mut stats = FixStats::default();
-    stats.files_processed = 1;
+    let mut stats = FixStats {
+        files_processed: 1,
+        ..Default::default()
+    };
 
     if args.backup && !args.dry_run {
         let backup_path = file_path.with_extension(format!(
@@ -215,25 +214,25 @@ fn should_delete_entity(entity: &Value) -> bool {
     };
 
     let has_custom_data = |item: &Value| {
-        if let Value::Compound(item_data) = item {
-            if let Some(Value::Compound(components)) = item_data.get("components") {
-                return components.contains_key("minecraft:custom_data");
-            }
+        if let Value::Compound(item_data) = item
+            && let Some(Value::Compound(components)) = item_data.get("components")
+        {
+            return components.contains_key("minecraft:custom_data");
         }
         false
     };
 
-    if let Some(Value::Compound(equipment)) = entity_data.get("equipment") {
-        if equipment.values().any(has_custom_data) {
-            return true;
-        }
+    if let Some(Value::Compound(equipment)) = entity_data.get("equipment")
+        && equipment.values().any(has_custom_data)
+    {
+        return true;
     }
 
     for field in ["ArmorItems", "HandItems"] {
-        if let Some(Value::List(items)) = entity_data.get(field) {
-            if items.iter().any(has_custom_data) {
-                return true;
-            }
+        if let Some(Value::List(items)) = entity_data.get(field)
+            && items.iter().any(has_custom_data)
+        {
+            return true;
         }
     }
 
@@ -320,13 +319,13 @@ fn fix_entity(
             }
         }
 
-        if args.clamp_positions {
-            if let Some(pos) = entity_data.get_mut("Pos") {
-                let pos_stats = fix_entity_position(pos, chunk_x, chunk_z)?;
-                stats.merge(&pos_stats);
-                if pos_stats.positions_fixed > 0 {
-                    entity_modified = true;
-                }
+        if args.clamp_positions
+            && let Some(pos) = entity_data.get_mut("Pos")
+        {
+            let pos_stats = fix_entity_position(pos, chunk_x, chunk_z)?;
+            stats.merge(&pos_stats);
+            if pos_stats.positions_fixed > 0 {
+                entity_modified = true;
             }
         }
 
@@ -381,13 +380,13 @@ fn fix_item_enchantments(item: &mut Value) -> Result<FixStats> {
 
     if let Value::Compound(item_data) = item {
         if let Some(Value::Compound(components)) = item_data.get_mut("components") {
-            if let Some(enchants) = components.get_mut("minecraft:enchantments") {
-                if let Value::Compound(enchant_map) = enchants {
-                    if let Some(Value::Compound(levels)) = enchant_map.get_mut("levels") {
-                        stats.enchantments_fixed += fix_enchantment_levels(levels);
-                    } else {
-                        stats.enchantments_fixed += fix_enchantment_levels(enchant_map);
-                    }
+            if let Some(enchants) = components.get_mut("minecraft:enchantments")
+                && let Value::Compound(enchant_map) = enchants
+            {
+                if let Some(Value::Compound(levels)) = enchant_map.get_mut("levels") {
+                    stats.enchantments_fixed += fix_enchantment_levels(levels);
+                } else {
+                    stats.enchantments_fixed += fix_enchantment_levels(enchant_map);
                 }
             }
 
@@ -399,13 +398,12 @@ fn fix_item_enchantments(item: &mut Value) -> Result<FixStats> {
 
                 if let Some(Value::List(enchantments)) = custom_data.get_mut("Enchantments") {
                     for enchant in enchantments {
-                        if let Value::Compound(enchant_data) = enchant {
-                            if let Some(Value::Short(lvl)) = enchant_data.get_mut("lvl") {
-                                if *lvl == 0 {
-                                    *lvl = 1;
-                                    stats.enchantments_fixed += 1;
-                                }
-                            }
+                        if let Value::Compound(enchant_data) = enchant
+                            && let Some(Value::Short(lvl)) = enchant_data.get_mut("lvl")
+                            && *lvl == 0
+                        {
+                            *lvl = 1;
+                            stats.enchantments_fixed += 1;
                         }
                     }
                 }
@@ -414,13 +412,12 @@ fn fix_item_enchantments(item: &mut Value) -> Result<FixStats> {
 
         if let Some(Value::List(enchantments)) = item_data.get_mut("Enchantments") {
             for enchant in enchantments {
-                if let Value::Compound(enchant_data) = enchant {
-                    if let Some(Value::Short(lvl)) = enchant_data.get_mut("lvl") {
-                        if *lvl == 0 {
-                            *lvl = 1;
-                            stats.enchantments_fixed += 1;
-                        }
-                    }
+                if let Value::Compound(enchant_data) = enchant
+                    && let Some(Value::Short(lvl)) = enchant_data.get_mut("lvl")
+                    && *lvl == 0
+                {
+                    *lvl = 1;
+                    stats.enchantments_fixed += 1;
                 }
             }
         }
@@ -434,23 +431,17 @@ fn fix_enchantment_levels(enchant_map: &mut HashMap<String, Value>) -> usize {
 
     for (_enchant_name, level) in enchant_map.iter_mut() {
         match level {
-            Value::Int(lvl) => {
-                if *lvl == 0 {
-                    *lvl = 1;
-                    fixed_count += 1;
-                }
+            Value::Int(lvl) if *lvl == 0 => {
+                *lvl = 1;
+                fixed_count += 1;
             }
-            Value::Short(lvl) => {
-                if *lvl == 0 {
-                    *lvl = 1;
-                    fixed_count += 1;
-                }
+            Value::Short(lvl) if *lvl == 0 => {
+                *lvl = 1;
+                fixed_count += 1;
             }
-            Value::Byte(lvl) => {
-                if *lvl == 0 {
-                    *lvl = 1;
-                    fixed_count += 1;
-                }
+            Value::Byte(lvl) if *lvl == 0 => {
+                *lvl = 1;
+                fixed_count += 1;
             }
             _ => {
                 // Unhandled type
@@ -506,31 +497,31 @@ fn fix_entity_uuid(uuid_value: &mut Value, used_uuids: &mut HashSet<String>) ->
 fn fix_entity_position(pos: &mut Value, chunk_x: i32, chunk_z: i32) -> Result<FixStats> {
     let mut stats = FixStats::default();
 
-    if let Value::List(coords) = pos {
-        if coords.len() >= 3 {
-            let mut position_fixed = false;
-            let expected_min_x = (chunk_x * 16) as f64;
-            let expected_max_x = ((chunk_x + 1) * 16) as f64;
-            let expected_min_z = (chunk_z * 16) as f64;
-            let expected_max_z = ((chunk_z + 1) * 16) as f64;
-
-            if let Value::Double(x) = &coords[0] {
-                if *x < expected_min_x || *x >= expected_max_x {
-                    coords[0] = Value::Double(expected_min_x + 8.0);
-                    position_fixed = true;
-                }
-            }
+    if let Value::List(coords) = pos
+        && coords.len() >= 3
+    {
+        let mut position_fixed = false;
+        let expected_min_x = (chunk_x * 16) as f64;
+        let expected_max_x = ((chunk_x + 1) * 16) as f64;
+        let expected_min_z = (chunk_z * 16) as f64;
+        let expected_max_z = ((chunk_z + 1) * 16) as f64;
+
+        if let Value::Double(x) = &coords[0]
+            && (*x < expected_min_x || *x >= expected_max_x)
+        {
+            coords[0] = Value::Double(expected_min_x + 8.0);
+            position_fixed = true;
+        }
 
-            if let Value::Double(z) = &coords[2] {
-                if *z < expected_min_z || *z >= expected_max_z {
-                    coords[2] = Value::Double(expected_min_z + 8.0);
-                    position_fixed = true;
-                }
-            }
+        if let Value::Double(z) = &coords[2]
+            && (*z < expected_min_z || *z >= expected_max_z)
+        {
+            coords[2] = Value::Double(expected_min_z + 8.0);
+            position_fixed = true;
+        }
 
-            if position_fixed {
-                stats.positions_fixed += 1;
-            }
+        if position_fixed {
+            stats.positions_fixed += 1;
         }
     }
 
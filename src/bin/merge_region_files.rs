@@ -0,0 +1,266 @@
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use linear_region_tools::{
+    format::{FormatRegistry, RegionFormat, WriteOptions},
+    merge::{merge_regions_with_report, MergePolicy, MergeReport},
+    world::find_region_directories,
+    Region, LINEAR_V2_DEFAULT_GRID_SIZE,
+};
+use rayon::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Policy {
+    Newest,
+    Left,
+    Right,
+    FillEmpty,
+}
+
+impl From<Policy> for MergePolicy {
+    fn from(policy: Policy) -> Self {
+        match policy {
+            Policy::Newest => MergePolicy::Newest,
+            Policy::Left => MergePolicy::LeftWins,
+            Policy::Right => MergePolicy::RightWins,
+            Policy::FillEmpty => MergePolicy::FillEmpty,
+        }
+    }
+}
+
+#[derive(Parser)]
+#[command(
+    name = "merge_region_files",
+    about = "Merge two region files, region folders or worlds.",
+    long_about = "Combine two region files of any format chunk by chunk. Given two folders, \
+                  regions with the same coordinates are merged and the rest copied"
+)]
+struct Args {
+    left: PathBuf,
+    right: PathBuf,
+    output: PathBuf,
+    /// Which chunk to keep when both sides have one
+    #[arg(short, long, value_enum, default_value_t = Policy::Newest)]
+    policy: Policy,
+    /// Output format (mca, linearv1 or linearv2) [default: the format of the left input]
+    #[arg(short, long)]
+    format: Option<String>,
+    #[arg(short, long, default_value_t = num_cpus::get())]
+    threads: usize,
+    #[arg(short, long, default_value_t = 6)]
+    compression_level: i32,
+    /// LinearV2 bucket grid size (1, 2, 4, 8, 16 or 32)
+    #[arg(long, default_value_t = LINEAR_V2_DEFAULT_GRID_SIZE)]
+    grid_size: u8,
+    /// Treat the inputs and output as world folders and merge the region,
+    /// entities and poi folders of every dimension
+    #[arg(long)]
+    world: bool,
+}
+
+/// One output region and the inputs it is merged from.
+struct MergeJob {
+    left: Option<PathBuf>,
+    right: Option<PathBuf>,
+    output_dir: PathBuf,
+    region_x: i32,
+    region_z: i32,
+}
+
+fn read_region<'a>(
+    registry: &'a FormatRegistry,
+    path: &Path,
+) -> Result<(Region, &'a dyn RegionFormat)> {
+    let Some(format) = registry.detect_file(path)? else {
+        bail!("{} is not a recognised region file", path.display());
+    };
+    let region = format
+        .read(path, None)
+        .with_context(|| format!("Failed to read {} as {}", path.display(), format.name()))?;
+    Ok((region, format))
+}
+
+fn collect_jobs(
+    registry: &FormatRegistry,
+    left_dir: &Path,
+    right_dir: &Path,
+    output_dir: &Path,
+    jobs: &mut Vec<MergeJob>,
+) -> Result<()> {
+    let mut left = registry.region_files(left_dir)?;
+    let mut right = registry.region_files(right_dir)?;
+    let mut coords: Vec<_> = left.keys().chain(right.keys()).copied().collect();
+    coords.sort_unstable();
+    coords.dedup();
+
+    for (region_x, region_z) in coords {
+        jobs.push(MergeJob {
+            left: left.remove(&(region_x, region_z)),
+            right: right.remove(&(region_x, region_z)),
+            output_dir: output_dir.to_path_buf(),
+            region_x,
+            region_z,
+        });
+    }
+    Ok(())
+}
+
+fn merge_job(
+    job: &MergeJob,
+    registry: &FormatRegistry,
+    output_format: Option<&dyn RegionFormat>,
+    policy: MergePolicy,
+    write_options: &WriteOptions,
+) -> Result<MergeReport> {
+    let empty = || Region::new(job.region_x, job.region_z);
+    let (left, left_format) = match &job.left {
+        Some(path) => {
+            let (region, format) = read_region(registry, path)?;
+            (region, Some(format))
+        }
+        None => (empty(), None),
+    };
+    let (right, right_format) = match &job.right {
+        Some(path) => {
+            let (region, format) = read_region(registry, path)?;
+            (region, Some(format))
+        }
+        None => (empty(), None),
+    };
+
+    let format = output_format
+        .or(left_format)
+        .or(right_format)
+        .expect("a merge job has at least one input");
+    let (merged, report) = merge_regions_with_report(&left, &right, policy)?;
+
+    let output_path = job.output_dir.join(format!(
+        "r.{}.{}.{}",
+        job.region_x,
+        job.region_z,
+        format.extension()
+    ));
+    format.write(&output_path, &merged, write_options, None)?;
+    Ok(report)
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .context("Failed to initialize thread pool")?;
+
+    let registry = FormatRegistry::default();
+    let output_format = match &args.format {
+        Some(name) => match registry.get(name) {
+            Some(format) => Some(format),
+            None => bail!("Unknown format: {}", name),
+        },
+        None => None,
+    };
+    let write_options = WriteOptions {
+        compression_level: args.compression_level,
+        grid_size: args.grid_size,
+        ..Default::default()
+    };
+    let policy = MergePolicy::from(args.policy);
+
+    if args.left.is_file() && args.right.is_file() {
+        let (left, left_format) = read_region(&registry, &args.left)?;
+        let (right, _) = read_region(&registry, &args.right)?;
+        let (merged, report) = merge_regions_with_report(&left, &right, policy)?;
+        output_format
+            .unwrap_or(left_format)
+            .write(&args.output, &merged, &write_options, None)?;
+
+        println!(
+            "Merged {} chunks ({} from left, {} from right, {} conflicts) into {}",
+            merged.chunk_count(),
+            report.from_left,
+            report.from_right,
+            report.conflicts,
+            args.output.display()
+        );
+        return Ok(());
+    }
+
+    if !args.left.is_dir() || !args.right.is_dir() {
+        bail!("LEFT and RIGHT must both be region files or both be folders");
+    }
+
+    let mut jobs = Vec::new();
+    if args.world {
+        let mut relative_paths: Vec<_> = find_region_directories(&args.left)?
+            .into_iter()
+            .chain(find_region_directories(&args.right)?)
+            .map(|directory| directory.relative_path)
+            .collect();
+        relative_paths.sort();
+        relative_paths.dedup();
+
+        for relative_path in &relative_paths {
+            collect_jobs(
+                &registry,
+                &args.left.join(relative_path),
+                &args.right.join(relative_path),
+                &args.output.join(relative_path),
+                &mut jobs,
+            )?;
+        }
+    } else {
+        collect_jobs(&registry, &args.left, &args.right, &args.output, &mut jobs)?;
+    }
+
+    if jobs.is_empty() {
+        eprintln!("No region files found");
+        std::process::exit(1);
+    }
+    for job in &jobs {
+        fs::create_dir_all(&job.output_dir)?;
+    }
+
+    let progress = ProgressBar::new(jobs.len() as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:.cyan}] {pos}/{len} ({percent}%) {msg}")?
+            .progress_chars("#>-"),
+    );
+
+    let totals = Mutex::new((MergeReport::default(), 0usize));
+    jobs.par_iter().for_each(|job| {
+        match merge_job(job, &registry, output_format, policy, &write_options) {
+            Ok(report) => {
+                let mut totals = totals.lock().unwrap();
+                totals.0.from_left += report.from_left;
+                totals.0.from_right += report.from_right;
+                totals.0.conflicts += report.conflicts;
+            }
+            Err(e) => {
+                let input = job.left.as_ref().or(job.right.as_ref()).unwrap();
+                progress.println(format!("Error merging {}: {:#}", input.display(), e));
+                totals.lock().unwrap().1 += 1;
+            }
+        }
+        progress.inc(1);
+    });
+    progress.finish();
+
+    let (report, errors) = totals.into_inner().unwrap();
+    println!();
+    println!("Merge Summary:");
+    println!("Regions written: {}", jobs.len() - errors);
+    println!("Chunks from left: {}", report.from_left);
+    println!("Chunks from right: {}", report.from_right);
+    println!("Conflicting chunks: {}", report.conflicts);
+    println!("Errors: {}", errors);
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
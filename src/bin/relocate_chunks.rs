@@ -0,0 +1,387 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use linear_region_tools::{
+    format::{rewrite_region_file, FormatRegistry, RegionFormat, WriteOptions},
+    relocate::relocate_chunk,
+    selection::{ChunkSelection, Overlap},
+    world::find_region_directories,
+    Chunk, Region, CHUNKS_PER_REGION, LINEAR_V2_DEFAULT_GRID_SIZE,
+};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(
+    name = "relocate_chunks",
+    about = "Copy or move a selection of chunks to other coordinates.",
+    long_about = "Copy or move the chunks in a chunk selection by a chunk offset, optionally \
+                  into another folder or world, rewriting the coordinates stored in their NBT"
+)]
+struct Args {
+    /// A region folder, or a world folder with --world
+    source: PathBuf,
+    /// Chunks to relocate, e.g. `blocks(-100, -100, 100, 100)`
+    #[arg(short, long, required_unless_present = "select_file")]
+    select: Option<ChunkSelection>,
+    /// Relocate the chunks in the selections listed in this file, one per line
+    #[arg(long, conflicts_with = "select")]
+    select_file: Option<PathBuf>,
+    /// Distance to move the chunks, in chunks, as `DX,DZ`
+    #[arg(short, long, value_parser = parse_offset, allow_hyphen_values = true)]
+    offset: (i32, i32),
+    /// Folder (or world with --world) to write to [default: SOURCE]
+    #[arg(long)]
+    dest: Option<PathBuf>,
+    /// Remove the chunks from their old position instead of copying them
+    #[arg(long = "move")]
+    move_chunks: bool,
+    /// Relocate the region, entities and poi folders of every dimension
+    #[arg(long)]
+    world: bool,
+    /// Replace chunks that already exist at the destination
+    #[arg(long)]
+    overwrite: bool,
+    /// Format of newly created region files (mca, linearv1 or linearv2)
+    /// [default: the format of the source files]
+    #[arg(short, long)]
+    format: Option<String>,
+    #[arg(short, long, default_value_t = num_cpus::get())]
+    threads: usize,
+    #[arg(short, long, default_value_t = 6)]
+    compression_level: i32,
+    /// LinearV2 bucket grid size (1, 2, 4, 8, 16 or 32) of new files
+    #[arg(long, default_value_t = LINEAR_V2_DEFAULT_GRID_SIZE)]
+    grid_size: u8,
+    /// Report what would be relocated without writing anything
+    #[arg(short, long)]
+    dry_run: bool,
+}
+
+fn parse_offset(s: &str) -> Result<(i32, i32), String> {
+    let (dx, dz) = s
+        .split_once(',')
+        .ok_or_else(|| format!("expected DX,DZ, found `{}`", s))?;
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<i32>()
+            .map_err(|e| format!("invalid offset `{}`: {}", value.trim(), e))
+    };
+    Ok((parse(dx)?, parse(dz)?))
+}
+
+/// Settings shared by every folder being relocated.
+struct Relocation<'a> {
+    registry: &'a FormatRegistry,
+    selection: &'a ChunkSelection,
+    offset: (i32, i32),
+    move_chunks: bool,
+    overwrite: bool,
+    format: Option<&'a dyn RegionFormat>,
+    write_options: WriteOptions,
+    dry_run: bool,
+}
+
+/// A region that will be written back, with the format of its file on disk
+/// (`None` for a new file) and the chunks moved out of it.
+struct PendingRegion<'a> {
+    region: Region,
+    format: Option<&'a dyn RegionFormat>,
+    removed: Vec<usize>,
+}
+
+#[derive(Default)]
+struct RelocateReport {
+    chunks: usize,
+    replaced: usize,
+    files_written: usize,
+    files_deleted: usize,
+}
+
+fn read_region<'a>(
+    registry: &'a FormatRegistry,
+    path: &Path,
+) -> Result<(Region, &'a dyn RegionFormat)> {
+    let Some(format) = registry.detect_file(path)? else {
+        bail!("{} is not a recognised region file", path.display());
+    };
+    let region = format
+        .read(path, None)
+        .with_context(|| format!("Failed to read {} as {}", path.display(), format.name()))?;
+    Ok((region, format))
+}
+
+/// Reads the selected chunks of one source file and returns them relocated,
+/// along with the source region once they are removed from it.
+fn extract_chunks<'a>(
+    relocation: &Relocation<'a>,
+    path: &Path,
+) -> Result<(PendingRegion<'a>, Vec<(Chunk, u32)>)> {
+    let (mut region, format) = read_region(relocation.registry, path)?;
+    let (dx, dz) = relocation.offset;
+
+    let mut selected = Vec::new();
+    let mut relocated = Vec::new();
+    for index in 0..CHUNKS_PER_REGION {
+        let Some(chunk) = region.get_chunk(index) else {
+            continue;
+        };
+        if !relocation.selection.contains(chunk.x, chunk.z) {
+            continue;
+        }
+        let moved = relocate_chunk(chunk, chunk.x + dx, chunk.z + dz)
+            .with_context(|| format!("Failed to relocate chunk ({}, {})", chunk.x, chunk.z))?;
+        relocated.push((moved, region.timestamps[index]));
+        selected.push(index);
+    }
+
+    let mut removed = Vec::new();
+    if relocation.move_chunks {
+        for &index in &selected {
+            region.remove_chunk(index);
+        }
+        removed = selected;
+    }
+    let pending = PendingRegion {
+        region,
+        format: Some(format),
+        removed,
+    };
+    Ok((pending, relocated))
+}
+
+fn relocate_dir(
+    relocation: &Relocation,
+    source_dir: &Path,
+    dest_dir: &Path,
+) -> Result<RelocateReport> {
+    let registry = relocation.registry;
+    let sources: Vec<_> = registry
+        .region_files(source_dir)?
+        .into_iter()
+        .filter(|((x, z), _)| relocation.selection.region_overlap(*x, *z) != Overlap::None)
+        .map(|(_, path)| path)
+        .collect();
+
+    let extracted = sources
+        .par_iter()
+        .map(|path| extract_chunks(relocation, path))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut report = RelocateReport::default();
+    let Some(new_format) = relocation
+        .format
+        .or_else(|| extracted.first().and_then(|(pending, _)| pending.format))
+    else {
+        return Ok(report);
+    };
+
+    // Every region touched is kept in memory until all chunks are placed, so
+    // that a move within one folder sees its own removals
+    let mut pending: HashMap<PathBuf, PendingRegion> = HashMap::new();
+    let mut by_region: BTreeMap<(i32, i32), Vec<(Chunk, u32)>> = BTreeMap::new();
+    for (path, (source, chunks)) in sources.into_iter().zip(extracted) {
+        if !source.removed.is_empty() {
+            pending.insert(path, source);
+        }
+        for (chunk, timestamp) in chunks {
+            by_region
+                .entry((chunk.x >> 5, chunk.z >> 5))
+                .or_default()
+                .push((chunk, timestamp));
+        }
+    }
+
+    let existing = registry.region_files(dest_dir)?;
+    let dest_paths: Vec<_> = by_region
+        .keys()
+        .map(|&(x, z)| match existing.get(&(x, z)) {
+            Some(path) => ((x, z), path.clone()),
+            None => (
+                (x, z),
+                dest_dir.join(format!("r.{}.{}.{}", x, z, new_format.extension())),
+            ),
+        })
+        .collect();
+    let loaded = dest_paths
+        .par_iter()
+        .filter(|(_, path)| !pending.contains_key(path))
+        .map(|&((x, z), ref path)| {
+            let target = if path.is_file() {
+                let (region, format) = read_region(registry, path)?;
+                PendingRegion {
+                    region,
+                    format: Some(format),
+                    removed: Vec::new(),
+                }
+            } else {
+                PendingRegion {
+                    region: Region::new(x, z),
+                    format: None,
+                    removed: Vec::new(),
+                }
+            };
+            Ok((path.clone(), target))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    pending.extend(loaded);
+
+    for ((_, path), chunks) in dest_paths.iter().zip(by_region.into_values()) {
+        let target = pending
+            .get_mut(path)
+            .expect("destination region was loaded");
+        for (chunk, timestamp) in chunks {
+            if target.region.get_chunk_at(chunk.x, chunk.z).is_some() {
+                report.replaced += 1;
+            }
+            report.chunks += 1;
+            target.region.insert_chunk(chunk, timestamp)?;
+        }
+    }
+
+    if report.replaced > 0 && !relocation.overwrite {
+        bail!(
+            "{} chunks already exist at the destination in {}; pass --overwrite to replace them",
+            report.replaced,
+            dest_dir.display()
+        );
+    }
+    report.files_written = pending.len();
+    if relocation.dry_run {
+        return Ok(report);
+    }
+
+    fs::create_dir_all(dest_dir)?;
+    let deleted = pending
+        .par_iter()
+        .map(|(path, target)| {
+            match target.format {
+                Some(format) => {
+                    // Slots refilled by the move keep their new chunk's .mcc file
+                    let removed: Vec<_> = target
+                        .removed
+                        .iter()
+                        .copied()
+                        .filter(|&index| target.region.get_chunk(index).is_none())
+                        .collect();
+                    rewrite_region_file(
+                        path,
+                        &target.region,
+                        format,
+                        &removed,
+                        &relocation.write_options,
+                    )?;
+                }
+                None => new_format.write(path, &target.region, &relocation.write_options, None)?,
+            }
+            Ok(target.region.chunk_count() == 0)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    report.files_deleted = deleted.into_iter().filter(|&deleted| deleted).count();
+    report.files_written -= report.files_deleted;
+    Ok(report)
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .context("Failed to initialize thread pool")?;
+
+    let selection = match (&args.select_file, &args.select) {
+        (Some(select_file), _) => ChunkSelection::from_file(select_file)?,
+        (None, Some(select)) => select.clone(),
+        (None, None) => bail!("No chunk selection given"),
+    };
+    let dest = args.dest.clone().unwrap_or_else(|| args.source.clone());
+    if args.offset == (0, 0) && dest == args.source {
+        bail!("Relocating chunks by (0, 0) within the same folder does nothing");
+    }
+
+    let registry = FormatRegistry::default();
+    let format = match &args.format {
+        Some(name) => match registry.get(name) {
+            Some(format) => Some(format),
+            None => bail!("Unknown format: {}", name),
+        },
+        None => None,
+    };
+    let relocation = Relocation {
+        registry: &registry,
+        selection: &selection,
+        offset: args.offset,
+        move_chunks: args.move_chunks,
+        overwrite: args.overwrite,
+        format,
+        write_options: WriteOptions {
+            compression_level: args.compression_level,
+            grid_size: args.grid_size,
+            ..Default::default()
+        },
+        dry_run: args.dry_run,
+    };
+
+    let dirs: Vec<(PathBuf, PathBuf)> = if args.world {
+        find_region_directories(&args.source)?
+            .into_iter()
+            .map(|directory| {
+                (
+                    args.source.join(&directory.relative_path),
+                    dest.join(&directory.relative_path),
+                )
+            })
+            .collect()
+    } else {
+        vec![(args.source.clone(), dest.clone())]
+    };
+
+    if args.dry_run {
+        println!("DRY RUN MODE - No files will be modified");
+    }
+
+    let mut total = RelocateReport::default();
+    let mut errors = 0;
+    for (source_dir, dest_dir) in &dirs {
+        match relocate_dir(&relocation, source_dir, dest_dir) {
+            Ok(report) => {
+                if report.chunks > 0 {
+                    println!(
+                        "{}: {} chunks -> {}",
+                        source_dir.display(),
+                        report.chunks,
+                        dest_dir.display()
+                    );
+                }
+                total.chunks += report.chunks;
+                total.replaced += report.replaced;
+                total.files_written += report.files_written;
+                total.files_deleted += report.files_deleted;
+            }
+            Err(e) => {
+                eprintln!("Error relocating {}: {:#}", source_dir.display(), e);
+                errors += 1;
+            }
+        }
+    }
+
+    println!();
+    println!("Relocate Summary:");
+    println!(
+        "Chunks {}: {}",
+        if args.move_chunks { "moved" } else { "copied" },
+        total.chunks
+    );
+    println!("Chunks replaced: {}", total.replaced);
+    println!("Region files written: {}", total.files_written);
+    println!("Region files deleted: {}", total.files_deleted);
+    println!("Errors: {}", errors);
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use linear_region_tools::{
+    format::FormatRegistry,
+    render::{downscale, render_region, write_png, ColorTable, Rgba, TILE_SIZE, TRANSPARENT},
+    selection::{ChunkSelection, Overlap},
+    world::{find_region_directories, RegionKind},
+    Region,
+};
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Parser)]
+#[command(
+    name = "render_map",
+    about = "Render top-down maps of region files to PNG.",
+    long_about = "Render a top-down image of every Anvil or Linear region file, one PNG tile \
+                  per region, using heightmaps and section palettes. Missing chunks are left \
+                  transparent and chunks that cannot be read are drawn in red."
+)]
+struct Args {
+    /// A region folder, or a world folder with --world
+    path: PathBuf,
+    /// Folder to write `r.<x>.<z>.png` tiles to
+    output: PathBuf,
+    /// File of `block = RRGGBB` lines overriding the built-in colours
+    #[arg(long)]
+    colors: Option<PathBuf>,
+    /// Also write all tiles stitched together as overview.png
+    #[arg(long)]
+    overview: bool,
+    /// Shrink the overview by this factor
+    #[arg(long, default_value_t = 4, value_parser = parse_scale)]
+    overview_scale: usize,
+    /// Only render regions overlapping this selection
+    #[arg(short, long)]
+    select: Option<ChunkSelection>,
+    /// Render the region folder of every dimension into its own subfolder
+    #[arg(long)]
+    world: bool,
+    #[arg(short, long, default_value_t = num_cpus::get())]
+    threads: usize,
+}
+
+fn parse_scale(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(scale) if scale > 0 && TILE_SIZE.is_multiple_of(scale) => Ok(scale),
+        _ => Err(format!("must divide {} evenly", TILE_SIZE)),
+    }
+}
+
+fn read_region(registry: &FormatRegistry, path: &Path) -> Result<Region> {
+    let Some(format) = registry.detect_file(path)? else {
+        bail!("{} is not a recognised region file", path.display());
+    };
+    format
+        .read(path, None)
+        .with_context(|| format!("Failed to read {} as {}", path.display(), format.name()))
+}
+
+/// Largest overview canvas, in pixels: 16384 x 16384, or 1 GiB of RGBA.
+const MAX_OVERVIEW_PIXELS: u64 = 1 << 28;
+
+/// Stitches downscaled tiles into one image covering all their regions.
+/// Regions far apart make for a canvas past [`MAX_OVERVIEW_PIXELS`], which
+/// is an error rather than an allocation that cannot succeed.
+fn write_overview(path: &Path, tiles: &[((i32, i32), Vec<Rgba>)], scale: usize) -> Result<()> {
+    let tile_size = TILE_SIZE / scale;
+    let min_x = tiles.iter().map(|((x, _), _)| *x).min().unwrap_or(0);
+    let max_x = tiles.iter().map(|((x, _), _)| *x).max().unwrap_or(0);
+    let min_z = tiles.iter().map(|((_, z), _)| *z).min().unwrap_or(0);
+    let max_z = tiles.iter().map(|((_, z), _)| *z).max().unwrap_or(0);
+    let width = (max_x as i64 - min_x as i64 + 1) as u64 * tile_size as u64;
+    let height = (max_z as i64 - min_z as i64 + 1) as u64 * tile_size as u64;
+    if width * height > MAX_OVERVIEW_PIXELS {
+        bail!(
+            "The overview would be {}x{} pixels; raise --overview-scale or render fewer regions",
+            width,
+            height
+        );
+    }
+    let (width, height) = (width as usize, height as usize);
+
+    let mut pixels = vec![TRANSPARENT; width * height];
+    for ((region_x, region_z), tile) in tiles {
+        let left = (region_x - min_x) as usize * tile_size;
+        let top = (region_z - min_z) as usize * tile_size;
+        for (row, line) in tile.chunks(tile_size).enumerate() {
+            let start = (top + row) * width + left;
+            pixels[start..start + tile_size].copy_from_slice(line);
+        }
+    }
+    write_png(path, width, height, &pixels)
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .context("Failed to initialize thread pool")?;
+
+    let colors = match &args.colors {
+        Some(path) => ColorTable::from_file(path)?,
+        None => ColorTable::default(),
+    };
+
+    let dirs: Vec<(PathBuf, PathBuf)> = if args.world {
+        find_region_directories(&args.path)?
+            .into_iter()
+            .filter(|directory| directory.kind == RegionKind::Region)
+            .map(|directory| {
+                let output = args.output.join(directory.dimension.replace(':', "_"));
+                (args.path.join(&directory.relative_path), output)
+            })
+            .collect()
+    } else {
+        vec![(args.path.clone(), args.output.clone())]
+    };
+
+    let registry = FormatRegistry::default();
+    let mut jobs = Vec::new();
+    for (dir, output) in &dirs {
+        let files: Vec<_> = registry
+            .region_files(dir)?
+            .into_iter()
+            .filter(|((x, z), _)| {
+                args.select
+                    .as_ref()
+                    .is_none_or(|select| select.region_overlap(*x, *z) != Overlap::None)
+            })
+            .collect();
+        if !files.is_empty() {
+            std::fs::create_dir_all(output)
+                .with_context(|| format!("Failed to create {}", output.display()))?;
+            jobs.push((output, files));
+        }
+    }
+    let total: usize = jobs.iter().map(|(_, files)| files.len()).sum();
+    if total == 0 {
+        bail!("No region files found in {}", args.path.display());
+    }
+
+    let progress = ProgressBar::new(total as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:.cyan}] {pos}/{len} ({percent}%) {msg}")?
+            .progress_chars("#>-"),
+    );
+
+    let tiles_written = AtomicU64::new(0);
+    let chunks_rendered = AtomicU64::new(0);
+    let chunks_unreadable = AtomicU64::new(0);
+    let errors = AtomicU64::new(0);
+
+    for (output, files) in &jobs {
+        let tiles: Vec<_> = files
+            .par_iter()
+            .filter_map(|((region_x, region_z), path)| {
+                let tile_path = output.join(format!("r.{}.{}.png", region_x, region_z));
+                let result = read_region(&registry, path).and_then(|region| {
+                    let map = render_region(&region, &colors);
+                    write_png(&tile_path, TILE_SIZE, TILE_SIZE, &map.pixels)?;
+                    Ok(map)
+                });
+                progress.inc(1);
+                match result {
+                    Ok(map) => {
+                        tiles_written.fetch_add(1, Ordering::Relaxed);
+                        chunks_rendered.fetch_add(map.chunks_rendered as u64, Ordering::Relaxed);
+                        chunks_unreadable
+                            .fetch_add(map.chunks_unreadable as u64, Ordering::Relaxed);
+                        args.overview.then(|| {
+                            let tile = downscale(&map.pixels, TILE_SIZE, args.overview_scale);
+                            ((*region_x, *region_z), tile)
+                        })
+                    }
+                    Err(e) => {
+                        progress.println(format!("Error rendering {}: {:#}", path.display(), e));
+                        errors.fetch_add(1, Ordering::Relaxed);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        if args.overview && !tiles.is_empty() {
+            let overview_path = output.join("overview.png");
+            if let Err(e) = write_overview(&overview_path, &tiles, args.overview_scale) {
+                eprintln!("Error writing {}: {:#}", overview_path.display(), e);
+                errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+    progress.finish_and_clear();
+
+    println!();
+    println!("Render Summary:");
+    println!("Tiles written: {}", tiles_written.load(Ordering::Relaxed));
+    println!(
+        "Chunks rendered: {}",
+        chunks_rendered.load(Ordering::Relaxed)
+    );
+    println!(
+        "Chunks that could not be rendered: {}",
+        chunks_unreadable.load(Ordering::Relaxed)
+    );
+    let errors = errors.load(Ordering::Relaxed);
+    println!("Errors: {}", errors);
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
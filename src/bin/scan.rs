@@ -0,0 +1,229 @@
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use linear_region_tools::{
+    format::FormatRegistry,
+    scan::{scan_region_file, ChunkRecord},
+    selection::{ChunkSelection, Overlap},
+    world::{find_region_directories, RegionKind},
+    Region,
+};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Parser)]
+#[command(
+    name = "scan",
+    about = "Write per-chunk metadata to CSV or JSON Lines.",
+    long_about = "Write one row per chunk of Anvil and Linear region files with its position, \
+                  timestamp, sizes, compression, DataVersion, Status, InhabitedTime and entity \
+                  and block entity counts"
+)]
+struct Args {
+    /// A region file, a region folder, or a world folder with --world
+    path: PathBuf,
+    /// File to write to [default: standard output]
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Output format [default: jsonl for a .jsonl or .json output, otherwise csv]
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+    /// Only list chunks in this selection
+    #[arg(short, long)]
+    select: Option<ChunkSelection>,
+    /// Scan the region folders of every dimension, counting entities from
+    /// their entities folders
+    #[arg(long)]
+    world: bool,
+    #[arg(short, long, default_value_t = num_cpus::get())]
+    threads: usize,
+}
+
+/// A region file and the entity file of the same region, if any.
+struct ScanJob {
+    dimension: Option<String>,
+    path: PathBuf,
+    entities: Option<PathBuf>,
+}
+
+enum RecordWriter {
+    Csv(Box<csv::Writer<Box<dyn Write + Send>>>),
+    Jsonl(BufWriter<Box<dyn Write + Send>>),
+}
+
+impl RecordWriter {
+    fn write(&mut self, record: &ChunkRecord) -> Result<()> {
+        match self {
+            RecordWriter::Csv(writer) => writer.serialize(record)?,
+            RecordWriter::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, record)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self {
+            RecordWriter::Csv(writer) => writer.flush()?,
+            RecordWriter::Jsonl(writer) => writer.flush()?,
+        }
+        Ok(())
+    }
+}
+
+fn collect_jobs(
+    registry: &FormatRegistry,
+    dir: &Path,
+    dimension: Option<&str>,
+    entities_dir: Option<&Path>,
+    jobs: &mut Vec<ScanJob>,
+) -> Result<()> {
+    let mut entities = match entities_dir {
+        Some(entities_dir) => registry.region_files(entities_dir)?,
+        None => Default::default(),
+    };
+    for (coords, path) in registry.region_files(dir)? {
+        jobs.push(ScanJob {
+            dimension: dimension.map(str::to_string),
+            path,
+            entities: entities.remove(&coords),
+        });
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .context("Failed to initialize thread pool")?;
+
+    let registry = FormatRegistry::default();
+    let mut jobs = Vec::new();
+    if args.world {
+        let mut dimensions: HashMap<String, HashMap<RegionKind, PathBuf>> = HashMap::new();
+        for directory in find_region_directories(&args.path)? {
+            dimensions
+                .entry(directory.dimension)
+                .or_default()
+                .insert(directory.kind, args.path.join(directory.relative_path));
+        }
+        let mut dimensions: Vec<_> = dimensions.into_iter().collect();
+        dimensions.sort_by(|a, b| a.0.cmp(&b.0));
+        for (dimension, dirs) in &dimensions {
+            if let Some(region_dir) = dirs.get(&RegionKind::Region) {
+                collect_jobs(
+                    &registry,
+                    region_dir,
+                    Some(dimension),
+                    dirs.get(&RegionKind::Entities).map(PathBuf::as_path),
+                    &mut jobs,
+                )?;
+            }
+        }
+    } else if args.path.is_file() {
+        jobs.push(ScanJob {
+            dimension: None,
+            path: args.path.clone(),
+            entities: None,
+        });
+    } else {
+        collect_jobs(&registry, &args.path, None, None, &mut jobs)?;
+    }
+
+    if let Some(select) = &args.select {
+        jobs.retain(|job| {
+            let filename = job.path.file_name().unwrap_or_default().to_string_lossy();
+            Region::parse_filename(&filename)
+                .is_ok_and(|(x, z)| select.region_overlap(x, z) != Overlap::None)
+        });
+    }
+    if jobs.is_empty() {
+        bail!("No region files found in {}", args.path.display());
+    }
+
+    let format = args.format.unwrap_or_else(|| {
+        let jsonl = args.output.as_ref().is_some_and(|output| {
+            output
+                .extension()
+                .is_some_and(|ext| ext == "jsonl" || ext == "json")
+        });
+        if jsonl {
+            OutputFormat::Jsonl
+        } else {
+            OutputFormat::Csv
+        }
+    });
+    let output: Box<dyn Write + Send> = match &args.output {
+        Some(path) => Box::new(
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?,
+        ),
+        None => Box::new(io::stdout()),
+    };
+    let writer = Mutex::new(match format {
+        OutputFormat::Csv => RecordWriter::Csv(Box::new(csv::Writer::from_writer(output))),
+        OutputFormat::Jsonl => RecordWriter::Jsonl(BufWriter::new(output)),
+    });
+
+    let progress = ProgressBar::new(jobs.len() as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:.cyan}] {pos}/{len} ({percent}%) {msg}")?
+            .progress_chars("#>-"),
+    );
+
+    let chunks = AtomicU64::new(0);
+    let errors = AtomicU64::new(0);
+    jobs.par_iter().for_each(|job| {
+        let result =
+            scan_region_file(&registry, &job.path, job.entities.as_deref()).and_then(|records| {
+                // Rows stay grouped by region file
+                let mut writer = writer.lock().unwrap();
+                for mut record in records {
+                    if args
+                        .select
+                        .as_ref()
+                        .is_some_and(|select| !select.contains(record.x, record.z))
+                    {
+                        continue;
+                    }
+                    record.dimension = job.dimension.clone();
+                    writer.write(&record)?;
+                    chunks.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(())
+            });
+        if let Err(e) = result {
+            progress.println(format!("Error scanning {}: {:#}", job.path.display(), e));
+            errors.fetch_add(1, Ordering::Relaxed);
+        }
+        progress.inc(1);
+    });
+    progress.finish_and_clear();
+    writer.into_inner().unwrap().flush()?;
+
+    eprintln!();
+    eprintln!("Scan Summary:");
+    eprintln!("Region files scanned: {}", jobs.len());
+    eprintln!("Chunks listed: {}", chunks.load(Ordering::Relaxed));
+    eprintln!("Errors: {}", errors.load(Ordering::Relaxed));
+
+    if errors.load(Ordering::Relaxed) > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
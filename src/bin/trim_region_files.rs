@@ -0,0 +1,292 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use linear_region_tools::{
+    format::{rewrite_region_file, FormatRegistry, RegionFormat, WriteOptions},
+    selection::{ChunkSelection, Overlap},
+    trim::{trim_region, TrimOptions, TrimReport},
+    world::{find_region_directories, RegionKind},
+    Region,
+};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Parser)]
+#[command(
+    name = "trim_region_files",
+    about = "Delete chunks that players spent little time in.",
+    long_about = "Remove every chunk whose InhabitedTime is below a threshold from Anvil and \
+                  Linear region files, keeping protected areas"
+)]
+struct Args {
+    /// A region folder, or a world folder with --world
+    path: PathBuf,
+    /// Remove chunks inhabited for fewer ticks than this (20 ticks = 1 second)
+    #[arg(short, long)]
+    min_inhabited: i64,
+    /// Chunk selection to keep, e.g. `chunks(-10, -10, 10, 10)`; may be repeated
+    #[arg(short, long)]
+    protect: Vec<ChunkSelection>,
+    /// File with one protected chunk selection per line; `#` starts a comment
+    #[arg(long)]
+    protect_file: Option<PathBuf>,
+    /// Only trim chunks in this selection
+    #[arg(short, long)]
+    select: Option<ChunkSelection>,
+    /// Only trim chunks in the selections listed in this file
+    #[arg(long, conflicts_with = "select")]
+    select_file: Option<PathBuf>,
+    /// Trim the region folders of every dimension, removing the same chunks
+    /// from the entities and poi folders
+    #[arg(long)]
+    world: bool,
+    #[arg(short, long, default_value_t = num_cpus::get())]
+    threads: usize,
+    #[arg(short, long, default_value_t = 6)]
+    compression_level: i32,
+    /// Report what would be removed without writing anything
+    #[arg(short, long)]
+    dry_run: bool,
+}
+
+/// A dimension's region folder and the entities and poi folders whose chunks
+/// follow it.
+struct TrimTarget {
+    region_dir: PathBuf,
+    companion_dirs: Vec<PathBuf>,
+}
+
+#[derive(Default)]
+struct TrimStats {
+    files: AtomicU64,
+    files_deleted: AtomicU64,
+    chunks_removed: AtomicU64,
+    chunks_kept: AtomicU64,
+    chunks_protected: AtomicU64,
+    chunks_unreadable: AtomicU64,
+    companion_chunks_removed: AtomicU64,
+    bytes_removed: AtomicU64,
+    bytes_saved: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl TrimStats {
+    fn add_report(&self, report: &TrimReport) {
+        self.files.fetch_add(1, Ordering::Relaxed);
+        self.chunks_removed
+            .fetch_add(report.removed.len() as u64, Ordering::Relaxed);
+        self.chunks_kept
+            .fetch_add(report.kept as u64, Ordering::Relaxed);
+        self.chunks_protected
+            .fetch_add(report.protected as u64, Ordering::Relaxed);
+        self.chunks_unreadable
+            .fetch_add(report.unreadable as u64, Ordering::Relaxed);
+        self.bytes_removed
+            .fetch_add(report.bytes_removed, Ordering::Relaxed);
+    }
+
+    fn add_rewritten(&self, region: &Region, bytes_freed: u64) {
+        if region.chunk_count() == 0 {
+            self.files_deleted.fetch_add(1, Ordering::Relaxed);
+        }
+        self.bytes_saved.fetch_add(bytes_freed, Ordering::Relaxed);
+    }
+}
+
+fn read_region<'a>(
+    registry: &'a FormatRegistry,
+    path: &Path,
+) -> Result<(Region, &'a dyn RegionFormat)> {
+    let Some(format) = registry.detect_file(path)? else {
+        bail!("{} is not a recognised region file", path.display());
+    };
+    let region = format
+        .read(path, None)
+        .with_context(|| format!("Failed to read {} as {}", path.display(), format.name()))?;
+    Ok((region, format))
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads)
+        .build_global()
+        .context("Failed to initialize thread pool")?;
+
+    let mut protected = args.protect.clone();
+    if let Some(protect_file) = &args.protect_file {
+        protected.push(ChunkSelection::from_file(protect_file)?);
+    }
+    let select = match &args.select_file {
+        Some(select_file) => Some(ChunkSelection::from_file(select_file)?),
+        None => args.select.clone(),
+    };
+    if let Some(select) = select {
+        protected.push(ChunkSelection::Not(Box::new(select)));
+    }
+    let options = TrimOptions {
+        min_inhabited_time: args.min_inhabited,
+        protected: ChunkSelection::union(protected),
+    };
+
+    let targets = if args.world {
+        let mut dimensions: BTreeMap<String, HashMap<RegionKind, PathBuf>> = BTreeMap::new();
+        for directory in find_region_directories(&args.path)? {
+            dimensions
+                .entry(directory.dimension)
+                .or_default()
+                .insert(directory.kind, args.path.join(directory.relative_path));
+        }
+        dimensions
+            .into_values()
+            .filter_map(|mut dirs| {
+                Some(TrimTarget {
+                    region_dir: dirs.remove(&RegionKind::Region)?,
+                    companion_dirs: dirs.into_values().collect(),
+                })
+            })
+            .collect()
+    } else {
+        vec![TrimTarget {
+            region_dir: args.path.clone(),
+            companion_dirs: Vec::new(),
+        }]
+    };
+
+    let registry = FormatRegistry::default();
+    let write_options = WriteOptions {
+        compression_level: args.compression_level,
+        ..Default::default()
+    };
+    let stats = TrimStats::default();
+
+    if args.dry_run {
+        println!("DRY RUN MODE - No files will be modified");
+    }
+
+    for target in &targets {
+        let files: Vec<_> = registry
+            .region_files(&target.region_dir)?
+            .into_iter()
+            .filter(|((x, z), _)| options.protected.region_overlap(*x, *z) != Overlap::Full)
+            .collect();
+        if files.is_empty() {
+            continue;
+        }
+
+        println!("Trimming {}", target.region_dir.display());
+        let progress = ProgressBar::new(files.len() as u64);
+        progress.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{bar:.cyan}] {pos}/{len} ({percent}%) {msg}")?
+                .progress_chars("#>-"),
+        );
+
+        let removed: HashMap<(i32, i32), Vec<usize>> = files
+            .par_iter()
+            .filter_map(|(coords, path)| {
+                let result = read_region(&registry, path).and_then(|(mut region, format)| {
+                    let report = trim_region(&mut region, &options);
+                    stats.add_report(&report);
+                    if !args.dry_run && !report.removed.is_empty() {
+                        let freed = rewrite_region_file(
+                            path,
+                            &region,
+                            format,
+                            &report.removed,
+                            &write_options,
+                        )?;
+                        stats.add_rewritten(&region, freed);
+                    }
+                    Ok(report.removed)
+                });
+                progress.inc(1);
+
+                match result {
+                    Ok(removed) if !removed.is_empty() => Some((*coords, removed)),
+                    Ok(_) => None,
+                    Err(e) => {
+                        progress.println(format!("Error trimming {}: {:#}", path.display(), e));
+                        stats.errors.fetch_add(1, Ordering::Relaxed);
+                        None
+                    }
+                }
+            })
+            .collect();
+        progress.finish();
+
+        if args.dry_run {
+            continue;
+        }
+
+        for companion_dir in &target.companion_dirs {
+            let companions: Vec<_> = registry
+                .region_files(companion_dir)?
+                .into_iter()
+                .filter(|(coords, _)| removed.contains_key(coords))
+                .collect();
+
+            companions.par_iter().for_each(|(coords, path)| {
+                let result = read_region(&registry, path).and_then(|(mut region, format)| {
+                    let indices: Vec<_> = removed[coords]
+                        .iter()
+                        .copied()
+                        .filter(|&index| region.get_chunk(index).is_some())
+                        .collect();
+                    if indices.is_empty() {
+                        return Ok(());
+                    }
+                    for &index in &indices {
+                        region.remove_chunk(index);
+                    }
+                    let freed =
+                        rewrite_region_file(path, &region, format, &indices, &write_options)?;
+                    stats
+                        .companion_chunks_removed
+                        .fetch_add(indices.len() as u64, Ordering::Relaxed);
+                    stats.add_rewritten(&region, freed);
+                    Ok(())
+                });
+                if let Err(e) = result {
+                    eprintln!("Error trimming {}: {:#}", path.display(), e);
+                    stats.errors.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+    }
+
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    println!();
+    println!("Trim Summary:");
+    println!("Region files processed: {}", load(&stats.files));
+    println!("Chunks removed: {}", load(&stats.chunks_removed));
+    println!(
+        "Chunks kept: {} ({} protected, {} without InhabitedTime)",
+        load(&stats.chunks_kept),
+        load(&stats.chunks_protected),
+        load(&stats.chunks_unreadable)
+    );
+    if args.world {
+        println!(
+            "Entity and POI chunks removed: {}",
+            load(&stats.companion_chunks_removed)
+        );
+    }
+    println!("Region files deleted: {}", load(&stats.files_deleted));
+    println!(
+        "Chunk data removed: {:.1} MiB uncompressed",
+        load(&stats.bytes_removed) as f64 / (1024.0 * 1024.0)
+    );
+    if !args.dry_run {
+        println!(
+            "Disk space saved: {:.1} MiB",
+            load(&stats.bytes_saved) as f64 / (1024.0 * 1024.0)
+        );
+    }
+    println!("Errors: {}", load(&stats.errors));
+
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use crate::{Region, CHUNKS_PER_REGION, REGION_DIMENSION};
+use fastnbt::Value;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkDiffKind {
+    /// Present only in the new region.
+    Added,
+    /// Present only in the old region.
+    Removed,
+    /// The payload differs. `changed_tags` lists the differing NBT paths when
+    /// the diff is NBT-aware and both chunks parse, and is `None` otherwise.
+    Changed { changed_tags: Option<Vec<String>> },
+    /// Same payload, different timestamp.
+    Touched,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChunkDiff {
+    pub index: usize,
+    pub x: i32,
+    pub z: i32,
+    /// 0 for added chunks
+    pub old_timestamp: u32,
+    /// 0 for removed chunks
+    pub new_timestamp: u32,
+    pub kind: ChunkDiffKind,
+}
+
+impl ChunkDiff {
+    /// Seconds between the old and new timestamps, 0 unless the chunk is in both regions.
+    pub fn timestamp_delta(&self) -> i64 {
+        match self.kind {
+            ChunkDiffKind::Added | ChunkDiffKind::Removed => 0,
+            _ => self.new_timestamp as i64 - self.old_timestamp as i64,
+        }
+    }
+}
+
+impl fmt::Display for ChunkDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "chunk ({}, {}) ", self.x, self.z)?;
+        match &self.kind {
+            ChunkDiffKind::Added => write!(f, "added"),
+            ChunkDiffKind::Removed => write!(f, "removed"),
+            ChunkDiffKind::Changed { changed_tags } => {
+                write!(f, "changed, timestamp {:+}s", self.timestamp_delta())?;
+                match changed_tags {
+                    Some(tags) => write!(f, ": {}", tags.join(", ")),
+                    None => Ok(()),
+                }
+            }
+            ChunkDiffKind::Touched => {
+                write!(f, "unchanged, timestamp {:+}s", self.timestamp_delta())
+            }
+        }
+    }
+}
+
+fn push_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn diff_value(old: &Value, new: &Value, path: String, depth: usize, changed: &mut Vec<String>) {
+    if old == new {
+        return;
+    }
+    if depth == 0 {
+        changed.push(path);
+        return;
+    }
+
+    match (old, new) {
+        (Value::Compound(old), Value::Compound(new)) => {
+            let mut keys: Vec<_> = old.keys().chain(new.keys()).collect();
+            keys.sort_unstable();
+            keys.dedup();
+            for key in keys {
+                let key_path = push_path(&path, key);
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_value(old, new, key_path, depth - 1, changed),
+                    _ => changed.push(key_path),
+                }
+            }
+        }
+        // Element by element only for lists of compounds that kept their length,
+        // such as `sections`; anything else is reported as a whole. The index
+        // belongs to the list's level, so it does not use up depth
+        (Value::List(old), Value::List(new))
+            if old.len() == new.len() && old.iter().all(|v| matches!(v, Value::Compound(_))) =>
+        {
+            for (i, (old, new)) in old.iter().zip(new).enumerate() {
+                diff_value(old, new, format!("{}[{}]", path, i), depth, changed);
+            }
+        }
+        _ => changed.push(path),
+    }
+}
+
+/// Paths of the tags that differ between two NBT values, e.g. `Entities` or
+/// `sections[3].block_states.palette`, sorted by key. Compounds and
+/// same-length lists of compounds are descended into at most `max_depth`
+/// levels; deeper differences are reported at that depth.
+pub fn diff_nbt(old: &Value, new: &Value, max_depth: usize) -> Vec<String> {
+    let mut changed = Vec::new();
+    diff_value(old, new, String::new(), max_depth, &mut changed);
+    changed
+}
+
+/// Lists the chunks added, removed or changed between `old` and `new`, which
+/// may have been read from different formats. With `nbt_depth`, chunks are
+/// compared as NBT: payloads that differ only in encoding count as unchanged,
+/// and changed chunks list their differing tags via [`diff_nbt`].
+pub fn diff_regions(old: &Region, new: &Region, nbt_depth: Option<usize>) -> Vec<ChunkDiff> {
+    let mut diffs = Vec::new();
+
+    for index in 0..CHUNKS_PER_REGION {
+        let (old_chunk, new_chunk) = (old.get_chunk(index), new.get_chunk(index));
+        if old_chunk.is_none() && new_chunk.is_none() {
+            continue;
+        }
+        let (old_timestamp, new_timestamp) = (old.timestamps[index], new.timestamps[index]);
+
+        let kind = match (old_chunk, new_chunk) {
+            (Some(_), None) => ChunkDiffKind::Removed,
+            (None, Some(_)) => ChunkDiffKind::Added,
+            (Some(old_chunk), Some(new_chunk)) => {
+                let changed = if old_chunk.as_slice() == new_chunk.as_slice() {
+                    Some(Vec::new())
+                } else {
+                    nbt_depth.and_then(|depth| {
+                        let old_nbt = old_chunk.parse_nbt().ok()?;
+                        let new_nbt = new_chunk.parse_nbt().ok()?;
+                        Some(diff_nbt(&old_nbt, &new_nbt, depth))
+                    })
+                };
+                match changed {
+                    Some(tags) if tags.is_empty() => {
+                        if old_timestamp == new_timestamp {
+                            continue;
+                        }
+                        ChunkDiffKind::Touched
+                    }
+                    changed_tags => ChunkDiffKind::Changed { changed_tags },
+                }
+            }
+            (None, None) => unreachable!(),
+        };
+
+        diffs.push(ChunkDiff {
+            index,
+            x: old.region_x * REGION_DIMENSION as i32 + (index % REGION_DIMENSION) as i32,
+            z: old.region_z * REGION_DIMENSION as i32 + (index / REGION_DIMENSION) as i32,
+            old_timestamp: old_chunk.map_or(0, |_| old_timestamp),
+            new_timestamp: new_chunk.map_or(0, |_| new_timestamp),
+            kind,
+        });
+    }
+
+    diffs
+}
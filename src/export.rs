@@ -0,0 +1,101 @@
+use crate::{
+    format::{FormatRegistry, RegionFormat, WriteOptions},
+    selection::{ChunkSelection, Overlap},
+    world::{find_region_directories, RegionKind},
+};
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files of the world root copied so the export opens as a world of its own.
+pub const WORLD_FILES: [&str; 2] = ["level.dat", "icon.png"];
+
+/// One source region file and where its selected chunks go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportJob {
+    pub source: PathBuf,
+    pub output_dir: PathBuf,
+    pub kind: RegionKind,
+}
+
+/// The region, entities and poi files of every dimension of `world` that
+/// overlap `selection`, each paired with its folder under `output`. A
+/// non-empty `dimensions` limits the export to those dimension ids.
+pub fn plan_export(
+    world: &Path,
+    output: &Path,
+    registry: &FormatRegistry,
+    selection: &ChunkSelection,
+    dimensions: &[String],
+) -> Result<Vec<ExportJob>> {
+    let mut jobs = Vec::new();
+    for directory in find_region_directories(world)? {
+        if !dimensions.is_empty() && !dimensions.contains(&directory.dimension) {
+            continue;
+        }
+        let output_dir = output.join(&directory.relative_path);
+        for ((x, z), source) in registry.region_files(&world.join(&directory.relative_path))? {
+            if selection.region_overlap(x, z) != Overlap::None {
+                jobs.push(ExportJob {
+                    source,
+                    output_dir: output_dir.clone(),
+                    kind: directory.kind,
+                });
+            }
+        }
+    }
+    Ok(jobs)
+}
+
+/// Creates `output` with the folders `jobs` write to, and copies the
+/// [`WORLD_FILES`] that `world` has.
+pub fn prepare_output(world: &Path, output: &Path, jobs: &[ExportJob]) -> Result<()> {
+    fs::create_dir_all(output)?;
+    for name in WORLD_FILES {
+        let source = world.join(name);
+        if source.is_file() {
+            fs::copy(&source, output.join(name))
+                .with_context(|| format!("Failed to copy {}", source.display()))?;
+        }
+    }
+    for job in jobs {
+        fs::create_dir_all(&job.output_dir)?;
+    }
+    Ok(())
+}
+
+/// Writes the chunks of `job.source` that are in `selection` into
+/// `job.output_dir`, in `output_format` or else the source's own format, and
+/// returns how many there were. Nothing is written when none are selected.
+pub fn export_region_file(
+    job: &ExportJob,
+    registry: &FormatRegistry,
+    output_format: Option<&dyn RegionFormat>,
+    selection: &ChunkSelection,
+    write_options: &WriteOptions,
+) -> Result<usize> {
+    let Some(source_format) = registry.detect_file(&job.source)? else {
+        bail!("{} is not a recognised region file", job.source.display());
+    };
+    let mut region = source_format.read(&job.source, None).with_context(|| {
+        format!(
+            "Failed to read {} as {}",
+            job.source.display(),
+            source_format.name()
+        )
+    })?;
+    region.retain_chunks(|chunk| selection.contains(chunk.x, chunk.z));
+    if region.chunk_count() == 0 {
+        return Ok(0);
+    }
+
+    let format = output_format.unwrap_or(source_format);
+    let output_path = job.output_dir.join(format!(
+        "r.{}.{}.{}",
+        region.region_x,
+        region.region_z,
+        format.extension()
+    ));
+    format.write(&output_path, &region, write_options, None)?;
+    Ok(region.chunk_count())
+}
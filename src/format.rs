@@ -0,0 +1,464 @@
+use crate::{
+    anvil::{self, AnvilWriteOptions, ChunkCompression},
+    linear::{self, LinearVersion},
+    PerformanceCounters, ReadMode, ReadReport, Region, LINEAR_SIGNATURE,
+    LINEAR_V2_DEFAULT_GRID_SIZE, LINEAR_VERSION_V1, LINEAR_VERSION_V2, REGION_DIMENSION,
+    SECTOR_SIZE,
+};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// What [`detect_region_format`] found in a file, with the header fields it read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectedFormat {
+    Anvil {
+        chunk_count: usize,
+        /// Location entries that point past the end of the file
+        out_of_bounds_chunks: usize,
+    },
+    LinearV1 {
+        newest_timestamp: u64,
+        compression_level: i8,
+        chunk_count: u16,
+        compressed_size: u32,
+    },
+    LinearV2 {
+        newest_timestamp: u64,
+        grid_size: u8,
+        region_x: i32,
+        region_z: i32,
+    },
+    Unknown,
+}
+
+impl DetectedFormat {
+    /// Name of the built-in [`RegionFormat`] that reads this file.
+    pub fn format_name(&self) -> Option<&'static str> {
+        match self {
+            DetectedFormat::Anvil { .. } => Some("mca"),
+            DetectedFormat::LinearV1 { .. } => Some("linearv1"),
+            DetectedFormat::LinearV2 { .. } => Some("linearv2"),
+            DetectedFormat::Unknown => None,
+        }
+    }
+
+    /// Linear format version; `None` for Anvil and unknown files.
+    pub fn version(&self) -> Option<u8> {
+        match self {
+            DetectedFormat::LinearV1 { .. } => Some(LINEAR_VERSION_V1),
+            DetectedFormat::LinearV2 { .. } => Some(LINEAR_VERSION_V2),
+            _ => None,
+        }
+    }
+}
+
+fn sniff_region_format(header: &[u8], file_len: u64) -> DetectedFormat {
+    if header.len() >= 8 && u64::from_be_bytes(header[..8].try_into().unwrap()) == LINEAR_SIGNATURE
+    {
+        linear::detect_linear(header)
+    } else {
+        anvil::detect_anvil(header, file_len)
+    }
+}
+
+/// Identifies a region file by its contents rather than its extension.
+pub fn detect_region_format<P: AsRef<Path>>(path: P) -> Result<DetectedFormat> {
+    let file = File::open(path.as_ref())?;
+    let file_len = file.metadata()?.len();
+    let mut header = Vec::with_capacity(SECTOR_SIZE * 2);
+    file.take((SECTOR_SIZE * 2) as u64)
+        .read_to_end(&mut header)?;
+    Ok(sniff_region_format(&header, file_len))
+}
+
+/// [`detect_region_format`] for a file already in memory.
+pub fn detect_region_format_from_bytes(data: &[u8]) -> DetectedFormat {
+    sniff_region_format(&data[..data.len().min(SECTOR_SIZE * 2)], data.len() as u64)
+}
+
+/// Writer settings shared by every format. Each format uses the fields that apply to it.
+#[derive(Debug, Clone, Copy)]
+pub struct WriteOptions {
+    /// zlib/gzip level (0-9) for Anvil, zstd level for Linear
+    pub compression_level: i32,
+    pub anvil_compression: ChunkCompression,
+    pub grid_size: u8,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            compression_level: 6,
+            anvil_compression: ChunkCompression::Zlib,
+            grid_size: LINEAR_V2_DEFAULT_GRID_SIZE,
+        }
+    }
+}
+
+/// A region file format that can take part in conversions.
+pub trait RegionFormat: Send + Sync {
+    /// Identifier used on the command line, e.g. `mca` or `linearv2`
+    fn name(&self) -> &'static str;
+
+    /// File extension without the leading dot
+    fn extension(&self) -> &'static str;
+
+    /// Whether `data`, the contents of a file, is in this format.
+    fn detect(&self, data: &[u8]) -> bool;
+
+    fn read(&self, path: &Path, counters: Option<Arc<PerformanceCounters>>) -> Result<Region>;
+
+    /// Like [`RegionFormat::read`], reporting damaged chunks. Formats without
+    /// per-chunk recovery read normally and return a clean report.
+    fn read_with_report(
+        &self,
+        path: &Path,
+        _mode: ReadMode,
+        counters: Option<Arc<PerformanceCounters>>,
+    ) -> Result<(Region, ReadReport)> {
+        let region = self.read(path, counters)?;
+        let report = ReadReport {
+            chunks_loaded: region.chunk_count(),
+            ..Default::default()
+        };
+        Ok((region, report))
+    }
+
+    fn write(
+        &self,
+        path: &Path,
+        region: &Region,
+        options: &WriteOptions,
+        counters: Option<Arc<PerformanceCounters>>,
+    ) -> Result<()>;
+}
+
+pub struct AnvilFormat;
+
+impl RegionFormat for AnvilFormat {
+    fn name(&self) -> &'static str {
+        "mca"
+    }
+
+    fn extension(&self) -> &'static str {
+        "mca"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        matches!(
+            detect_region_format_from_bytes(data),
+            DetectedFormat::Anvil { .. }
+        )
+    }
+
+    fn read(&self, path: &Path, counters: Option<Arc<PerformanceCounters>>) -> Result<Region> {
+        anvil::read_anvil_region(path, counters)
+    }
+
+    fn read_with_report(
+        &self,
+        path: &Path,
+        mode: ReadMode,
+        counters: Option<Arc<PerformanceCounters>>,
+    ) -> Result<(Region, ReadReport)> {
+        anvil::read_anvil_region_with_report(path, mode, counters)
+    }
+
+    fn write(
+        &self,
+        path: &Path,
+        region: &Region,
+        options: &WriteOptions,
+        counters: Option<Arc<PerformanceCounters>>,
+    ) -> Result<()> {
+        let anvil_options = AnvilWriteOptions::new(
+            options.anvil_compression,
+            options.compression_level.clamp(0, 9) as u32,
+        );
+        anvil::write_anvil_region_with_options(path, region, &anvil_options, counters)
+    }
+}
+
+pub struct LinearFormat(pub LinearVersion);
+
+impl RegionFormat for LinearFormat {
+    fn name(&self) -> &'static str {
+        match self.0 {
+            LinearVersion::V1 => "linearv1",
+            LinearVersion::V2 => "linearv2",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        "linear"
+    }
+
+    fn detect(&self, data: &[u8]) -> bool {
+        detect_region_format_from_bytes(data).format_name() == Some(self.name())
+    }
+
+    fn read(&self, path: &Path, counters: Option<Arc<PerformanceCounters>>) -> Result<Region> {
+        linear::read_linear_region(path, counters)
+    }
+
+    fn write(
+        &self,
+        path: &Path,
+        region: &Region,
+        options: &WriteOptions,
+        counters: Option<Arc<PerformanceCounters>>,
+    ) -> Result<()> {
+        linear::write_linear_region_with_grid(
+            path,
+            region,
+            options.compression_level,
+            self.0,
+            options.grid_size,
+            counters,
+        )
+    }
+}
+
+/// The set of formats available to a conversion, looked up by name.
+pub struct FormatRegistry {
+    formats: Vec<Box<dyn RegionFormat>>,
+}
+
+impl FormatRegistry {
+    /// An empty registry; see [`FormatRegistry::default`] for the built-in formats.
+    pub fn new() -> Self {
+        Self {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Adds `format`, replacing any registered format with the same name.
+    pub fn register(&mut self, format: Box<dyn RegionFormat>) {
+        self.formats.retain(|f| f.name() != format.name());
+        self.formats.push(format);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn RegionFormat> {
+        self.formats
+            .iter()
+            .find(|f| f.name().eq_ignore_ascii_case(name))
+            .map(|f| f.as_ref())
+    }
+
+    pub fn formats(&self) -> impl Iterator<Item = &dyn RegionFormat> {
+        self.formats.iter().map(|f| f.as_ref())
+    }
+
+    /// The first registered format whose [`RegionFormat::detect`] accepts `data`.
+    pub fn detect(&self, data: &[u8]) -> Option<&dyn RegionFormat> {
+        self.formats().find(|f| f.detect(data))
+    }
+
+    /// The format of the file at `path`, identified from its header by
+    /// [`detect_region_format`] and otherwise by [`FormatRegistry::detect`].
+    /// Files too short to hold an Anvil header, such as the empty `.mca` files
+    /// vanilla leaves behind, fall back to the format registered for their extension.
+    pub fn detect_file(&self, path: &Path) -> Result<Option<&dyn RegionFormat>> {
+        let detected = detect_region_format(path)?;
+        if let Some(format) = detected.format_name().and_then(|name| self.get(name)) {
+            return Ok(Some(format));
+        }
+        let data = std::fs::read(path)?;
+        if let Some(format) = self.detect(&data) {
+            return Ok(Some(format));
+        }
+        if data.len() < SECTOR_SIZE * 2
+            && let Some(ext) = path.extension()
+        {
+            return Ok(self.formats().find(|f| ext == f.extension()));
+        }
+        Ok(None)
+    }
+
+    /// The `r.<x>.<z>.<ext>` files in `dir` with a registered extension, keyed
+    /// by region coordinates. A missing `dir` has no files; two files for the
+    /// same region, e.g. `r.0.0.mca` and `r.0.0.linear`, are an error.
+    pub fn region_files(&self, dir: &Path) -> Result<BTreeMap<(i32, i32), PathBuf>> {
+        let mut files = BTreeMap::new();
+        if !dir.is_dir() {
+            return Ok(files);
+        }
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let filename = path.file_name().unwrap_or_default().to_string_lossy();
+            let known_extension = path
+                .extension()
+                .is_some_and(|ext| self.formats().any(|f| ext == f.extension()));
+            if !path.is_file() || !filename.starts_with("r.") || !known_extension {
+                continue;
+            }
+            if let Ok(coords) = Region::parse_filename(&filename)
+                && let Some(other) = files.insert(coords, path.clone())
+            {
+                bail!(
+                    "{} and {} both hold region ({}, {})",
+                    other.display(),
+                    path.display(),
+                    coords.0,
+                    coords.1
+                );
+            }
+        }
+        Ok(files)
+    }
+
+    /// Resolves a conversion mode such as `mca2linearv2` or `linearv12linearv2`
+    /// into its source and destination formats.
+    pub fn parse_conversion(&self, mode: &str) -> Option<(&dyn RegionFormat, &dyn RegionFormat)> {
+        // Historical spellings that share the `2` with the version number
+        let mode = match mode.to_ascii_lowercase().as_str() {
+            "linearv2mca" => "linearv22mca".to_string(),
+            other => other.to_string(),
+        };
+
+        for from in self.formats() {
+            let Some(rest) = mode.strip_prefix(from.name()) else {
+                continue;
+            };
+            let Some(to_name) = rest.strip_prefix('2') else {
+                continue;
+            };
+            if let Some(to) = self.get(to_name) {
+                return Some((from, to));
+            }
+        }
+        None
+    }
+}
+
+impl Default for FormatRegistry {
+    /// Anvil, LinearV1 and LinearV2.
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(AnvilFormat));
+        registry.register(Box::new(LinearFormat(LinearVersion::V1)));
+        registry.register(Box::new(LinearFormat(LinearVersion::V2)));
+        registry
+    }
+}
+
+/// Reads `source_path` as `from` and writes it to `dest_path` as `to`.
+pub fn convert_region_file(
+    from: &dyn RegionFormat,
+    to: &dyn RegionFormat,
+    source_path: &Path,
+    dest_path: &Path,
+    options: &WriteOptions,
+) -> Result<Region> {
+    let region = from.read(source_path, None).with_context(|| {
+        format!(
+            "Failed to read {} as {}",
+            source_path.display(),
+            from.name()
+        )
+    })?;
+    to.write(dest_path, &region, options, None)?;
+    Ok(region)
+}
+
+/// Writes `region` back over `path` in `format` after the chunks at `removed`
+/// were taken out of it, and returns the bytes freed on disk. The file is
+/// deleted once no chunks are left and a LinearV2 file keeps its grid size.
+/// Anvil files are updated in place: removed chunks and their `.mcc` files are
+/// deleted, and only changed chunks are rewritten, in the compression they had.
+pub fn rewrite_region_file(
+    path: &Path,
+    region: &Region,
+    format: &dyn RegionFormat,
+    removed: &[usize],
+    options: &WriteOptions,
+) -> Result<u64> {
+    let size_before = std::fs::metadata(path)?.len();
+    let detected = detect_region_format(path)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let external: Vec<_> = if matches!(detected, DetectedFormat::Anvil { .. }) {
+        removed
+            .iter()
+            .map(|&index| {
+                let x =
+                    region.region_x * REGION_DIMENSION as i32 + (index % REGION_DIMENSION) as i32;
+                let z =
+                    region.region_z * REGION_DIMENSION as i32 + (index / REGION_DIMENSION) as i32;
+                dir.join(format!("c.{}.{}.mcc", x, z))
+            })
+            .filter(|path| path.is_file())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut freed = 0;
+    for file in &external {
+        freed += std::fs::metadata(file)?.len();
+    }
+
+    if region.chunk_count() == 0 {
+        std::fs::remove_file(path)?;
+        for file in &external {
+            std::fs::remove_file(file)?;
+        }
+        return Ok(freed + size_before);
+    }
+
+    match detected {
+        DetectedFormat::Anvil { .. } => update_anvil_file(path, region, removed, options)?,
+        DetectedFormat::LinearV2 { grid_size, .. } => {
+            let options = WriteOptions {
+                grid_size,
+                ..*options
+            };
+            format.write(path, region, &options, None)?;
+        }
+        _ => format.write(path, region, options, None)?,
+    }
+    Ok(freed + size_before.saturating_sub(std::fs::metadata(path)?.len()))
+}
+
+/// Brings the Anvil file at `path` in line with `region` without rewriting
+/// the chunks that did not change. New chunks use `options.anvil_compression`.
+fn update_anvil_file(
+    path: &Path,
+    region: &Region,
+    removed: &[usize],
+    options: &WriteOptions,
+) -> Result<()> {
+    let storage = anvil::read_anvil_chunk_storage(path)?;
+    let mut file = anvil::AnvilRegionFile::open(path)?;
+    for &index in removed {
+        if region.get_chunk(index).is_none() {
+            file.delete_chunk(index)?;
+        }
+    }
+
+    for (index, slot) in storage.iter().enumerate() {
+        let Some(chunk) = region.get_chunk(index) else {
+            continue;
+        };
+        let timestamp = region.timestamps[index];
+        let unchanged = file.timestamp(index) == Some(timestamp)
+            && file
+                .read_chunk(index)?
+                .is_some_and(|stored| stored.as_slice() == chunk.as_slice());
+        if unchanged {
+            continue;
+        }
+
+        let compression = slot
+            .and_then(|slot| ChunkCompression::from_u8(slot.compression_type))
+            .unwrap_or(options.anvil_compression);
+        file.set_write_options(AnvilWriteOptions::new(
+            compression,
+            options.compression_level.clamp(0, 9) as u32,
+        ));
+        file.write_chunk(index, chunk.as_slice(), timestamp)?;
+    }
+    file.flush()
+}
@@ -0,0 +1,106 @@
+use crate::nbt;
+use anyhow::{Context, Result};
+use fastnbt::Value;
+use std::collections::HashMap;
+
+/// Blocks in one Y layer of a section.
+const LAYER_AREA: u64 = 256;
+
+/// Number of blocks of each type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockHistogram {
+    pub counts: HashMap<String, u64>,
+}
+
+impl BlockHistogram {
+    pub fn add(&mut self, block: &str, count: u64) {
+        match self.counts.get_mut(block) {
+            Some(total) => *total += count,
+            None => {
+                self.counts.insert(block.to_string(), count);
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &BlockHistogram) {
+        for (block, &count) in &other.counts {
+            self.add(block, count);
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Block names and counts, most common first.
+    pub fn sorted(&self) -> Vec<(&str, u64)> {
+        let mut entries: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(block, &count)| (block.as_str(), count))
+            .collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        entries
+    }
+}
+
+/// Counts how often each palette entry occurs in every Y layer of a section.
+fn count_layers<T>(blocks: &nbt::PalettedValues<T>) -> Vec<[u64; 16]> {
+    let mut counts = vec![[0u64; 16]; blocks.palette.len()];
+    for (i, &index) in blocks.indices.iter().enumerate() {
+        counts[index as usize][i >> 8] += 1;
+    }
+    counts
+}
+
+/// Calls `f` with the block Y, block name and count of every block in
+/// `chunk`, one Y layer at a time. Returns the number of sections read;
+/// sections without a palette, as in pre-1.13 chunks, are skipped.
+pub fn count_chunk_blocks<F>(chunk: &Value, mut f: F) -> Result<usize>
+where
+    F: FnMut(i32, &str, u64),
+{
+    let Some(sections) = nbt::chunk_sections(chunk) else {
+        return Ok(0);
+    };
+    let layout = nbt::PackingLayout::for_data_version(nbt::data_version(chunk));
+
+    let mut counted = 0;
+    for section in sections {
+        let (Some(y), Some((palette, data))) =
+            (nbt::section_y(section), nbt::section_block_states(section))
+        else {
+            continue;
+        };
+        let names: Vec<&str> = palette
+            .iter()
+            .map(|entry| nbt::block_name(entry).unwrap_or("unknown"))
+            .collect();
+        let blocks = nbt::PalettedValues::unpack(
+            names,
+            data,
+            nbt::SECTION_BLOCKS,
+            nbt::BLOCK_STATE_MIN_BITS,
+            layout,
+        )
+        .with_context(|| format!("Invalid block states in section {}", y))?;
+        let min_y = y * 16;
+
+        if let [name] = blocks.palette[..] {
+            for layer in 0..16 {
+                f(min_y + layer, name, LAYER_AREA);
+            }
+        } else {
+            let counts = count_layers(&blocks);
+            for (name, layers) in blocks.palette.iter().zip(&counts) {
+                for (layer, &count) in layers.iter().enumerate() {
+                    if count > 0 {
+                        f(min_y + layer as i32, name, count);
+                    }
+                }
+            }
+        }
+        counted += 1;
+    }
+    Ok(counted)
+}
@@ -0,0 +1,186 @@
+use crate::io_utils;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use xxhash_rust::xxh64::xxh64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    InProgress,
+    Completed,
+    Failed,
+}
+
+impl JobStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::InProgress => "in-progress",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "in-progress" => Some(JobStatus::InProgress),
+            "completed" => Some(JobStatus::Completed),
+            "failed" => Some(JobStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Latest journal record for one source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub status: JobStatus,
+    pub source_size: u64,
+    /// Source modification time, seconds since the Unix epoch
+    pub source_mtime: u64,
+    /// xxh64 of the written file; 0 unless completed
+    pub output_hash: u64,
+}
+
+/// Append-only record of a conversion run, so an interrupted run can be resumed.
+///
+/// Each state change is one tab-separated line, flushed immediately:
+/// `status, source size, source mtime, output hash, source path`. The last
+/// line for a source wins, and a line cut short by a crash is ignored.
+pub struct Journal {
+    entries: Mutex<HashMap<PathBuf, JournalEntry>>,
+    file: Mutex<File>,
+    /// A second handle to the journal, so syncs happen outside the write lock
+    sync_file: File,
+}
+
+fn source_metadata(source: &Path) -> Result<(u64, u64)> {
+    let metadata = std::fs::metadata(source)?;
+    let mtime = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_secs();
+    Ok((metadata.len(), mtime))
+}
+
+fn hash_file(path: &Path) -> Result<u64> {
+    if std::fs::metadata(path)?.len() == 0 {
+        return Ok(xxh64(&[], 0));
+    }
+    Ok(xxh64(&io_utils::mmap_file(path)?, 0))
+}
+
+fn parse_line(line: &str) -> Option<(PathBuf, JournalEntry)> {
+    let mut fields = line.splitn(5, '\t');
+    let status = JobStatus::parse(fields.next()?)?;
+    let source_size = fields.next()?.parse().ok()?;
+    let source_mtime = fields.next()?.parse().ok()?;
+    let output_hash = u64::from_str_radix(fields.next()?, 16).ok()?;
+    let source = PathBuf::from(fields.next()?);
+    Some((
+        source,
+        JournalEntry {
+            status,
+            source_size,
+            source_mtime,
+            output_hash,
+        },
+    ))
+}
+
+impl Journal {
+    /// Opens the journal at `path`, loading its records when `resume` is set
+    /// and starting a new one otherwise.
+    pub fn open<P: AsRef<Path>>(path: P, resume: bool) -> Result<Self> {
+        let path = path.as_ref();
+        let mut entries = HashMap::new();
+        let mut partial_line = false;
+
+        if resume && path.exists() {
+            let contents = std::fs::read_to_string(path)?;
+            partial_line = !contents.is_empty() && !contents.ends_with('\n');
+            let complete = &contents[..contents.rfind('\n').map_or(0, |i| i + 1)];
+            for line in complete.lines() {
+                if let Some((source, entry)) = parse_line(line) {
+                    entries.insert(source, entry);
+                }
+            }
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("Failed to open journal {}", path.display()))?;
+        if !resume {
+            file.set_len(0)?;
+        } else if partial_line {
+            // Terminate the line a crash cut short so the next record starts clean
+            file.write_all(b"\n")?;
+        }
+
+        Ok(Self {
+            entries: Mutex::new(entries),
+            sync_file: file.try_clone()?,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn entry<P: AsRef<Path>>(&self, source: P) -> Option<JournalEntry> {
+        let key = std::fs::canonicalize(source).ok()?;
+        self.entries.lock().unwrap().get(&key).cloned()
+    }
+
+    /// Whether `source` was converted to `dest` and neither has changed since:
+    /// the source still has the recorded size and mtime, and `dest` still has
+    /// the recorded hash.
+    pub fn is_complete<P: AsRef<Path>, Q: AsRef<Path>>(&self, source: P, dest: Q) -> bool {
+        let Some(entry) = self.entry(&source) else {
+            return false;
+        };
+        entry.status == JobStatus::Completed
+            && source_metadata(source.as_ref()).ok()
+                == Some((entry.source_size, entry.source_mtime))
+            && hash_file(dest.as_ref()).ok() == Some(entry.output_hash)
+    }
+
+    pub fn mark_in_progress<P: AsRef<Path>>(&self, source: P) -> Result<()> {
+        self.record(source.as_ref(), JobStatus::InProgress, 0)
+    }
+
+    pub fn mark_failed<P: AsRef<Path>>(&self, source: P) -> Result<()> {
+        self.record(source.as_ref(), JobStatus::Failed, 0)
+    }
+
+    pub fn mark_completed<P: AsRef<Path>, Q: AsRef<Path>>(&self, source: P, dest: Q) -> Result<()> {
+        let output_hash = hash_file(dest.as_ref())?;
+        self.record(source.as_ref(), JobStatus::Completed, output_hash)
+    }
+
+    fn record(&self, source: &Path, status: JobStatus, output_hash: u64) -> Result<()> {
+        let key = std::fs::canonicalize(source)?;
+        let (source_size, source_mtime) = source_metadata(source)?;
+        let entry = JournalEntry {
+            status,
+            source_size,
+            source_mtime,
+            output_hash,
+        };
+
+        let line = format!(
+            "{}\t{}\t{}\t{:016x}\t{}\n",
+            status.as_str(),
+            source_size,
+            source_mtime,
+            output_hash,
+            key.display()
+        );
+        self.file.lock().unwrap().write_all(line.as_bytes())?;
+        // Concurrent records share a sync instead of queueing behind each other's
+        self.sync_file.sync_data()?;
+
+        self.entries.lock().unwrap().insert(key, entry);
+        Ok(())
+    }
+}
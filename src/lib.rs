@@ -2,16 +2,31 @@ use anyhow::{Context, Result};
 use memmap2::Mmap;
 use smallvec::SmallVec;
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 pub mod anvil;
+pub mod diff;
+pub mod export;
+pub mod format;
+pub mod histogram;
+pub mod journal;
 pub mod linear;
+pub mod merge;
 pub mod nbt;
+pub mod relocate;
+pub mod render;
+pub mod scan;
+pub mod selection;
+pub mod snbt;
+pub mod trim;
+pub mod verify;
+pub mod world;
 
 pub const REGION_DIMENSION: usize = 32;
 pub const CHUNKS_PER_REGION: usize = REGION_DIMENSION * REGION_DIMENSION;
@@ -19,11 +34,13 @@ pub const SECTOR_SIZE: usize = 4096;
 pub const LINEAR_SIGNATURE: u64 = 0xc3ff13183cca9d9a;
 pub const LINEAR_VERSION_V1: u8 = 1;
 pub const LINEAR_VERSION_V2: u8 = 2;
+pub const LINEAR_V2_DEFAULT_GRID_SIZE: u8 = 8;
 pub const COMPRESSION_TYPE_GZIP: u8 = 1;
 pub const COMPRESSION_TYPE_ZLIB: u8 = 2;
 pub const COMPRESSION_TYPE_NONE: u8 = 3;
 pub const COMPRESSION_TYPE_LZ4: u8 = 4;
-pub const EXTERNAL_FILE_COMPRESSION_TYPE: u8 = 128 + 2;
+pub const EXTERNAL_FILE_FLAG: u8 = 128;
+pub const EXTERNAL_FILE_COMPRESSION_TYPE: u8 = EXTERNAL_FILE_FLAG + COMPRESSION_TYPE_ZLIB;
 type ChunkData = SmallVec<[u8; 8192]>;
 
 #[derive(Error, Debug)]
@@ -50,7 +67,333 @@ pub enum RegionError {
     InvalidFormat,
 
     #[error("Unsupported chunk compression type {compression_type} at chunk ({x}, {z})")]
-    UnsupportedCompression { compression_type: u8, x: i32, z: i32 },
+    UnsupportedCompression {
+        compression_type: u8,
+        x: i32,
+        z: i32,
+    },
+
+    #[error("Corrupt chunk ({x}, {z}): {reason}")]
+    CorruptChunk { x: i32, z: i32, reason: String },
+
+    #[error("Invalid grid size: {grid_size} (must be 1, 2, 4, 8, 16 or 32)")]
+    InvalidGridSize { grid_size: u8 },
+
+    #[error("Checksum mismatch in bucket {bucket}: expected {expected:#x}, found {found:#x}")]
+    ChecksumMismatch {
+        bucket: usize,
+        expected: u64,
+        found: u64,
+    },
+
+    #[error("Chunk ({x}, {z}) is outside region ({region_x}, {region_z})")]
+    ChunkOutsideRegion {
+        x: i32,
+        z: i32,
+        region_x: i32,
+        region_z: i32,
+    },
+
+    #[error("Chunk index {index} is out of range (must be below {CHUNKS_PER_REGION})")]
+    ChunkIndexOutOfRange { index: usize },
+}
+
+/// How readers react to damaged chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadMode {
+    /// Skip damaged chunks, clamp length fields past their sectors, and list
+    /// both in the [`ReadReport`].
+    Lenient,
+    /// Fail on the first anomaly.
+    Strict,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkIssueReason {
+    /// The location entry points into the header or past the end of the file.
+    SectorOutOfBounds {
+        sector_offset: u32,
+        sector_count: u8,
+    },
+    /// The length field is zero or larger than the allocated sectors.
+    InvalidLength {
+        length: u32,
+    },
+    /// The length field is larger than the allocated sectors; the chunk was
+    /// still loaded from the `available` bytes.
+    LengthClamped {
+        length: u32,
+        available: u32,
+    },
+    UnsupportedCompression {
+        compression_type: u8,
+    },
+    DecompressionFailed {
+        reason: String,
+    },
+    MissingExternalFile {
+        path: PathBuf,
+    },
+}
+
+impl ChunkIssueReason {
+    /// Whether the chunk is present but its payload cannot be decoded, as
+    /// opposed to a location table entry that points nowhere or a length
+    /// field that was clamped.
+    pub fn is_payload_error(&self) -> bool {
+        !matches!(
+            self,
+            ChunkIssueReason::SectorOutOfBounds { .. } | ChunkIssueReason::LengthClamped { .. }
+        )
+    }
+
+    /// Whether the chunk was loaded despite the issue.
+    pub fn is_recovered(&self) -> bool {
+        matches!(self, ChunkIssueReason::LengthClamped { .. })
+    }
+}
+
+impl fmt::Display for ChunkIssueReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkIssueReason::SectorOutOfBounds {
+                sector_offset,
+                sector_count,
+            } => write!(
+                f,
+                "sectors {}..{} are outside the file",
+                sector_offset,
+                *sector_offset + *sector_count as u32
+            ),
+            ChunkIssueReason::InvalidLength { length } => {
+                write!(f, "invalid length field {}", length)
+            }
+            ChunkIssueReason::LengthClamped { length, available } => {
+                write!(f, "length field {} clamped to {} bytes", length, available)
+            }
+            ChunkIssueReason::UnsupportedCompression { compression_type } => {
+                write!(f, "unsupported compression type {}", compression_type)
+            }
+            ChunkIssueReason::DecompressionFailed { reason } => {
+                write!(f, "decompression failed: {}", reason)
+            }
+            ChunkIssueReason::MissingExternalFile { path } => {
+                write!(f, "missing external file {}", path.display())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChunkIssue {
+    pub index: usize,
+    pub x: i32,
+    pub z: i32,
+    pub reason: ChunkIssueReason,
+}
+
+impl ChunkIssue {
+    pub fn into_error(self) -> RegionError {
+        match self.reason {
+            ChunkIssueReason::UnsupportedCompression { compression_type } => {
+                RegionError::UnsupportedCompression {
+                    compression_type,
+                    x: self.x,
+                    z: self.z,
+                }
+            }
+            reason => RegionError::CorruptChunk {
+                x: self.x,
+                z: self.z,
+                reason: reason.to_string(),
+            },
+        }
+    }
+}
+
+/// Outcome of a lenient region read: skipped chunks and clamped ones alike.
+#[derive(Debug, Clone, Default)]
+pub struct ReadReport {
+    pub chunks_loaded: usize,
+    /// The file was empty or shorter than the 8 KiB header.
+    pub truncated_header: bool,
+    pub issues: Vec<ChunkIssue>,
+}
+
+impl ReadReport {
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        !self.truncated_header && self.issues.is_empty()
+    }
+}
+
+/// A structural check that a region file failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationCheck {
+    FileTooShort {
+        size: u64,
+        minimum: u64,
+    },
+    InvalidSignature {
+        found: u64,
+    },
+    UnsupportedVersion {
+        version: u8,
+    },
+    /// The header's `compressed_size` differs from the bytes between header and footer.
+    CompressedSizeMismatch {
+        declared: u32,
+        actual: u64,
+    },
+    DecompressionFailed {
+        reason: String,
+    },
+    /// The header's `chunk_count` differs from the chunks in the metadata table.
+    ChunkCountMismatch {
+        declared: u16,
+        actual: u16,
+    },
+    /// The metadata table and chunk sizes do not add up to the decompressed length.
+    ChunkSizeMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    InvalidGridSize {
+        grid_size: u8,
+    },
+    /// The feature list or bucket table runs past the footer.
+    TruncatedTable,
+    ChecksumMismatch {
+        bucket: usize,
+        expected: u64,
+        found: u64,
+    },
+    /// A bucket's cells run past its decompressed data.
+    InvalidBucketLayout {
+        bucket: usize,
+    },
+    /// The LinearV2 existence bitmap disagrees with the bucket contents.
+    BitmapMismatch {
+        in_bitmap: bool,
+    },
+    SectorOutOfBounds {
+        sector_offset: u32,
+        sector_count: u8,
+    },
+    /// The chunk shares sectors with chunk `other`.
+    SectorOverlap {
+        other: usize,
+    },
+    InvalidLength {
+        length: u32,
+    },
+    UnsupportedCompression {
+        compression_type: u8,
+    },
+}
+
+impl fmt::Display for ValidationCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationCheck::FileTooShort { size, minimum } => {
+                write!(f, "file is {} bytes, at least {} expected", size, minimum)
+            }
+            ValidationCheck::InvalidSignature { found } => {
+                write!(f, "invalid signature {:#018x}", found)
+            }
+            ValidationCheck::UnsupportedVersion { version } => {
+                write!(f, "unsupported version {}", version)
+            }
+            ValidationCheck::CompressedSizeMismatch { declared, actual } => write!(
+                f,
+                "compressed size is {} bytes, header says {}",
+                actual, declared
+            ),
+            ValidationCheck::DecompressionFailed { reason } => {
+                write!(f, "decompression failed: {}", reason)
+            }
+            ValidationCheck::ChunkCountMismatch { declared, actual } => {
+                write!(f, "{} chunks present, header says {}", actual, declared)
+            }
+            ValidationCheck::ChunkSizeMismatch { expected, actual } => write!(
+                f,
+                "decompressed data is {} bytes, chunk sizes add up to {}",
+                actual, expected
+            ),
+            ValidationCheck::InvalidGridSize { grid_size } => {
+                write!(f, "invalid grid size {}", grid_size)
+            }
+            ValidationCheck::TruncatedTable => write!(f, "bucket table runs past the footer"),
+            ValidationCheck::ChecksumMismatch {
+                bucket,
+                expected,
+                found,
+            } => write!(
+                f,
+                "bucket {} checksum is {:#018x}, expected {:#018x}",
+                bucket, found, expected
+            ),
+            ValidationCheck::InvalidBucketLayout { bucket } => {
+                write!(f, "bucket {} cells run past its data", bucket)
+            }
+            ValidationCheck::BitmapMismatch { in_bitmap } => {
+                if *in_bitmap {
+                    write!(f, "marked present in the bitmap but missing")
+                } else {
+                    write!(f, "present but not marked in the bitmap")
+                }
+            }
+            ValidationCheck::SectorOutOfBounds {
+                sector_offset,
+                sector_count,
+            } => write!(
+                f,
+                "sectors {}..{} are outside the file",
+                sector_offset,
+                *sector_offset + *sector_count as u32
+            ),
+            ValidationCheck::SectorOverlap { other } => {
+                write!(f, "sectors overlap chunk {}", other)
+            }
+            ValidationCheck::InvalidLength { length } => {
+                write!(f, "invalid length field {}", length)
+            }
+            ValidationCheck::UnsupportedCompression { compression_type } => {
+                write!(f, "unsupported compression type {}", compression_type)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    /// Byte offset in the file of the field or data that failed the check
+    pub offset: u64,
+    /// Chunk index within the region, for per-chunk checks
+    pub chunk: Option<usize>,
+    pub check: ValidationCheck,
+}
+
+/// Outcome of [`linear::validate_linear_file`] or [`anvil::validate_anvil_file`].
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub file_size: u64,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn push(&mut self, offset: u64, chunk: Option<usize>, check: ValidationCheck) {
+        self.issues.push(ValidationIssue {
+            offset,
+            chunk,
+            check,
+        });
+    }
 }
 
 #[derive(Clone)]
@@ -143,6 +486,21 @@ impl Region {
         self.timestamps[index] = 0;
     }
 
+    /// Removes every chunk for which `keep` returns false and returns their indices.
+    pub fn retain_chunks<F: FnMut(&Chunk) -> bool>(&mut self, mut keep: F) -> Vec<usize> {
+        let mut removed: Vec<usize> = self
+            .chunks
+            .iter()
+            .filter(|(_, chunk)| !keep(chunk))
+            .map(|(&index, _)| index)
+            .collect();
+        removed.sort_unstable();
+        for &index in &removed {
+            self.remove_chunk(index);
+        }
+        removed
+    }
+
     #[inline]
     pub fn get_chunk_at(&self, x: i32, z: i32) -> Option<&Chunk> {
         let local_x = x & 31;
@@ -151,6 +509,9 @@ impl Region {
         self.get_chunk(index)
     }
 
+    /// Stores `chunk` at the slot of world chunk (`x`, `z`). Only the low five
+    /// bits of each coordinate are used, so chunks of other regions wrap
+    /// around; use [`Region::insert_chunk`] to have them rejected.
     #[inline]
     pub fn set_chunk_at(&mut self, x: i32, z: i32, chunk: Chunk, timestamp: u32) {
         let local_x = x & 31;
@@ -159,6 +520,21 @@ impl Region {
         self.set_chunk(index, chunk, timestamp);
     }
 
+    /// Stores `chunk` at its own coordinates, failing if they are not in this region.
+    pub fn insert_chunk(&mut self, chunk: Chunk, timestamp: u32) -> Result<()> {
+        if chunk.x >> 5 != self.region_x || chunk.z >> 5 != self.region_z {
+            return Err(RegionError::ChunkOutsideRegion {
+                x: chunk.x,
+                z: chunk.z,
+                region_x: self.region_x,
+                region_z: self.region_z,
+            }
+            .into());
+        }
+        self.set_chunk_at(chunk.x, chunk.z, chunk, timestamp);
+        Ok(())
+    }
+
     pub fn parse_filename(filename: &str) -> Result<(i32, i32)> {
         let parts: Vec<&str> = filename.split('.').collect();
         if parts.len() < 3 {
@@ -243,9 +619,17 @@ pub mod io_utils {
         Ok(mmap)
     }
 
+    /// A unique `<file name>.<uuid>.tmp` sibling of `path`, so writes to
+    /// `r.0.0.mca` and `r.0.0.linear` in one directory never share a temp file.
+    pub fn temp_path_for(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+        path.with_file_name(name)
+    }
+
     pub fn atomic_write<P: AsRef<Path>>(path: P, data: &[u8]) -> Result<()> {
         let path = path.as_ref();
-        let temp_path = path.with_extension("tmp");
+        let temp_path = temp_path_for(path);
 
         {
             let mut file = BufWriter::new(
@@ -260,10 +644,62 @@ pub mod io_utils {
             file.into_inner()?.sync_all()?;
         }
 
-        std::fs::rename(temp_path, path)?;
+        if let Err(e) = std::fs::rename(&temp_path, path) {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Moves `from` to `to`, copying and deleting when they are on different
+    /// filesystems.
+    pub fn move_file<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> Result<()> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        if std::fs::rename(from, to).is_ok() {
+            return Ok(());
+        }
+        let temp_path = temp_path_for(to);
+        std::fs::copy(from, &temp_path)?;
+        File::open(&temp_path)?.sync_all()?;
+        std::fs::rename(&temp_path, to)?;
+        std::fs::remove_file(from)?;
         Ok(())
     }
 
+    /// Whether `path` is named like a [`temp_path_for`] sibling of a region
+    /// file: `r.<x>.<z>.<ext>.<32 hex digits>.tmp`.
+    pub fn is_region_temp_file(path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            return false;
+        };
+        let Some((target, id)) = name
+            .strip_suffix(".tmp")
+            .and_then(|stem| stem.rsplit_once('.'))
+        else {
+            return false;
+        };
+        id.len() == 32
+            && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+            && target.starts_with("r.")
+            && target.split('.').count() == 4
+            && Region::parse_filename(target).is_ok()
+    }
+
+    /// Removes the region temp files that interrupted [`atomic_write`] calls
+    /// left in `dir` and returns how many there were. Other `.tmp` files are
+    /// left alone.
+    pub fn remove_temp_files<P: AsRef<Path>>(dir: P) -> Result<usize> {
+        let mut removed = 0;
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && is_region_temp_file(&path) {
+                std::fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
     pub fn set_mtime<P: AsRef<Path>>(path: P, mtime: u64) -> Result<()> {
         let file_time = filetime::FileTime::from_unix_time(mtime as i64, 0);
         filetime::set_file_mtime(path, file_time)?;
@@ -1,11 +1,14 @@
 use crate::{
-    io_utils, Chunk, PerformanceCounters, Region, RegionError, CHUNKS_PER_REGION, LINEAR_SIGNATURE,
-    LINEAR_VERSION_V1, LINEAR_VERSION_V2, REGION_DIMENSION,
+    format::DetectedFormat, io_utils, Chunk, PerformanceCounters, Region, RegionError,
+    ValidationCheck, ValidationReport, CHUNKS_PER_REGION, LINEAR_SIGNATURE,
+    LINEAR_V2_DEFAULT_GRID_SIZE, LINEAR_VERSION_V1, LINEAR_VERSION_V2, REGION_DIMENSION,
 };
 use anyhow::{Context, Result};
+use memmap2::Mmap;
+use std::io::{BufReader, Read, Write};
 use std::path::Path;
 use std::sync::Arc;
-use zstd;
+use xxhash_rust::xxh64::xxh64;
 
 #[derive(Debug, Clone, Copy)]
 pub enum LinearVersion {
@@ -23,42 +26,31 @@ impl LinearVersion {
 }
 
 fn decompress_with_retry(compressed_data: &[u8], header: &LinearHeader) -> Result<Vec<u8>> {
-    match zstd::bulk::decompress(compressed_data, 0) {
-        Ok(data) => return Ok(data),
-        Err(_) => {}
+    if let Ok(data) = zstd::bulk::decompress(compressed_data, 0) {
+        return Ok(data);
     }
 
-    match zstd::bulk::decompress(compressed_data, 64 * 1024 * 1024) {
-        Ok(data) => return Ok(data),
-        Err(_) => {}
+    if let Ok(data) = zstd::bulk::decompress(compressed_data, 64 * 1024 * 1024) {
+        return Ok(data);
     }
 
     let estimated_size = (header.chunk_count as usize) * 1024 * 16;
-    match zstd::bulk::decompress(compressed_data, estimated_size) {
-        Ok(data) => return Ok(data),
-        Err(_) => {}
-    }
-
-    match zstd::stream::Decoder::new(compressed_data) {
-        Ok(mut decoder) => {
-            let mut decompressed = Vec::new();
-            match std::io::copy(&mut decoder, &mut decompressed) {
-                Ok(_) => return Ok(decompressed),
-                Err(e) => {
-                    return Err(RegionError::DecompressionFailed {
-                        reason: format!("Streaming decompression failed: {}", e),
-                    }
-                    .into())
-                }
-            }
+    if let Ok(data) = zstd::bulk::decompress(compressed_data, estimated_size) {
+        return Ok(data);
+    }
+
+    let mut decoder = zstd::stream::Decoder::new(compressed_data).map_err(|e| {
+        RegionError::DecompressionFailed {
+            reason: format!("Streaming decoder creation failed: {}", e),
         }
-        Err(e) => {
-            return Err(RegionError::DecompressionFailed {
-                reason: format!("Streaming decoder creation failed: {}", e),
-            }
-            .into())
+    })?;
+    let mut decompressed = Vec::new();
+    std::io::copy(&mut decoder, &mut decompressed).map_err(|e| {
+        RegionError::DecompressionFailed {
+            reason: format!("Streaming decompression failed: {}", e),
         }
-    }
+    })?;
+    Ok(decompressed)
 }
 
 #[repr(C, packed)]
@@ -118,7 +110,7 @@ impl LinearHeader {
         })
     }
 
-    fn to_bytes(&self) -> [u8; Self::SIZE] {
+    fn to_bytes(self) -> [u8; Self::SIZE] {
         let mut bytes = [0u8; Self::SIZE];
         bytes[0..8].copy_from_slice(&self.signature.to_be_bytes());
         bytes[8] = self.version;
@@ -146,7 +138,7 @@ impl ChunkMeta {
         Self { size, timestamp }
     }
 
-    fn to_bytes(&self) -> [u8; Self::SIZE] {
+    fn to_bytes(self) -> [u8; Self::SIZE] {
         let mut bytes = [0u8; Self::SIZE];
         bytes[0..4].copy_from_slice(&self.size.to_be_bytes());
         bytes[4..8].copy_from_slice(&self.timestamp.to_be_bytes());
@@ -154,69 +146,130 @@ impl ChunkMeta {
     }
 }
 
-pub fn read_linear_region<P: AsRef<Path>>(
-    path: P,
-    counters: Option<Arc<PerformanceCounters>>,
-) -> Result<Region> {
-    let path = path.as_ref();
-
-    let filename = path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .context("Invalid filename")?;
-    let (region_x, region_z) = Region::parse_filename(filename)?;
+/// LinearV2 bucket entry (13 bytes)
+#[derive(Debug, Clone, Copy)]
+struct BucketMeta {
+    size: u32,
+    compression_level: i8,
+    hash: u64,
+}
 
-    let mmap = io_utils::mmap_file(path)?;
-    let file_size = mmap.len();
+impl BucketMeta {
+    const SIZE: usize = 13;
 
-    if let Some(ref counters) = counters {
-        counters.add_bytes_read(file_size as u64);
+    fn from_bytes(data: &[u8]) -> Self {
+        let size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let compression_level = data[4] as i8;
+        let hash = u64::from_be_bytes([
+            data[5], data[6], data[7], data[8], data[9], data[10], data[11], data[12],
+        ]);
+        Self {
+            size,
+            compression_level,
+            hash,
+        }
     }
 
-    if file_size < LinearHeader::SIZE + 8 {
-        return Err(RegionError::InvalidFormat.into());
+    fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..4].copy_from_slice(&self.size.to_be_bytes());
+        bytes[4] = self.compression_level as u8;
+        bytes[5..13].copy_from_slice(&self.hash.to_be_bytes());
+        bytes
     }
+}
 
-    let header = LinearHeader::from_bytes(&mmap[..LinearHeader::SIZE])?;
+/// LinearV2 fixed header (26 bytes), followed by the chunk existence bitmap
+#[derive(Debug, Clone, Copy)]
+struct LinearV2Header {
+    signature: u64,        // 8 bytes - LINEAR_SIGNATURE
+    version: u8,           // 1 byte - LINEAR_VERSION_V2
+    newest_timestamp: u64, // 8 bytes - newest chunk timestamp
+    grid_size: u8,         // 1 byte - buckets per region side
+    region_x: i32,         // 4 bytes
+    region_z: i32,         // 4 bytes
+}
 
-    if header.signature != LINEAR_SIGNATURE {
-        return Err(RegionError::InvalidSignature {
-            expected: LINEAR_SIGNATURE,
-            found: header.signature,
-        }
-        .into());
-    }
+impl LinearV2Header {
+    const SIZE: usize = 26;
+    const BITMAP_SIZE: usize = CHUNKS_PER_REGION / 8;
 
-    if header.version != 1 && header.version != 2 {
-        return Err(RegionError::UnsupportedVersion {
-            version: header.version,
+    fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < Self::SIZE {
+            return Err(RegionError::InvalidFormat.into());
         }
-        .into());
+
+        let signature = u64::from_be_bytes([
+            data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7],
+        ]);
+        let version = data[8];
+        let newest_timestamp = u64::from_be_bytes([
+            data[9], data[10], data[11], data[12], data[13], data[14], data[15], data[16],
+        ]);
+        let grid_size = data[17];
+        let region_x = i32::from_be_bytes([data[18], data[19], data[20], data[21]]);
+        let region_z = i32::from_be_bytes([data[22], data[23], data[24], data[25]]);
+
+        Ok(Self {
+            signature,
+            version,
+            newest_timestamp,
+            grid_size,
+            region_x,
+            region_z,
+        })
     }
 
-    let footer_start = file_size - 8;
-    let footer_signature = u64::from_be_bytes([
-        mmap[footer_start],
-        mmap[footer_start + 1],
-        mmap[footer_start + 2],
-        mmap[footer_start + 3],
-        mmap[footer_start + 4],
-        mmap[footer_start + 5],
-        mmap[footer_start + 6],
-        mmap[footer_start + 7],
-    ]);
+    fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..8].copy_from_slice(&self.signature.to_be_bytes());
+        bytes[8] = self.version;
+        bytes[9..17].copy_from_slice(&self.newest_timestamp.to_be_bytes());
+        bytes[17] = self.grid_size;
+        bytes[18..22].copy_from_slice(&self.region_x.to_be_bytes());
+        bytes[22..26].copy_from_slice(&self.region_z.to_be_bytes());
+        bytes
+    }
+}
 
-    if footer_signature != LINEAR_SIGNATURE {
-        return Err(RegionError::InvalidSignature {
-            expected: LINEAR_SIGNATURE,
-            found: footer_signature,
-        }
-        .into());
+fn check_grid_size(grid_size: u8) -> Result<()> {
+    if grid_size == 0 || grid_size > REGION_DIMENSION as u8 || !grid_size.is_power_of_two() {
+        return Err(RegionError::InvalidGridSize { grid_size }.into());
     }
+    Ok(())
+}
+
+/// Chunk indices stored in bucket `bucket`, in on-disk order.
+fn bucket_chunk_indices(grid_size: u8, bucket: usize) -> impl Iterator<Item = usize> {
+    let grid_size = grid_size as usize;
+    let cells = REGION_DIMENSION / grid_size;
+    let bx = bucket / grid_size;
+    let bz = bucket % grid_size;
+    (0..cells).flat_map(move |cx| {
+        (0..cells).map(move |cz| (bx * cells + cx) + (bz * cells + cz) * REGION_DIMENSION)
+    })
+}
+
+fn read_footer_signature(data: &[u8]) -> u64 {
+    let footer_start = data.len() - 8;
+    u64::from_be_bytes([
+        data[footer_start],
+        data[footer_start + 1],
+        data[footer_start + 2],
+        data[footer_start + 3],
+        data[footer_start + 4],
+        data[footer_start + 5],
+        data[footer_start + 6],
+        data[footer_start + 7],
+    ])
+}
+
+fn parse_linear_v1(data: &[u8], region: &mut Region) -> Result<()> {
+    let header = LinearHeader::from_bytes(&data[..LinearHeader::SIZE])?;
 
     let compressed_start = LinearHeader::SIZE + 8;
-    let compressed_end = footer_start;
-    let compressed_data = &mmap[compressed_start..compressed_end];
+    let compressed_end = data.len() - 8;
+    let compressed_data = &data[compressed_start..compressed_end];
     let decompressed = decompress_with_retry(compressed_data, &header)?;
 
     let expected_header_size = CHUNKS_PER_REGION * ChunkMeta::SIZE;
@@ -253,12 +306,6 @@ pub fn read_linear_region<P: AsRef<Path>>(
         return Err(RegionError::InvalidFormat.into());
     }
 
-    let mut region = Region::new(region_x, region_z);
-    region.mtime = std::fs::metadata(path)?
-        .modified()?
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_secs();
-
     let mut chunk_data_offset = expected_header_size;
 
     for (i, meta) in chunk_metas.iter().enumerate() {
@@ -269,8 +316,8 @@ pub fn read_linear_region<P: AsRef<Path>>(
             let chunk_end = chunk_start + meta.size as usize;
             let chunk_data = &decompressed[chunk_start..chunk_end];
 
-            let x = region_x * REGION_DIMENSION as i32 + (i % REGION_DIMENSION) as i32;
-            let z = region_z * REGION_DIMENSION as i32 + (i / REGION_DIMENSION) as i32;
+            let x = region.region_x * REGION_DIMENSION as i32 + (i % REGION_DIMENSION) as i32;
+            let z = region.region_z * REGION_DIMENSION as i32 + (i / REGION_DIMENSION) as i32;
 
             let chunk = Chunk::from_slice(chunk_data, x, z);
             region.set_chunk(i, chunk, meta.timestamp);
@@ -279,23 +326,268 @@ pub fn read_linear_region<P: AsRef<Path>>(
         }
     }
 
-    if let Some(ref counters) = counters {
-        counters.add_file();
-        counters.add_chunks(real_chunk_count as u64);
+    Ok(())
+}
+
+/// Position of one LinearV2 bucket inside the file
+#[derive(Debug, Clone, Copy)]
+struct BucketLocation {
+    offset: usize,
+    meta: BucketMeta,
+}
+
+fn parse_bucket_table(data: &[u8]) -> Result<(LinearV2Header, Vec<BucketLocation>)> {
+    let header = LinearV2Header::from_bytes(data)?;
+    check_grid_size(header.grid_size)?;
+
+    let footer_start = data.len() - 8;
+    let mut offset = LinearV2Header::SIZE + LinearV2Header::BITMAP_SIZE;
+    if offset > footer_start {
+        return Err(RegionError::InvalidFormat.into());
+    }
+
+    // NBT feature list: (name length, name, i32 value)*, terminated by a zero length
+    loop {
+        if offset >= footer_start {
+            return Err(RegionError::InvalidFormat.into());
+        }
+        let name_length = data[offset] as usize;
+        offset += 1;
+        if name_length == 0 {
+            break;
+        }
+        offset += name_length + 4;
+    }
+
+    let bucket_count = (header.grid_size as usize) * (header.grid_size as usize);
+    let metas_end = offset + bucket_count * BucketMeta::SIZE;
+    if metas_end > footer_start {
+        return Err(RegionError::InvalidFormat.into());
+    }
+
+    let mut buckets = Vec::with_capacity(bucket_count);
+    let mut bucket_offset = metas_end;
+    for meta_bytes in data[offset..metas_end].chunks_exact(BucketMeta::SIZE) {
+        let meta = BucketMeta::from_bytes(meta_bytes);
+        buckets.push(BucketLocation {
+            offset: bucket_offset,
+            meta,
+        });
+        bucket_offset += meta.size as usize;
+    }
+
+    if bucket_offset > footer_start {
+        return Err(RegionError::InvalidFormat.into());
+    }
+
+    Ok((header, buckets))
+}
+
+fn decode_bucket(data: &[u8], bucket: usize, location: &BucketLocation) -> Result<Vec<u8>> {
+    let compressed = &data[location.offset..location.offset + location.meta.size as usize];
+
+    let found = xxh64(compressed, 0);
+    if found != location.meta.hash {
+        return Err(RegionError::ChecksumMismatch {
+            bucket,
+            expected: location.meta.hash,
+            found,
+        }
+        .into());
+    }
+
+    let decompressed =
+        zstd::stream::decode_all(compressed).map_err(|e| RegionError::DecompressionFailed {
+            reason: format!("Bucket {} decompression failed: {}", bucket, e),
+        })?;
+    Ok(decompressed)
+}
+
+/// Walks the cells of a decompressed bucket, calling `f(index, timestamp, data)`
+/// for every chunk slot. `data` is `None` for empty slots.
+fn walk_bucket<F>(decompressed: &[u8], grid_size: u8, bucket: usize, mut f: F) -> Result<()>
+where
+    F: FnMut(usize, u32, Option<&[u8]>),
+{
+    let mut cursor = 0usize;
+    for i in bucket_chunk_indices(grid_size, bucket) {
+        if cursor + 12 > decompressed.len() {
+            return Err(RegionError::InvalidFormat.into());
+        }
+        let size = u32::from_be_bytes([
+            decompressed[cursor],
+            decompressed[cursor + 1],
+            decompressed[cursor + 2],
+            decompressed[cursor + 3],
+        ]) as usize;
+        let timestamp = u64::from_be_bytes([
+            decompressed[cursor + 4],
+            decompressed[cursor + 5],
+            decompressed[cursor + 6],
+            decompressed[cursor + 7],
+            decompressed[cursor + 8],
+            decompressed[cursor + 9],
+            decompressed[cursor + 10],
+            decompressed[cursor + 11],
+        ]) as u32;
+        cursor += 12;
+
+        // The size prefix counts the 8-byte timestamp as well
+        if size > 8 {
+            let chunk_end = cursor + size - 8;
+            if chunk_end > decompressed.len() {
+                return Err(RegionError::InvalidFormat.into());
+            }
+            f(i, timestamp, Some(&decompressed[cursor..chunk_end]));
+            cursor = chunk_end;
+        } else {
+            f(i, timestamp, None);
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_linear_v2(data: &[u8], region: &mut Region) -> Result<()> {
+    let (header, buckets) = parse_bucket_table(data)?;
+
+    for (bucket, location) in buckets.iter().enumerate() {
+        if location.meta.size == 0 {
+            continue;
+        }
+
+        let decompressed = decode_bucket(data, bucket, location)?;
+        walk_bucket(
+            &decompressed,
+            header.grid_size,
+            bucket,
+            |i, timestamp, chunk_data| {
+                region.timestamps[i] = timestamp;
+                if let Some(chunk_data) = chunk_data {
+                    let x =
+                        region.region_x * REGION_DIMENSION as i32 + (i % REGION_DIMENSION) as i32;
+                    let z =
+                        region.region_z * REGION_DIMENSION as i32 + (i / REGION_DIMENSION) as i32;
+                    region.set_chunk(i, Chunk::from_slice(chunk_data, x, z), timestamp);
+                }
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Checks the header and footer signatures and the version byte.
+fn check_linear_signatures(data: &[u8]) -> Result<LinearHeader> {
+    if data.len() < LinearHeader::SIZE + 8 {
+        return Err(RegionError::InvalidFormat.into());
+    }
+
+    let header = LinearHeader::from_bytes(&data[..LinearHeader::SIZE])?;
+
+    if header.signature != LINEAR_SIGNATURE {
+        return Err(RegionError::InvalidSignature {
+            expected: LINEAR_SIGNATURE,
+            found: header.signature,
+        }
+        .into());
+    }
+
+    if header.version != LINEAR_VERSION_V1 && header.version != LINEAR_VERSION_V2 {
+        return Err(RegionError::UnsupportedVersion {
+            version: header.version,
+        }
+        .into());
+    }
+
+    let footer_signature = read_footer_signature(data);
+    if footer_signature != LINEAR_SIGNATURE {
+        return Err(RegionError::InvalidSignature {
+            expected: LINEAR_SIGNATURE,
+            found: footer_signature,
+        }
+        .into());
+    }
+
+    Ok(header)
+}
+
+/// Reads the header fields of a linear file without decompressing anything.
+pub(crate) fn detect_linear(data: &[u8]) -> DetectedFormat {
+    let Ok(header) = LinearHeader::from_bytes(data) else {
+        return DetectedFormat::Unknown;
+    };
+    if header.signature != LINEAR_SIGNATURE {
+        return DetectedFormat::Unknown;
+    }
+
+    match header.version {
+        LINEAR_VERSION_V1 => DetectedFormat::LinearV1 {
+            newest_timestamp: header.newest_timestamp,
+            compression_level: header.compression_level,
+            chunk_count: header.chunk_count,
+            compressed_size: header.compressed_size,
+        },
+        LINEAR_VERSION_V2 => match LinearV2Header::from_bytes(data) {
+            Ok(header) => DetectedFormat::LinearV2 {
+                newest_timestamp: header.newest_timestamp,
+                grid_size: header.grid_size,
+                region_x: header.region_x,
+                region_z: header.region_z,
+            },
+            Err(_) => DetectedFormat::Unknown,
+        },
+        _ => DetectedFormat::Unknown,
+    }
+}
+
+fn parse_linear(data: &[u8], region_x: i32, region_z: i32) -> Result<Region> {
+    let header = check_linear_signatures(data)?;
+
+    let mut region = Region::new(region_x, region_z);
+    if header.version == LINEAR_VERSION_V2 {
+        parse_linear_v2(data, &mut region)?;
+    } else {
+        parse_linear_v1(data, &mut region)?;
     }
 
     Ok(region)
 }
 
-pub fn write_linear_region<P: AsRef<Path>>(
+pub fn read_linear_region<P: AsRef<Path>>(
     path: P,
-    region: &Region,
-    compression_level: i32,
-    version: LinearVersion,
     counters: Option<Arc<PerformanceCounters>>,
-) -> Result<()> {
+) -> Result<Region> {
     let path = path.as_ref();
 
+    let filename = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("Invalid filename")?;
+    let (region_x, region_z) = Region::parse_filename(filename)?;
+
+    let mmap = io_utils::mmap_file(path)?;
+    let file_size = mmap.len();
+
+    if let Some(ref counters) = counters {
+        counters.add_bytes_read(file_size as u64);
+    }
+
+    let mut region = parse_linear(&mmap, region_x, region_z)?;
+    region.mtime = std::fs::metadata(path)?
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+
+    if let Some(ref counters) = counters {
+        counters.add_file();
+        counters.add_chunks(region.chunk_count() as u64);
+    }
+
+    Ok(region)
+}
+
+fn encode_linear_v1(region: &Region, compression_level: i32) -> Result<Vec<u8>> {
     let mut chunk_metas = Vec::with_capacity(CHUNKS_PER_REGION);
     let mut chunk_data = Vec::new();
     let mut newest_timestamp = 0u32;
@@ -339,7 +631,7 @@ pub fn write_linear_region<P: AsRef<Path>>(
         compression_level as i8,
         chunk_count,
         compressed.len() as u32,
-        version,
+        LinearVersion::V1,
     );
 
     let mut file_data = Vec::with_capacity(LinearHeader::SIZE + 8 + compressed.len() + 8);
@@ -351,6 +643,178 @@ pub fn write_linear_region<P: AsRef<Path>>(
 
     file_data.extend_from_slice(&LINEAR_SIGNATURE.to_be_bytes());
 
+    Ok(file_data)
+}
+
+fn encode_linear_v2(region: &Region, compression_level: i32, grid_size: u8) -> Result<Vec<u8>> {
+    check_grid_size(grid_size)?;
+
+    let mut newest_timestamp = 0u32;
+    let mut bitmap = [0u8; LinearV2Header::BITMAP_SIZE];
+    for i in 0..CHUNKS_PER_REGION {
+        if region.get_chunk(i).is_some() {
+            bitmap[i / 8] |= 1 << (7 - (i % 8));
+            newest_timestamp = newest_timestamp.max(region.timestamps[i]);
+        }
+    }
+
+    let bucket_count = (grid_size as usize) * (grid_size as usize);
+    let mut bucket_metas = Vec::with_capacity(bucket_count);
+    let mut buckets = Vec::with_capacity(bucket_count);
+
+    for bucket in 0..bucket_count {
+        let mut raw = Vec::new();
+        let mut has_data = false;
+
+        for i in bucket_chunk_indices(grid_size, bucket) {
+            let timestamp = region.timestamps[i] as u64;
+            match region.get_chunk(i) {
+                Some(chunk) => {
+                    has_data = true;
+                    raw.extend_from_slice(&(chunk.size() as u32 + 8).to_be_bytes());
+                    raw.extend_from_slice(&timestamp.to_be_bytes());
+                    raw.extend_from_slice(chunk.as_slice());
+                }
+                None => {
+                    raw.extend_from_slice(&0u32.to_be_bytes());
+                    raw.extend_from_slice(&timestamp.to_be_bytes());
+                }
+            }
+        }
+
+        if !has_data {
+            bucket_metas.push(BucketMeta {
+                size: 0,
+                compression_level: compression_level as i8,
+                hash: 0,
+            });
+            buckets.push(Vec::new());
+            continue;
+        }
+
+        let compressed = zstd::bulk::compress(&raw, compression_level).map_err(|e| {
+            RegionError::CompressionFailed {
+                reason: format!("ZSTD compression of bucket {} failed: {}", bucket, e),
+            }
+        })?;
+
+        bucket_metas.push(BucketMeta {
+            size: compressed.len() as u32,
+            compression_level: compression_level as i8,
+            hash: xxh64(&compressed, 0),
+        });
+        buckets.push(compressed);
+    }
+
+    let header = LinearV2Header {
+        signature: LINEAR_SIGNATURE,
+        version: LINEAR_VERSION_V2,
+        newest_timestamp: newest_timestamp as u64,
+        grid_size,
+        region_x: region.region_x,
+        region_z: region.region_z,
+    };
+
+    let buckets_size: usize = buckets.iter().map(Vec::len).sum();
+    let mut file_data = Vec::with_capacity(
+        LinearV2Header::SIZE
+            + LinearV2Header::BITMAP_SIZE
+            + 1
+            + bucket_count * BucketMeta::SIZE
+            + buckets_size
+            + 8,
+    );
+    file_data.extend_from_slice(&header.to_bytes());
+    file_data.extend_from_slice(&bitmap);
+
+    // Empty NBT feature list
+    file_data.push(0);
+
+    for meta in &bucket_metas {
+        file_data.extend_from_slice(&meta.to_bytes());
+    }
+    for bucket in &buckets {
+        file_data.extend_from_slice(bucket);
+    }
+
+    file_data.extend_from_slice(&LINEAR_SIGNATURE.to_be_bytes());
+
+    Ok(file_data)
+}
+
+fn encode_linear(
+    region: &Region,
+    compression_level: i32,
+    version: LinearVersion,
+    grid_size: u8,
+) -> Result<Vec<u8>> {
+    match version {
+        LinearVersion::V1 => encode_linear_v1(region, compression_level),
+        LinearVersion::V2 => encode_linear_v2(region, compression_level, grid_size),
+    }
+}
+
+pub fn write_linear_region<P: AsRef<Path>>(
+    path: P,
+    region: &Region,
+    compression_level: i32,
+    version: LinearVersion,
+    counters: Option<Arc<PerformanceCounters>>,
+) -> Result<()> {
+    write_linear_region_with_grid(
+        path,
+        region,
+        compression_level,
+        version,
+        LINEAR_V2_DEFAULT_GRID_SIZE,
+        counters,
+    )
+}
+
+/// Reads a LinearV1 or LinearV2 region held in memory.
+pub fn read_linear_region_from_bytes(data: &[u8], region_x: i32, region_z: i32) -> Result<Region> {
+    parse_linear(data, region_x, region_z)
+}
+
+/// Reads a LinearV1 or LinearV2 region from a stream, e.g. an archive entry or a download.
+pub fn read_linear_region_from_reader<R: Read>(
+    mut reader: R,
+    region_x: i32,
+    region_z: i32,
+) -> Result<Region> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    parse_linear(&data, region_x, region_z)
+}
+
+/// Encodes `region` and writes it to `writer`. The grid size is ignored for LinearV1.
+pub fn write_linear_region_to_writer<W: Write>(
+    mut writer: W,
+    region: &Region,
+    compression_level: i32,
+    version: LinearVersion,
+    grid_size: u8,
+) -> Result<()> {
+    let file_data = encode_linear(region, compression_level, version, grid_size)?;
+    writer.write_all(&file_data)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Like [`write_linear_region`], with an explicit LinearV2 bucket grid size.
+/// The grid size is ignored for LinearV1.
+pub fn write_linear_region_with_grid<P: AsRef<Path>>(
+    path: P,
+    region: &Region,
+    compression_level: i32,
+    version: LinearVersion,
+    grid_size: u8,
+    counters: Option<Arc<PerformanceCounters>>,
+) -> Result<()> {
+    let path = path.as_ref();
+
+    let file_data = encode_linear(region, compression_level, version, grid_size)?;
+
     io_utils::atomic_write(path, &file_data)?;
 
     io_utils::set_mtime(path, region.mtime)?;
@@ -358,7 +822,7 @@ pub fn write_linear_region<P: AsRef<Path>>(
     if let Some(ref counters) = counters {
         counters.add_file();
         counters.add_bytes_written(file_data.len() as u64);
-        counters.add_chunks(chunk_count as u64);
+        counters.add_chunks(region.chunk_count() as u64);
     }
 
     Ok(())
@@ -381,17 +845,403 @@ pub fn verify_linear_file<P: AsRef<Path>>(path: P) -> bool {
         return false;
     }
 
-    let footer_start = mmap.len() - 8;
-    let footer_signature = u64::from_be_bytes([
-        mmap[footer_start],
-        mmap[footer_start + 1],
-        mmap[footer_start + 2],
-        mmap[footer_start + 3],
-        mmap[footer_start + 4],
-        mmap[footer_start + 5],
-        mmap[footer_start + 6],
-        mmap[footer_start + 7],
-    ]);
+    read_footer_signature(&mmap) == LINEAR_SIGNATURE
+}
+
+/// Checks every structural invariant of a LinearV1 or LinearV2 file and lists
+/// each failure with the byte offset of the field or data at fault.
+pub fn validate_linear_file<P: AsRef<Path>>(path: P) -> Result<ValidationReport> {
+    let path = path.as_ref();
+    // Zero-byte files cannot be mapped
+    let mmap = if std::fs::metadata(path)?.len() > 0 {
+        Some(io_utils::mmap_file(path)?)
+    } else {
+        None
+    };
+    Ok(validate_linear_data(mmap.as_deref().unwrap_or(&[])))
+}
+
+fn validate_linear_data(data: &[u8]) -> ValidationReport {
+    let mut report = ValidationReport {
+        file_size: data.len() as u64,
+        ..Default::default()
+    };
+
+    let minimum = LinearHeader::SIZE + 16;
+    if data.len() < minimum {
+        report.push(
+            0,
+            None,
+            ValidationCheck::FileTooShort {
+                size: data.len() as u64,
+                minimum: minimum as u64,
+            },
+        );
+        return report;
+    }
+
+    let header = LinearHeader::from_bytes(&data[..LinearHeader::SIZE]).unwrap();
+    if header.signature != LINEAR_SIGNATURE {
+        report.push(
+            0,
+            None,
+            ValidationCheck::InvalidSignature {
+                found: header.signature,
+            },
+        );
+    }
+    let footer_start = data.len() - 8;
+    let footer_signature = read_footer_signature(data);
+    if footer_signature != LINEAR_SIGNATURE {
+        report.push(
+            footer_start as u64,
+            None,
+            ValidationCheck::InvalidSignature {
+                found: footer_signature,
+            },
+        );
+    }
 
-    footer_signature == LINEAR_SIGNATURE
+    match header.version {
+        LINEAR_VERSION_V1 => validate_linear_v1(data, &header, &mut report),
+        LINEAR_VERSION_V2 => validate_linear_v2(data, &mut report),
+        version => report.push(8, None, ValidationCheck::UnsupportedVersion { version }),
+    }
+
+    report
+}
+
+fn validate_linear_v1(data: &[u8], header: &LinearHeader, report: &mut ValidationReport) {
+    let compressed_start = LinearHeader::SIZE + 8;
+    let compressed_data = &data[compressed_start..data.len() - 8];
+    if header.compressed_size as usize != compressed_data.len() {
+        report.push(
+            20,
+            None,
+            ValidationCheck::CompressedSizeMismatch {
+                declared: header.compressed_size,
+                actual: compressed_data.len() as u64,
+            },
+        );
+    }
+
+    let decompressed = match decompress_with_retry(compressed_data, header) {
+        Ok(decompressed) => decompressed,
+        Err(e) => {
+            report.push(
+                compressed_start as u64,
+                None,
+                ValidationCheck::DecompressionFailed {
+                    reason: format!("{:#}", e),
+                },
+            );
+            return;
+        }
+    };
+
+    let table_size = CHUNKS_PER_REGION * ChunkMeta::SIZE;
+    if decompressed.len() < table_size {
+        report.push(
+            compressed_start as u64,
+            None,
+            ValidationCheck::ChunkSizeMismatch {
+                expected: table_size as u64,
+                actual: decompressed.len() as u64,
+            },
+        );
+        return;
+    }
+
+    let mut chunk_count = 0u16;
+    let mut expected_size = table_size as u64;
+    for meta_bytes in decompressed[..table_size].chunks_exact(ChunkMeta::SIZE) {
+        let meta = ChunkMeta::from_bytes(meta_bytes);
+        if meta.size > 0 {
+            chunk_count = chunk_count.wrapping_add(1);
+            expected_size += meta.size as u64;
+        }
+    }
+
+    if chunk_count != header.chunk_count {
+        report.push(
+            18,
+            None,
+            ValidationCheck::ChunkCountMismatch {
+                declared: header.chunk_count,
+                actual: chunk_count,
+            },
+        );
+    }
+    if expected_size != decompressed.len() as u64 {
+        report.push(
+            compressed_start as u64,
+            None,
+            ValidationCheck::ChunkSizeMismatch {
+                expected: expected_size,
+                actual: decompressed.len() as u64,
+            },
+        );
+    }
+}
+
+fn validate_linear_v2(data: &[u8], report: &mut ValidationReport) {
+    let bitmap_start = LinearV2Header::SIZE;
+    if data.len() < bitmap_start + LinearV2Header::BITMAP_SIZE + 8 {
+        report.push(
+            0,
+            None,
+            ValidationCheck::FileTooShort {
+                size: data.len() as u64,
+                minimum: (bitmap_start + LinearV2Header::BITMAP_SIZE + 8) as u64,
+            },
+        );
+        return;
+    }
+
+    let grid_size = data[17];
+    if check_grid_size(grid_size).is_err() {
+        report.push(17, None, ValidationCheck::InvalidGridSize { grid_size });
+        return;
+    }
+
+    let Ok((_, buckets)) = parse_bucket_table(data) else {
+        report.push(
+            (bitmap_start + LinearV2Header::BITMAP_SIZE) as u64,
+            None,
+            ValidationCheck::TruncatedTable,
+        );
+        return;
+    };
+
+    let bitmap = &data[bitmap_start..bitmap_start + LinearV2Header::BITMAP_SIZE];
+    // `None` for chunks in buckets that could not be decoded
+    let mut present = [Some(false); CHUNKS_PER_REGION];
+    for (bucket, location) in buckets.iter().enumerate() {
+        if location.meta.size == 0 {
+            continue;
+        }
+
+        let compressed = &data[location.offset..location.offset + location.meta.size as usize];
+        let found = xxh64(compressed, 0);
+        if found != location.meta.hash {
+            report.push(
+                location.offset as u64,
+                None,
+                ValidationCheck::ChecksumMismatch {
+                    bucket,
+                    expected: location.meta.hash,
+                    found,
+                },
+            );
+        }
+
+        let decompressed = match zstd::stream::decode_all(compressed) {
+            Ok(decompressed) => decompressed,
+            Err(e) => {
+                report.push(
+                    location.offset as u64,
+                    None,
+                    ValidationCheck::DecompressionFailed {
+                        reason: format!("Bucket {}: {}", bucket, e),
+                    },
+                );
+                for i in bucket_chunk_indices(grid_size, bucket) {
+                    present[i] = None;
+                }
+                continue;
+            }
+        };
+
+        let walked = walk_bucket(&decompressed, grid_size, bucket, |i, _, chunk_data| {
+            present[i] = Some(chunk_data.is_some());
+        });
+        if walked.is_err() {
+            report.push(
+                location.offset as u64,
+                None,
+                ValidationCheck::InvalidBucketLayout { bucket },
+            );
+        }
+    }
+
+    for (i, &present) in present.iter().enumerate() {
+        let in_bitmap = bitmap[i / 8] & (1 << (7 - (i % 8))) != 0;
+        if present.is_some_and(|present| present != in_bitmap) {
+            report.push(
+                (bitmap_start + i / 8) as u64,
+                Some(i),
+                ValidationCheck::BitmapMismatch { in_bitmap },
+            );
+        }
+    }
+}
+
+enum ReaderLayout {
+    V1 {
+        metas: Vec<ChunkMeta>,
+        offsets: Vec<usize>,
+    },
+    V2 {
+        grid_size: u8,
+        buckets: Vec<BucketLocation>,
+    },
+}
+
+/// Random-access reader over a memory-mapped linear file.
+///
+/// Only the data needed for the requested chunk is decompressed: V1 files
+/// stream the zstd payload up to the chunk's offset, V2 files decode the single
+/// bucket that holds it.
+pub struct LinearRegionReader {
+    mmap: Mmap,
+    region_x: i32,
+    region_z: i32,
+    version: LinearVersion,
+    layout: ReaderLayout,
+}
+
+impl LinearRegionReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+
+        let filename = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .context("Invalid filename")?;
+        let (region_x, region_z) = Region::parse_filename(filename)?;
+
+        let mmap = io_utils::mmap_file(path)?;
+        let header = check_linear_signatures(&mmap)?;
+
+        let (version, layout) = if header.version == LINEAR_VERSION_V2 {
+            let (v2_header, buckets) = parse_bucket_table(&mmap)?;
+            let layout = ReaderLayout::V2 {
+                grid_size: v2_header.grid_size,
+                buckets,
+            };
+            (LinearVersion::V2, layout)
+        } else {
+            let mut decoder = Self::v1_decoder(&mmap)?;
+            let mut meta_bytes = vec![0u8; CHUNKS_PER_REGION * ChunkMeta::SIZE];
+            decoder
+                .read_exact(&mut meta_bytes)
+                .map_err(|e| RegionError::DecompressionFailed {
+                    reason: format!("Failed to read chunk table: {}", e),
+                })?;
+
+            let metas: Vec<ChunkMeta> = meta_bytes
+                .chunks_exact(ChunkMeta::SIZE)
+                .map(ChunkMeta::from_bytes)
+                .collect();
+
+            let mut offsets = Vec::with_capacity(CHUNKS_PER_REGION);
+            let mut offset = 0usize;
+            for meta in &metas {
+                offsets.push(offset);
+                offset += meta.size as usize;
+            }
+
+            (LinearVersion::V1, ReaderLayout::V1 { metas, offsets })
+        };
+
+        Ok(Self {
+            mmap,
+            region_x,
+            region_z,
+            version,
+            layout,
+        })
+    }
+
+    fn v1_decoder(data: &[u8]) -> Result<zstd::stream::Decoder<'_, BufReader<&[u8]>>> {
+        let compressed_data = &data[LinearHeader::SIZE + 8..data.len() - 8];
+        let decoder = zstd::stream::Decoder::new(compressed_data).map_err(|e| {
+            RegionError::DecompressionFailed {
+                reason: format!("Streaming decoder creation failed: {}", e),
+            }
+        })?;
+        Ok(decoder)
+    }
+
+    #[inline]
+    pub fn region_x(&self) -> i32 {
+        self.region_x
+    }
+
+    #[inline]
+    pub fn region_z(&self) -> i32 {
+        self.region_z
+    }
+
+    #[inline]
+    pub fn version(&self) -> LinearVersion {
+        self.version
+    }
+
+    /// Reads the chunk at local `index`, or `None` if the slot is empty.
+    pub fn get_chunk(&self, index: usize) -> Result<Option<Chunk>> {
+        if index >= CHUNKS_PER_REGION {
+            return Ok(None);
+        }
+
+        let x = self.region_x * REGION_DIMENSION as i32 + (index % REGION_DIMENSION) as i32;
+        let z = self.region_z * REGION_DIMENSION as i32 + (index / REGION_DIMENSION) as i32;
+
+        match &self.layout {
+            ReaderLayout::V1 { metas, offsets } => {
+                let size = metas[index].size as usize;
+                if size == 0 {
+                    return Ok(None);
+                }
+
+                let mut decoder = Self::v1_decoder(&self.mmap)?;
+                let skip = (CHUNKS_PER_REGION * ChunkMeta::SIZE + offsets[index]) as u64;
+                let skipped = std::io::copy(&mut (&mut decoder).take(skip), &mut std::io::sink())
+                    .map_err(|e| RegionError::DecompressionFailed {
+                    reason: format!("Streaming decompression failed: {}", e),
+                })?;
+                if skipped != skip {
+                    return Err(RegionError::InvalidFormat.into());
+                }
+
+                let mut data = vec![0u8; size];
+                decoder
+                    .read_exact(&mut data)
+                    .map_err(|e| RegionError::DecompressionFailed {
+                        reason: format!("Streaming decompression failed: {}", e),
+                    })?;
+
+                Ok(Some(Chunk::new(data, x, z)))
+            }
+            ReaderLayout::V2 { grid_size, buckets } => {
+                let cells = REGION_DIMENSION / *grid_size as usize;
+                let bx = (index % REGION_DIMENSION) / cells;
+                let bz = (index / REGION_DIMENSION) / cells;
+                let bucket = bx * *grid_size as usize + bz;
+
+                let location = &buckets[bucket];
+                if location.meta.size == 0 {
+                    return Ok(None);
+                }
+
+                let decompressed = decode_bucket(&self.mmap, bucket, location)?;
+                let mut found = None;
+                walk_bucket(&decompressed, *grid_size, bucket, |i, _, chunk_data| {
+                    if i == index {
+                        found = chunk_data.map(|data| Chunk::from_slice(data, x, z));
+                    }
+                })?;
+
+                Ok(found)
+            }
+        }
+    }
+
+    /// Reads the chunk at world chunk coordinates `(x, z)`.
+    pub fn get_chunk_at(&self, x: i32, z: i32) -> Result<Option<Chunk>> {
+        let local_x = x & 31;
+        let local_z = z & 31;
+        let index = (local_z as usize) * REGION_DIMENSION + (local_x as usize);
+        self.get_chunk(index)
+    }
 }
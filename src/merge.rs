@@ -0,0 +1,87 @@
+use crate::{Region, CHUNKS_PER_REGION};
+use anyhow::{bail, Result};
+
+/// Which chunk [`merge_regions`] keeps for each slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// The chunk with the newer timestamp; the left one on a tie.
+    Newest,
+    /// The left chunk wherever the left region has one.
+    LeftWins,
+    /// The right chunk wherever the right region has one.
+    RightWins,
+    /// The left region exactly as it is, mtime and timestamps included, with
+    /// right chunks added to its empty slots only.
+    FillEmpty,
+}
+
+/// Where the chunks of a merged region came from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    pub from_left: usize,
+    pub from_right: usize,
+    /// Slots present in both regions, whichever side won
+    pub conflicts: usize,
+}
+
+/// Combines two regions with the same coordinates chunk by chunk. Chunks
+/// present on only one side are always kept; `policy` decides between two
+/// chunks in the same slot. The merged region has the newer mtime of the two
+/// unless `policy` is [`MergePolicy::FillEmpty`].
+pub fn merge_regions(left: &Region, right: &Region, policy: MergePolicy) -> Result<Region> {
+    merge_regions_with_report(left, right, policy).map(|(region, _)| region)
+}
+
+/// Like [`merge_regions`], also counting which side each chunk came from.
+pub fn merge_regions_with_report(
+    left: &Region,
+    right: &Region,
+    policy: MergePolicy,
+) -> Result<(Region, MergeReport)> {
+    if (left.region_x, left.region_z) != (right.region_x, right.region_z) {
+        bail!(
+            "Cannot merge region ({}, {}) with region ({}, {})",
+            left.region_x,
+            left.region_z,
+            right.region_x,
+            right.region_z
+        );
+    }
+
+    let mut merged = Region::new(left.region_x, left.region_z);
+    if policy == MergePolicy::FillEmpty {
+        merged.mtime = left.mtime;
+        merged.timestamps = left.timestamps.clone();
+    } else {
+        merged.mtime = left.mtime.max(right.mtime);
+    }
+    let mut report = MergeReport::default();
+
+    for index in 0..CHUNKS_PER_REGION {
+        let take_left = match (left.get_chunk(index), right.get_chunk(index)) {
+            (None, None) => continue,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (Some(_), Some(_)) => {
+                report.conflicts += 1;
+                match policy {
+                    MergePolicy::Newest => left.timestamps[index] >= right.timestamps[index],
+                    MergePolicy::LeftWins | MergePolicy::FillEmpty => true,
+                    MergePolicy::RightWins => false,
+                }
+            }
+        };
+
+        let source = if take_left {
+            report.from_left += 1;
+            left
+        } else {
+            report.from_right += 1;
+            right
+        };
+        let chunk = source.get_chunk(index).unwrap().clone();
+        merged.set_chunk(index, chunk, source.timestamps[index]);
+    }
+
+    Ok((merged, report))
+}
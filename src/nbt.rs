@@ -1,8 +1,24 @@
-use anyhow::{Context, Result};
+use crate::Chunk;
+use anyhow::{bail, Context, Result};
+use fastnbt::{ByteArray, LongArray};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io::{Read, Write};
+use std::sync::OnceLock;
 
 pub use fastnbt::{from_bytes, from_reader, to_bytes, to_writer, Value};
 
+/// First DataVersion (20w17a) whose packed arrays do not span longs.
+pub const ALIGNED_PACKING_DATA_VERSION: i32 = 2529;
+/// First DataVersion (21w43a) of the 1.18 chunk format, without the `Level` compound.
+pub const MODERN_CHUNK_DATA_VERSION: i32 = 2844;
+/// Entries of a heightmap, one per column of a chunk.
+pub const HEIGHTMAP_ENTRIES: usize = 256;
+/// Blocks in a chunk section.
+pub const SECTION_BLOCKS: usize = 4096;
+/// Biome cells of 4x4x4 blocks in a chunk section.
+pub const SECTION_BIOMES: usize = 64;
+
 #[inline]
 pub fn parse_nbt(data: &[u8]) -> Result<Value> {
     from_bytes(data).context("Failed to parse NBT data")
@@ -22,3 +38,572 @@ pub fn parse_nbt_from_reader<R: Read>(reader: R) -> Result<Value> {
 pub fn write_nbt_to_writer<W: Write>(writer: W, value: &Value) -> Result<()> {
     to_writer(writer, value).context("Failed to write NBT to writer")
 }
+
+/// The tag `name` of a chunk, from the top level of 1.18+ chunks or the
+/// `Level` compound of older ones.
+pub fn chunk_tag<'a>(chunk: &'a Value, name: &str) -> Option<&'a Value> {
+    let Value::Compound(root) = chunk else {
+        return None;
+    };
+    match root.get(name) {
+        Some(tag) => Some(tag),
+        None => match root.get("Level") {
+            Some(Value::Compound(level)) => level.get(name),
+            _ => None,
+        },
+    }
+}
+
+/// The chunk's `InhabitedTime` in ticks.
+pub fn inhabited_time(chunk: &Value) -> Option<i64> {
+    match *chunk_tag(chunk, "InhabitedTime")? {
+        Value::Long(ticks) => Some(ticks),
+        Value::Int(ticks) => Some(ticks as i64),
+        _ => None,
+    }
+}
+
+/// The `DataVersion` the chunk was saved with, always a top-level tag.
+pub fn data_version(chunk: &Value) -> Option<i32> {
+    match chunk {
+        Value::Compound(root) => match root.get("DataVersion")? {
+            Value::Int(version) => Some(*version),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The chunk's generation `Status`, e.g. `minecraft:full` or `full`.
+pub fn chunk_status(chunk: &Value) -> Option<&str> {
+    match chunk_tag(chunk, "Status")? {
+        Value::String(status) => Some(status),
+        _ => None,
+    }
+}
+
+fn compound_get<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match value {
+        Value::Compound(compound) => compound.get(key),
+        _ => None,
+    }
+}
+
+/// The chunk's sections: `sections` in 1.18+, `Level.Sections` before.
+pub fn chunk_sections(chunk: &Value) -> Option<&[Value]> {
+    match chunk_tag(chunk, "sections").or_else(|| chunk_tag(chunk, "Sections"))? {
+        Value::List(sections) => Some(sections),
+        _ => None,
+    }
+}
+
+/// The section's `Y`, in sections of 16 blocks.
+pub fn section_y(section: &Value) -> Option<i32> {
+    match compound_get(section, "Y")? {
+        Value::Byte(y) => Some(*y as i32),
+        Value::Int(y) => Some(*y),
+        _ => None,
+    }
+}
+
+/// The block state palette of a section and its packed indices, if it has
+/// more than one entry: `block_states` in 1.18+, `Palette` and `BlockStates`
+/// before.
+pub fn section_block_states(section: &Value) -> Option<(&[Value], Option<&[i64]>)> {
+    let (palette, data) = match compound_get(section, "block_states") {
+        Some(states) => (
+            compound_get(states, "palette")?,
+            compound_get(states, "data"),
+        ),
+        None => (
+            compound_get(section, "Palette")?,
+            compound_get(section, "BlockStates"),
+        ),
+    };
+    let Value::List(palette) = palette else {
+        return None;
+    };
+    let data = match data {
+        Some(Value::LongArray(data)) => Some(&data[..]),
+        _ => None,
+    };
+    Some((palette, data))
+}
+
+/// The `Name` of a block state palette entry.
+pub fn block_name(state: &Value) -> Option<&str> {
+    match compound_get(state, "Name")? {
+        Value::String(name) => Some(name),
+        _ => None,
+    }
+}
+
+/// How packed values are laid out in a long array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackingLayout {
+    /// Values follow each other bit by bit and may span two longs, as before 1.16.
+    Spanning,
+    /// Each long holds `64 / bits` values and leaves the remaining high bits
+    /// unused, as in 1.16+.
+    Aligned,
+}
+
+impl PackingLayout {
+    /// The layout of chunks saved with `data_version`. Chunks without one
+    /// predate palettes, so the choice does not matter for them.
+    pub fn for_data_version(data_version: Option<i32>) -> Self {
+        match data_version {
+            Some(version) if version < ALIGNED_PACKING_DATA_VERSION => Self::Spanning,
+            _ => Self::Aligned,
+        }
+    }
+
+    /// Longs needed for `count` values of `bits` bits.
+    pub fn packed_len(self, bits: usize, count: usize) -> usize {
+        match self {
+            Self::Spanning => (count * bits).div_ceil(64),
+            Self::Aligned => count.div_ceil(64 / bits),
+        }
+    }
+}
+
+/// Bits per entry of a heightmap packed into `longs` longs. Vanilla uses
+/// `ceil(log2(height + 1))` for the world height, e.g. 9 for 384 blocks; the
+/// narrowest width whose entries take exactly `longs` longs is picked.
+pub fn heightmap_bits(longs: usize, layout: PackingLayout) -> Option<usize> {
+    (1..=16).find(|&bits| layout.packed_len(bits, HEIGHTMAP_ENTRIES) == longs)
+}
+
+/// Minimum bits per index of block states; smaller palettes are padded to it.
+pub const BLOCK_STATE_MIN_BITS: usize = 4;
+/// Minimum bits per index of 1.18+ biomes.
+pub const BIOME_MIN_BITS: usize = 1;
+
+/// Bits per index for a palette with `palette_len` entries, at least `min_bits`.
+pub fn bits_per_entry(palette_len: usize, min_bits: usize) -> usize {
+    ((usize::BITS - palette_len.saturating_sub(1).leading_zeros()) as usize).max(min_bits)
+}
+
+/// Bits per index in the packed block states of a palette with `palette_len` entries.
+pub fn block_state_bits(palette_len: usize) -> usize {
+    bits_per_entry(palette_len, BLOCK_STATE_MIN_BITS)
+}
+
+/// Bits per index in the packed biomes of a palette with `palette_len` entries.
+pub fn biome_bits(palette_len: usize) -> usize {
+    bits_per_entry(palette_len, BIOME_MIN_BITS)
+}
+
+fn check_bits(bits: usize) -> Result<()> {
+    if !(1..=16).contains(&bits) {
+        bail!("Cannot pack values of {} bits", bits);
+    }
+    Ok(())
+}
+
+/// Unpacks `count` values of `bits` bits each from `data`, which must have
+/// exactly the number of longs `layout` needs.
+pub fn unpack_longs(
+    data: &[i64],
+    bits: usize,
+    count: usize,
+    layout: PackingLayout,
+) -> Result<Vec<u16>> {
+    check_bits(bits)?;
+    let expected = layout.packed_len(bits, count);
+    if data.len() != expected {
+        bail!(
+            "Packed array has {} longs, expected {} for {} values of {} bits",
+            data.len(),
+            expected,
+            count,
+            bits
+        );
+    }
+
+    let mask = (1u64 << bits) - 1;
+    let mut values = Vec::with_capacity(count);
+    match layout {
+        PackingLayout::Spanning => {
+            for i in 0..count {
+                let offset = i * bits;
+                let (word, shift) = (offset / 64, offset % 64);
+                let mut value = data[word] as u64 >> shift;
+                if shift + bits > 64 {
+                    value |= (data[word + 1] as u64) << (64 - shift);
+                }
+                values.push((value & mask) as u16);
+            }
+        }
+        PackingLayout::Aligned => {
+            let per_long = 64 / bits;
+            for i in 0..count {
+                let long = data[i / per_long] as u64;
+                values.push(((long >> ((i % per_long) * bits)) & mask) as u16);
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Packs `values` of `bits` bits each, the inverse of [`unpack_longs`].
+/// Values are truncated to `bits` bits.
+pub fn pack_longs(values: &[u16], bits: usize, layout: PackingLayout) -> Result<Vec<i64>> {
+    check_bits(bits)?;
+    let mask = (1u64 << bits) - 1;
+    let mut data = vec![0u64; layout.packed_len(bits, values.len())];
+    match layout {
+        PackingLayout::Spanning => {
+            for (i, &value) in values.iter().enumerate() {
+                let value = value as u64 & mask;
+                let offset = i * bits;
+                let (word, shift) = (offset / 64, offset % 64);
+                data[word] |= value << shift;
+                if shift + bits > 64 {
+                    data[word + 1] |= value >> (64 - shift);
+                }
+            }
+        }
+        PackingLayout::Aligned => {
+            let per_long = 64 / bits;
+            for (i, &value) in values.iter().enumerate() {
+                data[i / per_long] |= (value as u64 & mask) << ((i % per_long) * bits);
+            }
+        }
+    }
+    Ok(data.into_iter().map(|long| long as i64).collect())
+}
+
+/// Unpacked palette indices together with their palette, for reading and
+/// changing block states or biomes one entry at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PalettedValues<T> {
+    pub palette: Vec<T>,
+    pub indices: Vec<u16>,
+}
+
+impl<T: Clone + PartialEq> PalettedValues<T> {
+    /// `count` copies of `value`.
+    pub fn filled(value: T, count: usize) -> Self {
+        Self {
+            palette: vec![value],
+            indices: vec![0; count],
+        }
+    }
+
+    /// Unpacks `count` indices into `palette`. Without `data` every entry is
+    /// the first palette entry, as 1.18+ chunks store single-entry palettes.
+    pub fn unpack(
+        palette: Vec<T>,
+        data: Option<&[i64]>,
+        count: usize,
+        min_bits: usize,
+        layout: PackingLayout,
+    ) -> Result<Self> {
+        let indices = match (data, palette.len()) {
+            (_, 0) => bail!("Empty palette"),
+            (None, 1) => vec![0; count],
+            (None, len) => bail!("Palette has {} entries but no data", len),
+            (Some(data), len) => unpack_longs(data, bits_per_entry(len, min_bits), count, layout)?,
+        };
+        if let Some(&index) = indices.iter().find(|&&i| i as usize >= palette.len()) {
+            bail!(
+                "Palette index {} out of range ({} entries)",
+                index,
+                palette.len()
+            );
+        }
+        Ok(Self { palette, indices })
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> &T {
+        &self.palette[self.indices[index] as usize]
+    }
+
+    /// Sets entry `index`, adding `value` to the palette if it is new.
+    pub fn set(&mut self, index: usize, value: T) {
+        let palette_index = match self.palette.iter().position(|entry| *entry == value) {
+            Some(position) => position,
+            None => {
+                self.palette.push(value);
+                self.palette.len() - 1
+            }
+        };
+        self.indices[index] = palette_index as u16;
+    }
+
+    /// Drops palette entries no index refers to, keeping the order of the
+    /// rest. Returns the number of entries removed.
+    pub fn remove_unused(&mut self) -> usize {
+        let mut used = vec![false; self.palette.len()];
+        for &index in &self.indices {
+            used[index as usize] = true;
+        }
+        let mut remap = vec![0u16; self.palette.len()];
+        let mut kept = 0;
+        for (old, &is_used) in used.iter().enumerate() {
+            if is_used {
+                remap[old] = kept;
+                kept += 1;
+            }
+        }
+        let removed = self.palette.len() - kept as usize;
+        if removed > 0 {
+            let mut position = 0;
+            self.palette.retain(|_| {
+                position += 1;
+                used[position - 1]
+            });
+            for index in &mut self.indices {
+                *index = remap[*index as usize];
+            }
+        }
+        removed
+    }
+
+    /// Packs the indices with as few bits as the palette allows, at least
+    /// `min_bits`. 1.18+ chunks leave out the data of single-entry palettes.
+    pub fn pack(&self, min_bits: usize, layout: PackingLayout) -> Result<Vec<i64>> {
+        pack_longs(
+            &self.indices,
+            bits_per_entry(self.palette.len(), min_bits),
+            layout,
+        )
+    }
+}
+
+/// A block state palette entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockState {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(
+        rename = "Properties",
+        default,
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub properties: HashMap<String, String>,
+}
+
+/// A palette and the packed indices into it, as `block_states` and `biomes`
+/// are stored in 1.18+ sections. `data` is absent when the palette has a
+/// single entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PalettedContainer<T> {
+    pub palette: Vec<T>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<LongArray>,
+    #[serde(skip, default = "OnceLock::new")]
+    values: OnceLock<PalettedValues<T>>,
+}
+
+impl<T: Clone + PartialEq> PalettedContainer<T> {
+    /// The palette entry at position `index` of `count`, unpacking the
+    /// indices with at least `min_bits` bits each on first use.
+    fn entry(&self, index: usize, count: usize, min_bits: usize) -> Result<&T> {
+        let values = match self.values.get() {
+            Some(values) => values,
+            None => {
+                let values = PalettedValues::unpack(
+                    self.palette.clone(),
+                    self.data.as_deref(),
+                    count,
+                    min_bits,
+                    PackingLayout::Aligned,
+                )?;
+                self.values.get_or_init(|| values)
+            }
+        };
+        Ok(values.get(index))
+    }
+}
+
+/// A decoded 1.18+ chunk section. Tags without a field here are only kept
+/// in the raw section of [`LazySection`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChunkSection {
+    #[serde(rename = "Y")]
+    pub y: i8,
+    pub block_states: Option<PalettedContainer<BlockState>>,
+    pub biomes: Option<PalettedContainer<String>>,
+    #[serde(rename = "BlockLight")]
+    pub block_light: Option<ByteArray>,
+    #[serde(rename = "SkyLight")]
+    pub sky_light: Option<ByteArray>,
+}
+
+impl ChunkSection {
+    /// The block state at section coordinates `x`, `y`, `z` in `0..16`.
+    pub fn block_state(&self, x: usize, y: usize, z: usize) -> Result<Option<&BlockState>> {
+        let Some(states) = &self.block_states else {
+            return Ok(None);
+        };
+        let index = (y << 8) | (z << 4) | x;
+        states
+            .entry(index, SECTION_BLOCKS, BLOCK_STATE_MIN_BITS)
+            .map(Some)
+    }
+
+    /// The biome of the 4x4x4 cell at `x`, `y`, `z` in `0..4`.
+    pub fn biome(&self, x: usize, y: usize, z: usize) -> Result<Option<&str>> {
+        let Some(biomes) = &self.biomes else {
+            return Ok(None);
+        };
+        let index = (y << 4) | (z << 2) | x;
+        biomes
+            .entry(index, SECTION_BIOMES, BIOME_MIN_BITS)
+            .map(|biome| Some(biome.as_str()))
+    }
+}
+
+/// A chunk section kept as raw NBT until [`LazySection::get`] decodes it.
+/// It is written back exactly as it was read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LazySection {
+    raw: Value,
+    #[serde(skip)]
+    decoded: OnceLock<ChunkSection>,
+}
+
+impl LazySection {
+    pub fn raw(&self) -> &Value {
+        &self.raw
+    }
+
+    /// The section's `Y` without decoding the rest of it.
+    pub fn y(&self) -> Option<i32> {
+        section_y(&self.raw)
+    }
+
+    /// Decodes the section on first use.
+    pub fn get(&self) -> Result<&ChunkSection> {
+        if let Some(section) = self.decoded.get() {
+            return Ok(section);
+        }
+        let section = fastnbt::from_value(&self.raw).context("Failed to decode chunk section")?;
+        Ok(self.decoded.get_or_init(|| section))
+    }
+}
+
+/// Structure starts and references of a chunk. Empty `starts` are not written.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Structures {
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub starts: HashMap<String, Value>,
+    #[serde(rename = "References", default)]
+    pub references: HashMap<String, LongArray>,
+}
+
+/// A chunk in the 1.18+ format. Tags without a field here are kept in
+/// `other`, so a chunk read and written again keeps all its data. Proto-chunks
+/// may lack `sections`, `block_entities` and `Heightmaps`, which are then `None`
+/// and stay absent when written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModernChunk {
+    #[serde(rename = "DataVersion")]
+    pub data_version: i32,
+    #[serde(rename = "xPos")]
+    pub x_pos: i32,
+    #[serde(rename = "yPos")]
+    pub y_pos: i32,
+    #[serde(rename = "zPos")]
+    pub z_pos: i32,
+    #[serde(rename = "Status")]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sections: Option<Vec<LazySection>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_entities: Option<Vec<Value>>,
+    #[serde(
+        rename = "Heightmaps",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub heightmaps: Option<HashMap<String, LongArray>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structures: Option<Structures>,
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
+
+impl ModernChunk {
+    /// Parses uncompressed chunk NBT, failing for chunks older than 1.18.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if let Ok(version) = from_bytes::<DataVersionOnly>(data)
+            && version.data_version < MODERN_CHUNK_DATA_VERSION
+        {
+            bail!(
+                "Chunk has DataVersion {}, the 1.18 format starts at {}",
+                version.data_version,
+                MODERN_CHUNK_DATA_VERSION
+            );
+        }
+        from_bytes(data).context("Failed to parse 1.18+ chunk")
+    }
+
+    pub fn from_chunk(chunk: &Chunk) -> Result<Self> {
+        Self::from_bytes(chunk.as_slice())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        to_bytes(self).context("Failed to serialize chunk")
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        Ok(Chunk::new(self.to_bytes()?, self.x_pos, self.z_pos))
+    }
+
+    /// The section at section Y `y`, in sections of 16 blocks.
+    pub fn section(&self, y: i32) -> Option<&LazySection> {
+        self.sections
+            .iter()
+            .flatten()
+            .find(|section| section.y() == Some(y))
+    }
+
+    /// The block state at chunk coordinates `x`, `z` in `0..16` and world
+    /// height `y`, or `None` where the chunk has no section.
+    pub fn block_state(&self, x: usize, y: i32, z: usize) -> Result<Option<&BlockState>> {
+        match self.section(y.div_euclid(16)) {
+            Some(section) => section.get()?.block_state(x, y.rem_euclid(16) as usize, z),
+            None => Ok(None),
+        }
+    }
+
+    /// The heightmap `name`, e.g. `MOTION_BLOCKING`, as the world height of
+    /// the first free block above each column, indexed `z * 16 + x`.
+    pub fn heightmap(&self, name: &str) -> Result<Option<Vec<i32>>> {
+        let Some(data) = self.heightmaps.as_ref().and_then(|maps| maps.get(name)) else {
+            return Ok(None);
+        };
+        let min_y = self.y_pos * 16;
+        let layout = PackingLayout::Aligned;
+        let Some(bits) = heightmap_bits(data.len(), layout) else {
+            bail!(
+                "Heightmap {} has {} longs, which fit no bit width",
+                name,
+                data.len()
+            );
+        };
+        let heights = unpack_longs(data, bits, HEIGHTMAP_ENTRIES, layout)?;
+        Ok(Some(
+            heights.into_iter().map(|h| min_y + h as i32).collect(),
+        ))
+    }
+}
+
+/// Reads only the `DataVersion` of a chunk.
+#[derive(Deserialize)]
+struct DataVersionOnly {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+}
@@ -0,0 +1,142 @@
+use crate::Chunk;
+use anyhow::{Context, Result};
+use fastnbt::Value;
+use std::collections::HashMap;
+
+type Compound = HashMap<String, Value>;
+
+fn checked_shift(value: i32, delta: i32, key: &str) -> Result<i32> {
+    value
+        .checked_add(delta)
+        .with_context(|| format!("Moving {} {} by {} overflows", key, value, delta))
+}
+
+fn shift_int(compound: &mut Compound, key: &str, delta: i32) -> Result<()> {
+    if let Some(Value::Int(value)) = compound.get_mut(key) {
+        *value = checked_shift(*value, delta, key)?;
+    }
+    Ok(())
+}
+
+/// Shifts the x and z of an `[x, y, z]` int array, or of `[x, z]` with `has_y` unset.
+fn shift_int_array(
+    compound: &mut Compound,
+    key: &str,
+    has_y: bool,
+    dx: i32,
+    dz: i32,
+) -> Result<()> {
+    if let Some(Value::IntArray(pos)) = compound.get_mut(key) {
+        let z_index = if has_y { 2 } else { 1 };
+        if pos.len() > z_index {
+            pos[0] = checked_shift(pos[0], dx, key)?;
+            pos[z_index] = checked_shift(pos[z_index], dz, key)?;
+        }
+    }
+    Ok(())
+}
+
+fn compounds_mut<'a>(
+    compound: &'a mut Compound,
+    key: &str,
+) -> impl Iterator<Item = &'a mut Compound> + 'a {
+    let list = match compound.get_mut(key) {
+        Some(Value::List(list)) => Some(list),
+        _ => None,
+    };
+    list.into_iter().flatten().filter_map(|value| match value {
+        Value::Compound(compound) => Some(compound),
+        _ => None,
+    })
+}
+
+fn shift_entity(entity: &mut Compound, dx: i32, dz: i32) -> Result<()> {
+    if let Some(Value::List(pos)) = entity.get_mut("Pos")
+        && pos.len() == 3
+    {
+        for (index, delta) in [(0, dx), (2, dz)] {
+            if let Value::Double(value) = &mut pos[index] {
+                *value += delta as f64;
+            }
+        }
+    }
+    // Item frames and paintings also store the block they hang on
+    shift_int(entity, "TileX", dx)?;
+    shift_int(entity, "TileZ", dz)?;
+    shift_int_array(entity, "block_pos", true, dx, dz)?;
+
+    for passenger in compounds_mut(entity, "Passengers") {
+        shift_entity(passenger, dx, dz)?;
+    }
+    Ok(())
+}
+
+/// Shifts the block positions stored in a chunk compound, either the root of
+/// a 1.18+ chunk or the `Level` compound of an older one.
+fn shift_chunk_compound(chunk: &mut Compound, dx_chunks: i32, dz_chunks: i32) -> Result<()> {
+    let (dx, dz) = dx_chunks
+        .checked_mul(16)
+        .zip(dz_chunks.checked_mul(16))
+        .context("Chunk offset is too large to move blocks by")?;
+
+    shift_int(chunk, "xPos", dx_chunks)?;
+    shift_int(chunk, "zPos", dz_chunks)?;
+    // Entity chunks store their position as [x, z]
+    shift_int_array(chunk, "Position", false, dx_chunks, dz_chunks)?;
+
+    for key in ["block_entities", "TileEntities"] {
+        for block_entity in compounds_mut(chunk, key) {
+            shift_int(block_entity, "x", dx)?;
+            shift_int(block_entity, "z", dz)?;
+        }
+    }
+    for key in ["block_ticks", "fluid_ticks", "TileTicks", "LiquidTicks"] {
+        for tick in compounds_mut(chunk, key) {
+            shift_int(tick, "x", dx)?;
+            shift_int(tick, "z", dz)?;
+        }
+    }
+    for entity in compounds_mut(chunk, "Entities") {
+        shift_entity(entity, dx, dz)?;
+    }
+
+    // POI chunks keep records per section
+    if let Some(Value::Compound(sections)) = chunk.get_mut("Sections") {
+        for section in sections.values_mut() {
+            if let Value::Compound(section) = section {
+                for record in compounds_mut(section, "Records") {
+                    shift_int_array(record, "pos", true, dx, dz)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rewrites the coordinates inside a region, entity or POI chunk moved by
+/// (`dx_chunks`, `dz_chunks`): `xPos`/`zPos`, entity-chunk `Position`, block
+/// entity and scheduled tick positions, entity `Pos` (passengers included)
+/// and POI record positions. Structure references are left as they are.
+/// Fails if a coordinate would overflow, leaving `nbt` partly moved.
+pub fn relocate_nbt(nbt: &mut Value, dx_chunks: i32, dz_chunks: i32) -> Result<()> {
+    let Value::Compound(root) = nbt else {
+        return Ok(());
+    };
+    shift_chunk_compound(root, dx_chunks, dz_chunks)?;
+    if let Some(Value::Compound(level)) = root.get_mut("Level") {
+        shift_chunk_compound(level, dx_chunks, dz_chunks)?;
+    }
+    Ok(())
+}
+
+/// A copy of `chunk` moved to chunk (`x`, `z`), with its NBT rewritten by
+/// [`relocate_nbt`].
+pub fn relocate_chunk(chunk: &Chunk, x: i32, z: i32) -> Result<Chunk> {
+    let mut nbt = chunk.parse_nbt()?;
+    let (dx, dz) = x
+        .checked_sub(chunk.x)
+        .zip(z.checked_sub(chunk.z))
+        .context("Chunk offset is out of range")?;
+    relocate_nbt(&mut nbt, dx, dz)?;
+    Chunk::from_nbt(&nbt, x, z)
+}
@@ -0,0 +1,399 @@
+use crate::{nbt, Region, CHUNKS_PER_REGION, REGION_DIMENSION};
+use anyhow::{bail, Context, Result};
+use fastnbt::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Width and height in pixels of a region tile, one pixel per block.
+pub const TILE_SIZE: usize = REGION_DIMENSION * 16;
+
+pub type Rgba = [u8; 4];
+
+pub const TRANSPARENT: Rgba = [0, 0, 0, 0];
+/// Colour of chunks that could not be rendered, so damage stands out.
+pub const UNREADABLE_COLOR: Rgba = [255, 0, 0, 255];
+
+/// Colours of common surface blocks, as `0xRRGGBB`.
+const DEFAULT_COLORS: &[(&str, u32)] = &[
+    ("minecraft:grass_block", 0x7cbd6b),
+    ("minecraft:short_grass", 0x7cbd6b),
+    ("minecraft:tall_grass", 0x7cbd6b),
+    ("minecraft:fern", 0x6a9a55),
+    ("minecraft:dirt", 0x866043),
+    ("minecraft:coarse_dirt", 0x77553b),
+    ("minecraft:podzol", 0x5b3f1f),
+    ("minecraft:mycelium", 0x6f6265),
+    ("minecraft:dirt_path", 0x94794a),
+    ("minecraft:farmland", 0x6e4a2a),
+    ("minecraft:mud", 0x3c393d),
+    ("minecraft:stone", 0x7d7d7d),
+    ("minecraft:cobblestone", 0x7a7a7a),
+    ("minecraft:andesite", 0x888888),
+    ("minecraft:diorite", 0xbcbcbc),
+    ("minecraft:granite", 0x956755),
+    ("minecraft:deepslate", 0x505052),
+    ("minecraft:tuff", 0x6c6d66),
+    ("minecraft:calcite", 0xdfe0dc),
+    ("minecraft:gravel", 0x837f7e),
+    ("minecraft:sand", 0xdbcfa3),
+    ("minecraft:red_sand", 0xbe6621),
+    ("minecraft:sandstone", 0xd8cb9b),
+    ("minecraft:clay", 0xa0a6b3),
+    ("minecraft:terracotta", 0x985e43),
+    ("minecraft:water", 0x3f76e4),
+    ("minecraft:bubble_column", 0x3f76e4),
+    ("minecraft:lava", 0xcf5b13),
+    ("minecraft:ice", 0x91b7fd),
+    ("minecraft:packed_ice", 0x8db4fa),
+    ("minecraft:blue_ice", 0x74a8fd),
+    ("minecraft:snow", 0xf9fefe),
+    ("minecraft:snow_block", 0xf9fefe),
+    ("minecraft:powder_snow", 0xf8fdfd),
+    ("minecraft:oak_leaves", 0x4c8a2e),
+    ("minecraft:spruce_leaves", 0x3b5e3b),
+    ("minecraft:birch_leaves", 0x6a9443),
+    ("minecraft:jungle_leaves", 0x3e8c1e),
+    ("minecraft:acacia_leaves", 0x5a8a26),
+    ("minecraft:dark_oak_leaves", 0x3a6e1f),
+    ("minecraft:mangrove_leaves", 0x4f8a2a),
+    ("minecraft:cherry_leaves", 0xe5adc2),
+    ("minecraft:azalea_leaves", 0x5a7a2d),
+    ("minecraft:oak_log", 0x6d5532),
+    ("minecraft:spruce_log", 0x3b2712),
+    ("minecraft:birch_log", 0xd8d7d2),
+    ("minecraft:oak_planks", 0xa2834f),
+    ("minecraft:spruce_planks", 0x735531),
+    ("minecraft:netherrack", 0x6f3634),
+    ("minecraft:soul_sand", 0x513e32),
+    ("minecraft:soul_soil", 0x4b3a2e),
+    ("minecraft:basalt", 0x505155),
+    ("minecraft:blackstone", 0x2a2328),
+    ("minecraft:crimson_nylium", 0x831f1f),
+    ("minecraft:warped_nylium", 0x2b7265),
+    ("minecraft:glowstone", 0xaa8654),
+    ("minecraft:magma_block", 0x8e3f1f),
+    ("minecraft:bedrock", 0x555555),
+    ("minecraft:end_stone", 0xdbde9e),
+    ("minecraft:obsidian", 0x0f0b19),
+    ("minecraft:chorus_plant", 0x5d395d),
+    ("minecraft:purpur_block", 0xa97ea9),
+    ("minecraft:moss_block", 0x596e2d),
+    ("minecraft:glass", 0xc0d8de),
+];
+
+fn rgb(color: u32) -> Rgba {
+    [(color >> 16) as u8, (color >> 8) as u8, color as u8, 255]
+}
+
+/// A colour for a block missing from the table, guessed from its name.
+fn guess_color(block: &str) -> Rgba {
+    let name = block.rsplit(':').next().unwrap_or(block);
+    let guesses: &[(&str, u32)] = &[
+        ("leaves", 0x4c8a2e),
+        ("water", 0x3f76e4),
+        ("ice", 0x91b7fd),
+        ("snow", 0xf9fefe),
+        ("log", 0x6d5532),
+        ("wood", 0x6d5532),
+        ("planks", 0xa2834f),
+        ("stairs", 0x9a9a9a),
+        ("slab", 0x9a9a9a),
+        ("sand", 0xdbcfa3),
+        ("grass", 0x7cbd6b),
+        ("flower", 0x7cbd6b),
+        ("terracotta", 0x985e43),
+        ("concrete", 0xa0a0a0),
+        ("wool", 0xe9ecec),
+        ("deepslate", 0x505052),
+        ("stone", 0x7d7d7d),
+        ("ore", 0x7d7d7d),
+        ("nether", 0x6f3634),
+        ("copper", 0xc06b4f),
+    ];
+    guesses
+        .iter()
+        .find(|(keyword, _)| name.contains(keyword))
+        .map(|&(_, color)| rgb(color))
+        .unwrap_or(rgb(0x8f8f8f))
+}
+
+fn is_air(block: &str) -> bool {
+    matches!(
+        block,
+        "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air"
+    )
+}
+
+/// Block name to map colour. Blocks that are not listed get a colour guessed
+/// from their name.
+#[derive(Debug, Clone)]
+pub struct ColorTable {
+    colors: HashMap<String, Rgba>,
+}
+
+impl Default for ColorTable {
+    fn default() -> Self {
+        Self {
+            colors: DEFAULT_COLORS
+                .iter()
+                .map(|&(block, color)| (block.to_string(), rgb(color)))
+                .collect(),
+        }
+    }
+}
+
+impl ColorTable {
+    pub fn set(&mut self, block: &str, color: Rgba) {
+        self.colors.insert(block.to_string(), color);
+    }
+
+    pub fn color(&self, block: &str) -> Rgba {
+        self.colors
+            .get(block)
+            .copied()
+            .unwrap_or_else(|| guess_color(block))
+    }
+
+    /// Adds the colours in `text`, one `block = RRGGBB` or `block = RRGGBBAA`
+    /// per line with an optional `#` before the colour. Lines starting with
+    /// `#` are comments.
+    pub fn extend_from_str(&mut self, text: &str) -> Result<()> {
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parse = || {
+                let (block, color) = line.split_once('=')?;
+                let color = color.trim();
+                let color = color.strip_prefix('#').unwrap_or(color);
+                let value = u32::from_str_radix(color, 16).ok()?;
+                let color = match color.len() {
+                    6 => rgb(value),
+                    8 => value.to_be_bytes(),
+                    _ => return None,
+                };
+                Some((block.trim(), color))
+            };
+            let Some((block, color)) = parse() else {
+                bail!(
+                    "Line {}: expected `block = RRGGBB`, found `{}`",
+                    number + 1,
+                    line
+                );
+            };
+            self.set(block, color);
+        }
+        Ok(())
+    }
+
+    /// The built-in table with the colours of the file at `path` added.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut table = Self::default();
+        table
+            .extend_from_str(&text)
+            .with_context(|| format!("Invalid colour table {}", path.display()))?;
+        Ok(table)
+    }
+}
+
+/// A section's block names, unpacked.
+struct DecodedSection<'a> {
+    y: i32,
+    blocks: nbt::PalettedValues<&'a str>,
+}
+
+impl DecodedSection<'_> {
+    fn block(&self, x: usize, y: usize, z: usize) -> &str {
+        self.blocks.get((y << 8) | (z << 4) | x)
+    }
+}
+
+fn decode_sections(chunk: &Value) -> Result<Vec<DecodedSection<'_>>> {
+    let layout = nbt::PackingLayout::for_data_version(nbt::data_version(chunk));
+    let mut decoded = Vec::new();
+    for section in nbt::chunk_sections(chunk).unwrap_or_default() {
+        let (Some(y), Some((palette, data))) =
+            (nbt::section_y(section), nbt::section_block_states(section))
+        else {
+            continue;
+        };
+        let names: Vec<&str> = palette
+            .iter()
+            .map(|entry| nbt::block_name(entry).unwrap_or("unknown"))
+            .collect();
+        let blocks = nbt::PalettedValues::unpack(
+            names,
+            data,
+            nbt::SECTION_BLOCKS,
+            nbt::BLOCK_STATE_MIN_BITS,
+            layout,
+        )
+        .with_context(|| format!("Invalid block states in section {}", y))?;
+        decoded.push(DecodedSection { y, blocks });
+    }
+    decoded.sort_by_key(|section| std::cmp::Reverse(section.y));
+    Ok(decoded)
+}
+
+/// Surface heights from the `MOTION_BLOCKING` or `WORLD_SURFACE` heightmap,
+/// as the Y of the topmost block of each column.
+fn surface_heights(chunk: &Value) -> Option<Vec<i32>> {
+    let Some(Value::Compound(heightmaps)) = nbt::chunk_tag(chunk, "Heightmaps") else {
+        return None;
+    };
+    let data = ["MOTION_BLOCKING", "WORLD_SURFACE"]
+        .iter()
+        .find_map(|name| match heightmaps.get(*name) {
+            Some(Value::LongArray(data)) => Some(data),
+            _ => None,
+        })?;
+    // Heightmaps count from the bottom of the world, which 1.18+ chunks store in yPos
+    let min_y = match nbt::chunk_tag(chunk, "yPos") {
+        Some(Value::Int(y)) => *y * 16,
+        _ => 0,
+    };
+    let layout = nbt::PackingLayout::for_data_version(nbt::data_version(chunk));
+    let bits = nbt::heightmap_bits(data.len(), layout)?;
+    let heights = nbt::unpack_longs(data, bits, nbt::HEIGHTMAP_ENTRIES, layout).ok()?;
+    Some(heights.into_iter().map(|h| min_y + h as i32 - 1).collect())
+}
+
+/// The colour and height of the top non-air block in every column of
+/// `chunk`, indexed `z * 16 + x`, or `None` for empty columns. The heightmap
+/// is trusted when it points at a solid block; otherwise the column is
+/// searched from the top.
+pub fn render_chunk(chunk: &Value, colors: &ColorTable) -> Result<Vec<Option<(Rgba, i32)>>> {
+    let sections = decode_sections(chunk)?;
+    let heights = surface_heights(chunk);
+
+    let block_at = |x: usize, y: i32, z: usize| {
+        let section = sections
+            .iter()
+            .find(|section| section.y == y.div_euclid(16))?;
+        Some(section.block(x, y.rem_euclid(16) as usize, z))
+    };
+
+    let mut columns = vec![None; 256];
+    for (column, pixel) in columns.iter_mut().enumerate() {
+        let (x, z) = (column % 16, column / 16);
+        if let Some(heights) = &heights
+            && let Some(block) = block_at(x, heights[column], z)
+            && !is_air(block)
+        {
+            *pixel = Some((colors.color(block), heights[column]));
+            continue;
+        }
+
+        'search: for section in &sections {
+            for layer in (0..16).rev() {
+                let block = section.block(x, layer, z);
+                if !is_air(block) {
+                    *pixel = Some((colors.color(block), section.y * 16 + layer as i32));
+                    break 'search;
+                }
+            }
+        }
+    }
+    Ok(columns)
+}
+
+/// A top-down image of one region.
+#[derive(Debug, Clone)]
+pub struct RegionMap {
+    /// `TILE_SIZE * TILE_SIZE` pixels, row by row from the north-west corner
+    pub pixels: Vec<Rgba>,
+    pub chunks_rendered: usize,
+    /// Chunks drawn in [`UNREADABLE_COLOR`]
+    pub chunks_unreadable: usize,
+}
+
+/// Renders `region` with one pixel per block. Missing chunks stay
+/// transparent, and each block is shaded lighter or darker than the one to
+/// its north depending on whether it is higher or lower.
+pub fn render_region(region: &Region, colors: &ColorTable) -> RegionMap {
+    let mut pixels = vec![TRANSPARENT; TILE_SIZE * TILE_SIZE];
+    let mut heights = vec![None; TILE_SIZE * TILE_SIZE];
+    let mut map = RegionMap {
+        pixels: Vec::new(),
+        chunks_rendered: 0,
+        chunks_unreadable: 0,
+    };
+
+    for index in 0..CHUNKS_PER_REGION {
+        let Some(chunk) = region.get_chunk(index) else {
+            continue;
+        };
+        let origin_x = (index % REGION_DIMENSION) * 16;
+        let origin_z = (index / REGION_DIMENSION) * 16;
+        let columns = chunk.parse_nbt().and_then(|nbt| render_chunk(&nbt, colors));
+
+        match columns {
+            Ok(columns) => {
+                map.chunks_rendered += 1;
+                for (column, pixel) in columns.into_iter().enumerate() {
+                    let offset = (origin_z + column / 16) * TILE_SIZE + origin_x + column % 16;
+                    if let Some((color, height)) = pixel {
+                        pixels[offset] = color;
+                        heights[offset] = Some(height);
+                    }
+                }
+            }
+            Err(_) => {
+                map.chunks_unreadable += 1;
+                for z in 0..16 {
+                    let start = (origin_z + z) * TILE_SIZE + origin_x;
+                    pixels[start..start + 16].fill(UNREADABLE_COLOR);
+                }
+            }
+        }
+    }
+
+    for offset in TILE_SIZE..pixels.len() {
+        let (Some(height), Some(north)) = (heights[offset], heights[offset - TILE_SIZE]) else {
+            continue;
+        };
+        let factor = match height.cmp(&north) {
+            std::cmp::Ordering::Greater => 1.15,
+            std::cmp::Ordering::Less => 0.85,
+            std::cmp::Ordering::Equal => continue,
+        };
+        let pixel = &mut pixels[offset];
+        for channel in &mut pixel[..3] {
+            *channel = (*channel as f32 * factor).min(255.0) as u8;
+        }
+    }
+
+    map.pixels = pixels;
+    map
+}
+
+/// Shrinks a square image of `size` pixels by `factor`, keeping the
+/// top-left pixel of every `factor` x `factor` block.
+pub fn downscale(pixels: &[Rgba], size: usize, factor: usize) -> Vec<Rgba> {
+    let scaled = size / factor;
+    let mut out = Vec::with_capacity(scaled * scaled);
+    for z in 0..scaled {
+        for x in 0..scaled {
+            out.push(pixels[z * factor * size + x * factor]);
+        }
+    }
+    out
+}
+
+/// Writes RGBA `pixels` as a PNG of `width` x `height`.
+pub fn write_png(path: &Path, width: usize, height: usize, pixels: &[Rgba]) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels.as_flattened())?;
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use crate::{
+    anvil::read_anvil_chunk_storage, format::FormatRegistry, nbt, Chunk, CHUNKS_PER_REGION,
+    COMPRESSION_TYPE_GZIP, COMPRESSION_TYPE_LZ4, COMPRESSION_TYPE_NONE, COMPRESSION_TYPE_ZLIB,
+    REGION_DIMENSION,
+};
+use anyhow::{bail, Context, Result};
+use fastnbt::Value;
+use serde::Serialize;
+use std::path::Path;
+
+/// Metadata of one chunk, as written by the `scan` command. Fields read from
+/// the chunk's NBT are `None` when it does not parse or lacks the tag.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChunkRecord {
+    /// Dimension id when scanning a world
+    pub dimension: Option<String>,
+    pub region_x: i32,
+    pub region_z: i32,
+    /// Index of the chunk in its region, `z * 32 + x`
+    pub index: usize,
+    pub x: i32,
+    pub z: i32,
+    pub timestamp: u32,
+    /// Uncompressed NBT size in bytes
+    pub size: usize,
+    /// Compressed size in bytes; Linear files compress chunks together and have none
+    pub compressed_size: Option<u64>,
+    /// `gzip`, `zlib`, `none` or `lz4` for Anvil, `zstd` for Linear
+    pub compression: String,
+    /// Whether the chunk is stored in a `.mcc` file
+    pub external: bool,
+    pub data_version: Option<i32>,
+    pub status: Option<String>,
+    pub inhabited_time: Option<i64>,
+    /// Entities stored with the chunk, or in the matching entity chunk when
+    /// one is given to [`scan_region_file`]
+    pub entities: Option<usize>,
+    pub block_entities: Option<usize>,
+}
+
+/// Name of an Anvil compression type byte.
+pub fn compression_name(compression_type: u8) -> String {
+    match compression_type {
+        COMPRESSION_TYPE_GZIP => "gzip".to_string(),
+        COMPRESSION_TYPE_ZLIB => "zlib".to_string(),
+        COMPRESSION_TYPE_NONE => "none".to_string(),
+        COMPRESSION_TYPE_LZ4 => "lz4".to_string(),
+        other => format!("unknown ({})", other),
+    }
+}
+
+fn list_len(chunk: &Value, name: &str) -> Option<usize> {
+    match nbt::chunk_tag(chunk, name)? {
+        Value::List(list) => Some(list.len()),
+        _ => None,
+    }
+}
+
+/// Fills the NBT-derived fields of a record for `chunk`, leaving the storage
+/// fields for the caller.
+fn chunk_record(chunk: &Chunk, index: usize, timestamp: u32) -> ChunkRecord {
+    let parsed = chunk.parse_nbt().ok();
+    let parsed = parsed.as_ref();
+    ChunkRecord {
+        dimension: None,
+        region_x: chunk.x.div_euclid(REGION_DIMENSION as i32),
+        region_z: chunk.z.div_euclid(REGION_DIMENSION as i32),
+        index,
+        x: chunk.x,
+        z: chunk.z,
+        timestamp,
+        size: chunk.size(),
+        compressed_size: None,
+        compression: String::new(),
+        external: false,
+        data_version: parsed.and_then(nbt::data_version),
+        status: parsed.and_then(nbt::chunk_status).map(str::to_string),
+        inhabited_time: parsed.and_then(nbt::inhabited_time),
+        entities: parsed.and_then(|nbt| list_len(nbt, "Entities")),
+        block_entities: parsed.and_then(|nbt| {
+            list_len(nbt, "block_entities").or_else(|| list_len(nbt, "TileEntities"))
+        }),
+    }
+}
+
+/// Describes every chunk of the region file at `path`, in index order. With
+/// `entities`, the path of the matching file in a 1.17+ `entities/` folder,
+/// entity counts come from there and chunks without an entity chunk count zero.
+pub fn scan_region_file(
+    registry: &FormatRegistry,
+    path: &Path,
+    entities: Option<&Path>,
+) -> Result<Vec<ChunkRecord>> {
+    let read = |path: &Path| {
+        let Some(format) = registry.detect_file(path)? else {
+            bail!("{} is not a recognised region file", path.display());
+        };
+        let region = format
+            .read(path, None)
+            .with_context(|| format!("Failed to read {} as {}", path.display(), format.name()))?;
+        Ok((region, format))
+    };
+    let (region, format) = read(path)?;
+    let storage = match format.name() {
+        "mca" => Some(read_anvil_chunk_storage(path)?),
+        _ => None,
+    };
+    let entity_region = entities
+        .map(|path| read(path).map(|(region, _)| region))
+        .transpose()?;
+
+    let mut records = Vec::with_capacity(region.chunk_count());
+    for index in 0..CHUNKS_PER_REGION {
+        let Some(chunk) = region.get_chunk(index) else {
+            continue;
+        };
+        let mut record = chunk_record(chunk, index, region.timestamps[index]);
+        match &storage {
+            Some(storage) => {
+                if let Some(stored) = storage[index] {
+                    record.compressed_size = Some(stored.compressed_size);
+                    record.compression = compression_name(stored.compression_type);
+                    record.external = stored.external;
+                }
+            }
+            None => record.compression = "zstd".to_string(),
+        }
+        if let Some(entity_region) = &entity_region {
+            record.entities = Some(
+                entity_region
+                    .get_chunk(index)
+                    .and_then(|chunk| chunk.parse_nbt().ok())
+                    .and_then(|nbt| list_len(&nbt, "Entities"))
+                    .unwrap_or(0),
+            );
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
@@ -0,0 +1,454 @@
+use crate::REGION_DIMENSION;
+use anyhow::{Context, Result};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An inclusive rectangle of chunk coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkBox {
+    pub min_x: i32,
+    pub min_z: i32,
+    pub max_x: i32,
+    pub max_z: i32,
+}
+
+impl ChunkBox {
+    /// The box spanning two corners given in any order.
+    pub fn new(x1: i32, z1: i32, x2: i32, z2: i32) -> Self {
+        Self {
+            min_x: x1.min(x2),
+            min_z: z1.min(z2),
+            max_x: x1.max(x2),
+            max_z: z1.max(z2),
+        }
+    }
+
+    /// The chunks overlapping a box of block coordinates.
+    pub fn from_blocks(x1: i32, z1: i32, x2: i32, z2: i32) -> Self {
+        Self::new(x1 >> 4, z1 >> 4, x2 >> 4, z2 >> 4)
+    }
+
+    /// The 32x32 chunks of region (`region_x`, `region_z`).
+    pub fn region(region_x: i32, region_z: i32) -> Self {
+        let size = REGION_DIMENSION as i32;
+        Self::new(
+            region_x * size,
+            region_z * size,
+            region_x * size + size - 1,
+            region_z * size + size - 1,
+        )
+    }
+
+    #[inline]
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        (self.min_x..=self.max_x).contains(&x) && (self.min_z..=self.max_z).contains(&z)
+    }
+
+    fn overlap(&self, other: &ChunkBox) -> Overlap {
+        if self.max_x < other.min_x
+            || other.max_x < self.min_x
+            || self.max_z < other.min_z
+            || other.max_z < self.min_z
+        {
+            Overlap::None
+        } else if self.contains(other.min_x, other.min_z) && self.contains(other.max_x, other.max_z)
+        {
+            Overlap::Full
+        } else {
+            Overlap::Partial
+        }
+    }
+}
+
+/// How much of a region a [`ChunkSelection`] covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    None,
+    Partial,
+    Full,
+}
+
+/// A set of chunks, built from boxes, circles and region lists combined with
+/// union, intersection and negation.
+///
+/// The text form, accepted by [`FromStr`] and produced by [`fmt::Display`]:
+///
+/// - `chunks(x1, z1, x2, z2)`: chunk coordinates, inclusive
+/// - `blocks(x1, z1, x2, z2)`: the chunks overlapping a box of block coordinates
+/// - `circle(x, z, radius)`: chunks whose centre is within `radius` blocks of block (`x`, `z`)
+/// - `regions(x, z, ...)`: whole regions, as pairs of region coordinates
+/// - `all` and `none`
+/// - `a | b`, `a & b`, `!a` and parentheses; `&` binds tighter than `|`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkSelection {
+    All,
+    Box(ChunkBox),
+    Circle {
+        center_x: i32,
+        center_z: i32,
+        radius: u32,
+    },
+    Regions(Vec<(i32, i32)>),
+    /// Chunks in any member; empty selects nothing
+    Union(Vec<ChunkSelection>),
+    /// Chunks in every member
+    Intersection(Vec<ChunkSelection>),
+    Not(Box<ChunkSelection>),
+}
+
+impl Default for ChunkSelection {
+    /// Selects nothing.
+    fn default() -> Self {
+        ChunkSelection::Union(Vec::new())
+    }
+}
+
+fn circle_contains(center_x: i32, center_z: i32, radius: u32, x: i32, z: i32) -> bool {
+    let dx = (x as i64) * 16 + 8 - center_x as i64;
+    let dz = (z as i64) * 16 + 8 - center_z as i64;
+    dx * dx + dz * dz <= (radius as i64) * (radius as i64)
+}
+
+impl ChunkSelection {
+    /// The union of `members`, or the only member if there is just one.
+    pub fn union(mut members: Vec<ChunkSelection>) -> Self {
+        match members.len() {
+            1 => members.pop().unwrap(),
+            _ => ChunkSelection::Union(members),
+        }
+    }
+
+    /// Whether chunk (`x`, `z`) is selected.
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        match self {
+            ChunkSelection::All => true,
+            ChunkSelection::Box(area) => area.contains(x, z),
+            ChunkSelection::Circle {
+                center_x,
+                center_z,
+                radius,
+            } => circle_contains(*center_x, *center_z, *radius, x, z),
+            ChunkSelection::Regions(regions) => {
+                let size = REGION_DIMENSION as i32;
+                regions.contains(&(x.div_euclid(size), z.div_euclid(size)))
+            }
+            ChunkSelection::Union(members) => members.iter().any(|m| m.contains(x, z)),
+            ChunkSelection::Intersection(members) => members.iter().all(|m| m.contains(x, z)),
+            ChunkSelection::Not(inner) => !inner.contains(x, z),
+        }
+    }
+
+    /// How much of region (`region_x`, `region_z`) is selected, so whole
+    /// region files can be skipped or taken without checking every chunk.
+    /// [`Overlap::Partial`] may be returned for a region that turns out to be
+    /// fully selected or not at all when members of a union or intersection
+    /// only cover it together.
+    pub fn region_overlap(&self, region_x: i32, region_z: i32) -> Overlap {
+        let region = ChunkBox::region(region_x, region_z);
+        match self {
+            ChunkSelection::All => Overlap::Full,
+            ChunkSelection::Box(area) => area.overlap(&region),
+            ChunkSelection::Circle {
+                center_x,
+                center_z,
+                radius,
+            } => {
+                let inside = |x, z| circle_contains(*center_x, *center_z, *radius, x, z);
+                // Chunk centres are a grid, so the closest one is the clamped chunk
+                // holding the centre, and the circle covers the region if it covers
+                // the four corner chunks
+                let nearest_x = (center_x >> 4).clamp(region.min_x, region.max_x);
+                let nearest_z = (center_z >> 4).clamp(region.min_z, region.max_z);
+                if !inside(nearest_x, nearest_z) {
+                    Overlap::None
+                } else if inside(region.min_x, region.min_z)
+                    && inside(region.min_x, region.max_z)
+                    && inside(region.max_x, region.min_z)
+                    && inside(region.max_x, region.max_z)
+                {
+                    Overlap::Full
+                } else {
+                    Overlap::Partial
+                }
+            }
+            ChunkSelection::Regions(regions) => {
+                if regions.contains(&(region_x, region_z)) {
+                    Overlap::Full
+                } else {
+                    Overlap::None
+                }
+            }
+            ChunkSelection::Union(members) => {
+                let overlaps: Vec<_> = members
+                    .iter()
+                    .map(|m| m.region_overlap(region_x, region_z))
+                    .collect();
+                if overlaps.contains(&Overlap::Full) {
+                    Overlap::Full
+                } else if overlaps.iter().all(|o| *o == Overlap::None) {
+                    Overlap::None
+                } else {
+                    Overlap::Partial
+                }
+            }
+            ChunkSelection::Intersection(members) => {
+                let overlaps: Vec<_> = members
+                    .iter()
+                    .map(|m| m.region_overlap(region_x, region_z))
+                    .collect();
+                if overlaps.contains(&Overlap::None) {
+                    Overlap::None
+                } else if overlaps.iter().all(|o| *o == Overlap::Full) {
+                    Overlap::Full
+                } else {
+                    Overlap::Partial
+                }
+            }
+            ChunkSelection::Not(inner) => match inner.region_overlap(region_x, region_z) {
+                Overlap::None => Overlap::Full,
+                Overlap::Partial => Overlap::Partial,
+                Overlap::Full => Overlap::None,
+            },
+        }
+    }
+
+    /// The union of the selections in a file, one per line. `#` starts a comment.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut members = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let selection = line
+                .parse()
+                .with_context(|| format!("{}:{}", path.display(), number + 1))?;
+            members.push(selection);
+        }
+        Ok(ChunkSelection::union(members))
+    }
+}
+
+impl fmt::Display for ChunkSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, members: &[ChunkSelection], op| {
+            write!(f, "(")?;
+            for (i, member) in members.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                write!(f, "{}", member)?;
+            }
+            write!(f, ")")
+        };
+
+        match self {
+            ChunkSelection::All => write!(f, "all"),
+            ChunkSelection::Union(members) if members.is_empty() => write!(f, "none"),
+            ChunkSelection::Box(area) => write!(
+                f,
+                "chunks({}, {}, {}, {})",
+                area.min_x, area.min_z, area.max_x, area.max_z
+            ),
+            ChunkSelection::Circle {
+                center_x,
+                center_z,
+                radius,
+            } => write!(f, "circle({}, {}, {})", center_x, center_z, radius),
+            ChunkSelection::Regions(regions) => {
+                let coords: Vec<_> = regions
+                    .iter()
+                    .map(|(x, z)| format!("{}, {}", x, z))
+                    .collect();
+                write!(f, "regions({})", coords.join(", "))
+            }
+            ChunkSelection::Union(members) => join(f, members, '|'),
+            ChunkSelection::Intersection(members) if members.is_empty() => write!(f, "all"),
+            ChunkSelection::Intersection(members) => join(f, members, '&'),
+            ChunkSelection::Not(inner) => write!(f, "!{}", inner),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid chunk selection at column {column}: {message}")]
+pub struct SelectionParseError {
+    /// 1-based character position in the input
+    pub column: usize,
+    pub message: String,
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, SelectionParseError> {
+        Err(SelectionParseError {
+            column: self.input[..self.pos].chars().count() + 1,
+            message: message.into(),
+        })
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SelectionParseError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            self.error(format!("expected '{}'", c))
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        self.skip_whitespace();
+        let rest = &self.input[self.pos..];
+        let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn union(&mut self) -> Result<ChunkSelection, SelectionParseError> {
+        let mut members = vec![self.intersection()?];
+        while self.eat('|') {
+            members.push(self.intersection()?);
+        }
+        Ok(ChunkSelection::union(members))
+    }
+
+    fn intersection(&mut self) -> Result<ChunkSelection, SelectionParseError> {
+        let mut members = vec![self.factor()?];
+        while self.eat('&') {
+            members.push(self.factor()?);
+        }
+        Ok(match members.len() {
+            1 => members.pop().unwrap(),
+            _ => ChunkSelection::Intersection(members),
+        })
+    }
+
+    fn factor(&mut self) -> Result<ChunkSelection, SelectionParseError> {
+        if self.eat('!') {
+            return Ok(ChunkSelection::Not(Box::new(self.factor()?)));
+        }
+        if self.eat('(') {
+            let inner = self.union()?;
+            self.expect(')')?;
+            return Ok(inner);
+        }
+
+        let start = self.pos;
+        let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        match name {
+            "all" => return Ok(ChunkSelection::All),
+            "none" => return Ok(ChunkSelection::default()),
+            "" => return self.error("expected a selection"),
+            _ => {}
+        }
+
+        let args = self.arguments()?;
+        let arity = |n: usize| -> Result<(), SelectionParseError> {
+            if args.len() == n {
+                Ok(())
+            } else {
+                Err(SelectionParseError {
+                    column: self.input[..start].chars().count() + 1,
+                    message: format!("{} takes {} arguments, found {}", name, n, args.len()),
+                })
+            }
+        };
+        match name {
+            "chunks" => {
+                arity(4)?;
+                Ok(ChunkSelection::Box(ChunkBox::new(
+                    args[0], args[1], args[2], args[3],
+                )))
+            }
+            "blocks" => {
+                arity(4)?;
+                Ok(ChunkSelection::Box(ChunkBox::from_blocks(
+                    args[0], args[1], args[2], args[3],
+                )))
+            }
+            "circle" => {
+                arity(3)?;
+                if args[2] < 0 {
+                    return self.error("circle radius cannot be negative");
+                }
+                Ok(ChunkSelection::Circle {
+                    center_x: args[0],
+                    center_z: args[1],
+                    radius: args[2] as u32,
+                })
+            }
+            "regions" => {
+                if args.is_empty() || args.len() % 2 != 0 {
+                    return self.error("regions takes pairs of region coordinates");
+                }
+                let pairs = args.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+                Ok(ChunkSelection::Regions(pairs))
+            }
+            _ => {
+                self.pos = start;
+                self.error(format!("unknown selection '{}'", name))
+            }
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<i32>, SelectionParseError> {
+        self.expect('(')?;
+        let mut args = Vec::new();
+        if self.eat(')') {
+            return Ok(args);
+        }
+        loop {
+            let number = self.take_while(|c| c == '-' || c == '+' || c.is_ascii_digit());
+            match number.parse() {
+                Ok(value) => args.push(value),
+                Err(_) => return self.error(format!("expected a number, found '{}'", number)),
+            }
+            if self.eat(')') {
+                return Ok(args);
+            }
+            if !self.eat(',') {
+                return self.error("expected ',' or ')'");
+            }
+        }
+    }
+}
+
+impl FromStr for ChunkSelection {
+    type Err = SelectionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { input: s, pos: 0 };
+        let selection = parser.union()?;
+        if parser.peek().is_some() {
+            return parser.error("unexpected trailing input");
+        }
+        Ok(selection)
+    }
+}
@@ -0,0 +1,404 @@
+use anyhow::{bail, Result};
+use fastnbt::{ByteArray, IntArray, LongArray, Value};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Formats `value` as indented SNBT. Compound keys are sorted so the same
+/// NBT always gives the same text, and every number carries its type
+/// suffix, so [`from_snbt`] reads back the exact same value.
+pub fn to_snbt(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, 0);
+    out
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("    ");
+    }
+}
+
+/// Writes a float printed with `Debug`, which gives the shortest text that
+/// reads back as the same value, spelling infinities the way Java does.
+fn write_float(out: &mut String, debug: String, suffix: char) {
+    out.push_str(match debug.as_str() {
+        "inf" => "Infinity",
+        "-inf" => "-Infinity",
+        other => other,
+    });
+    out.push(suffix);
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn is_bare_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+fn write_key(out: &mut String, key: &str) {
+    if !key.is_empty() && key.chars().all(is_bare_char) {
+        out.push_str(key);
+    } else {
+        write_string(out, key);
+    }
+}
+
+fn write_array<T: std::fmt::Display>(out: &mut String, prefix: char, values: &[T], suffix: &str) {
+    let _ = write!(out, "[{};", prefix);
+    for (i, value) in values.iter().enumerate() {
+        let _ = write!(
+            out,
+            "{}{}{}",
+            if i == 0 { " " } else { ", " },
+            value,
+            suffix
+        );
+    }
+    out.push(']');
+}
+
+fn write_value(out: &mut String, value: &Value, depth: usize) {
+    match value {
+        Value::Byte(v) => {
+            let _ = write!(out, "{}b", v);
+        }
+        Value::Short(v) => {
+            let _ = write!(out, "{}s", v);
+        }
+        Value::Int(v) => {
+            let _ = write!(out, "{}", v);
+        }
+        Value::Long(v) => {
+            let _ = write!(out, "{}L", v);
+        }
+        Value::Float(v) => write_float(out, format!("{:?}", v), 'f'),
+        Value::Double(v) => write_float(out, format!("{:?}", v), 'd'),
+        Value::String(s) => write_string(out, s),
+        Value::ByteArray(values) => write_array(out, 'B', values, "b"),
+        Value::IntArray(values) => write_array(out, 'I', values, ""),
+        Value::LongArray(values) => write_array(out, 'L', values, "L"),
+        Value::List(values) => {
+            // Lists of numbers and strings stay on one line
+            let nested = values
+                .iter()
+                .any(|v| matches!(v, Value::List(_) | Value::Compound(_)));
+            if values.is_empty() || !nested {
+                out.push('[');
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    write_value(out, v, depth);
+                }
+                out.push(']');
+                return;
+            }
+            out.push_str("[\n");
+            for (i, v) in values.iter().enumerate() {
+                indent(out, depth + 1);
+                write_value(out, v, depth + 1);
+                out.push_str(if i + 1 < values.len() { ",\n" } else { "\n" });
+            }
+            indent(out, depth);
+            out.push(']');
+        }
+        Value::Compound(compound) => {
+            if compound.is_empty() {
+                out.push_str("{}");
+                return;
+            }
+            let mut keys: Vec<&String> = compound.keys().collect();
+            keys.sort();
+            out.push_str("{\n");
+            for (i, key) in keys.iter().enumerate() {
+                indent(out, depth + 1);
+                write_key(out, key);
+                out.push_str(": ");
+                write_value(out, &compound[*key], depth + 1);
+                out.push_str(if i + 1 < keys.len() { ",\n" } else { "\n" });
+            }
+            indent(out, depth);
+            out.push('}');
+        }
+    }
+}
+
+/// Parses SNBT text into a value. Besides the output of [`to_snbt`] this
+/// reads what Minecraft's `/data` command prints: unquoted keys and
+/// strings, single-quoted strings, `true` and `false` as bytes, and numbers
+/// without a suffix as ints, or doubles when they have a decimal point.
+pub fn from_snbt(text: &str) -> Result<Value> {
+    let mut parser = Parser { text, pos: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        parser.fail("Unexpected text after the value")?;
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn fail<T>(&self, message: &str) -> Result<T> {
+        let line = self.text[..self.pos].matches('\n').count() + 1;
+        let column = self.text[..self.pos]
+            .rsplit('\n')
+            .next()
+            .map_or(0, |s| s.chars().count())
+            + 1;
+        bail!("{} at line {}, column {}", message, line, column)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek()
+            && c.is_whitespace()
+        {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    /// Skips whitespace and consumes `expected` if it comes next.
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        if !self.eat(expected) {
+            self.fail(&format!("Expected `{}`", expected))?;
+        }
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.compound(),
+            Some('[') => self.list_or_array(),
+            Some('"' | '\'') => Ok(Value::String(self.quoted()?)),
+            Some(_) => {
+                let start = self.pos;
+                let token = self.bare();
+                if token.is_empty() {
+                    return self.fail("Expected a value");
+                }
+                match parse_bare(token) {
+                    Ok(value) => Ok(value),
+                    Err(message) => {
+                        self.pos = start;
+                        self.fail(&message)
+                    }
+                }
+            }
+            None => self.fail("Unexpected end of text"),
+        }
+    }
+
+    fn bare(&mut self) -> &str {
+        let start = self.pos;
+        while let Some(c) = self.peek()
+            && is_bare_char(c)
+        {
+            self.pos += 1;
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let Some(quote) = self.peek() else {
+            return self.fail("Expected a string");
+        };
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return self.fail("Unterminated string");
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '\\' => {
+                    let Some(escaped) = self.peek() else {
+                        return self.fail("Unterminated string");
+                    };
+                    self.pos += escaped.len_utf8();
+                    s.push(match escaped {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        '\\' | '"' | '\'' => escaped,
+                        _ => return self.fail(&format!("Unknown escape `\\{}`", escaped)),
+                    });
+                }
+                c if c == quote => return Ok(s),
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"' | '\'') => self.quoted(),
+            _ => {
+                let key = self.bare();
+                if key.is_empty() {
+                    return self.fail("Expected a key");
+                }
+                Ok(key.to_string())
+            }
+        }
+    }
+
+    fn compound(&mut self) -> Result<Value> {
+        self.expect('{')?;
+        let mut compound = HashMap::new();
+        if self.eat('}') {
+            return Ok(Value::Compound(compound));
+        }
+        loop {
+            let key = self.key()?;
+            self.expect(':')?;
+            let value = self.value()?;
+            if compound.insert(key.clone(), value).is_some() {
+                return self.fail(&format!("Duplicate key `{}`", key));
+            }
+            if self.eat('}') {
+                return Ok(Value::Compound(compound));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn list_or_array(&mut self) -> Result<Value> {
+        self.expect('[')?;
+        let rest = &self.text[self.pos..];
+        let array_type = match rest.as_bytes() {
+            [t @ (b'B' | b'I' | b'L'), b';', ..] => Some(*t),
+            _ => None,
+        };
+        if let Some(array_type) = array_type {
+            self.pos += 2;
+            return self.array(array_type);
+        }
+
+        let mut values: Vec<Value> = Vec::new();
+        if self.eat(']') {
+            return Ok(Value::List(values));
+        }
+        loop {
+            let value = self.value()?;
+            if let Some(first) = values.first()
+                && std::mem::discriminant(first) != std::mem::discriminant(&value)
+            {
+                return self.fail("List elements must all have the same type");
+            }
+            values.push(value);
+            if self.eat(']') {
+                return Ok(Value::List(values));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn array(&mut self, array_type: u8) -> Result<Value> {
+        let mut values = Vec::new();
+        if !self.eat(']') {
+            loop {
+                match (array_type, self.value()?) {
+                    (b'B', Value::Byte(v)) => values.push(v as i64),
+                    (b'I', Value::Int(v)) => values.push(v as i64),
+                    (b'L', Value::Long(v)) => values.push(v),
+                    _ => return self.fail("Array element of the wrong type"),
+                }
+                if self.eat(']') {
+                    break;
+                }
+                self.expect(',')?;
+            }
+        }
+        Ok(match array_type {
+            b'B' => Value::ByteArray(ByteArray::new(values.iter().map(|&v| v as i8).collect())),
+            b'I' => Value::IntArray(IntArray::new(values.iter().map(|&v| v as i32).collect())),
+            _ => Value::LongArray(LongArray::new(values)),
+        })
+    }
+}
+
+/// Whether `s` is made of the characters of a decimal number.
+fn is_numeric(s: &str) -> bool {
+    s.bytes().any(|b| b.is_ascii_digit())
+        && s.bytes()
+            .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'+' | b'-'))
+}
+
+/// An unquoted token: a number with its type, a boolean or a string.
+fn parse_bare(token: &str) -> std::result::Result<Value, String> {
+    match token {
+        "true" => return Ok(Value::Byte(1)),
+        "false" => return Ok(Value::Byte(0)),
+        _ => {}
+    }
+
+    let (body, suffix) = match token.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&token[..i], Some(c.to_ascii_lowercase())),
+        _ => (token, None),
+    };
+    let special = matches!(body, "NaN" | "Infinity" | "-Infinity");
+    if !(is_numeric(body) || special && matches!(suffix, Some('f' | 'd'))) {
+        return Ok(Value::String(token.to_string()));
+    }
+    let string = Value::String(token.to_string());
+    // Integers that parse but do not fit their type are an error, not a string
+    let int_error = |body: &str| match body.parse::<i128>() {
+        Ok(_) => Err(format!("Number `{}` is out of range", token)),
+        Err(_) => Ok(string.clone()),
+    };
+    let float = |body: &str| match body {
+        "NaN" => Ok(f64::NAN),
+        "Infinity" => Ok(f64::INFINITY),
+        "-Infinity" => Ok(f64::NEG_INFINITY),
+        _ => body.parse::<f64>(),
+    };
+    let integer = !body.contains(['.', 'e', 'E']);
+
+    let value = match suffix {
+        Some('b') if integer => body.parse().map(Value::Byte).or_else(|_| int_error(body))?,
+        Some('s') if integer => body
+            .parse()
+            .map(Value::Short)
+            .or_else(|_| int_error(body))?,
+        Some('l') if integer => body.parse().map(Value::Long).or_else(|_| int_error(body))?,
+        None if integer => body.parse().map(Value::Int).or_else(|_| int_error(body))?,
+        Some('f') => float(body).map_or(string, |v| Value::Float(v as f32)),
+        Some('d') => float(body).map_or(string, Value::Double),
+        None if body.contains('.') => body.parse().map_or(string, Value::Double),
+        _ => string,
+    };
+    Ok(value)
+}
@@ -0,0 +1,59 @@
+use crate::{nbt, selection::ChunkSelection, Region, CHUNKS_PER_REGION};
+
+#[derive(Debug, Clone, Default)]
+pub struct TrimOptions {
+    /// Chunks inhabited for fewer ticks than this are removed
+    pub min_inhabited_time: i64,
+    /// Chunks in this selection are always kept
+    pub protected: ChunkSelection,
+}
+
+/// Outcome of [`trim_region`].
+#[derive(Debug, Clone, Default)]
+pub struct TrimReport {
+    /// Indices of the removed chunks
+    pub removed: Vec<usize>,
+    pub kept: usize,
+    /// Kept because they are in a protected area
+    pub protected: usize,
+    /// Kept because their NBT does not parse or has no `InhabitedTime`
+    pub unreadable: usize,
+    /// Uncompressed NBT bytes of the removed chunks
+    pub bytes_removed: u64,
+}
+
+/// Removes the chunks of `region` whose `InhabitedTime` is below
+/// `options.min_inhabited_time`, except those in protected areas. Chunks
+/// without a readable `InhabitedTime` are kept.
+pub fn trim_region(region: &mut Region, options: &TrimOptions) -> TrimReport {
+    let mut report = TrimReport::default();
+
+    for index in 0..CHUNKS_PER_REGION {
+        let Some(chunk) = region.get_chunk(index) else {
+            continue;
+        };
+        if options.protected.contains(chunk.x, chunk.z) {
+            report.kept += 1;
+            report.protected += 1;
+            continue;
+        }
+        let inhabited = chunk
+            .parse_nbt()
+            .ok()
+            .and_then(|nbt| nbt::inhabited_time(&nbt));
+        match inhabited {
+            Some(ticks) if ticks < options.min_inhabited_time => {
+                report.bytes_removed += chunk.size() as u64;
+                report.removed.push(index);
+                region.remove_chunk(index);
+            }
+            Some(_) => report.kept += 1,
+            None => {
+                report.kept += 1;
+                report.unreadable += 1;
+            }
+        }
+    }
+
+    report
+}
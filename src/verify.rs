@@ -0,0 +1,102 @@
+use crate::{Region, CHUNKS_PER_REGION, REGION_DIMENSION};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MismatchKind {
+    /// Present in the source but not in the written file.
+    Missing,
+    /// Present in the written file but not in the source.
+    Unexpected,
+    PayloadDiffers {
+        source_size: usize,
+        written_size: usize,
+    },
+    TimestampDiffers {
+        source: u32,
+        written: u32,
+    },
+    /// The written chunk does not parse as NBT.
+    InvalidNbt {
+        reason: String,
+    },
+}
+
+impl fmt::Display for MismatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MismatchKind::Missing => write!(f, "missing from the written file"),
+            MismatchKind::Unexpected => write!(f, "not present in the source"),
+            MismatchKind::PayloadDiffers {
+                source_size,
+                written_size,
+            } => write!(
+                f,
+                "payload differs ({} bytes in source, {} bytes written)",
+                source_size, written_size
+            ),
+            MismatchKind::TimestampDiffers { source, written } => {
+                write!(f, "timestamp {} was written as {}", source, written)
+            }
+            MismatchKind::InvalidNbt { reason } => write!(f, "NBT does not parse: {}", reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChunkMismatch {
+    pub index: usize,
+    pub x: i32,
+    pub z: i32,
+    pub kind: MismatchKind,
+}
+
+/// Compares a region with the result of reading it back after a write: the
+/// chunk set, every payload byte and the timestamps of present chunks. With
+/// `check_nbt`, every written chunk must also parse as NBT.
+pub fn compare_regions(source: &Region, written: &Region, check_nbt: bool) -> Vec<ChunkMismatch> {
+    let mut mismatches = Vec::new();
+
+    for index in 0..CHUNKS_PER_REGION {
+        let (source_chunk, written_chunk) = (source.get_chunk(index), written.get_chunk(index));
+        if source_chunk.is_none() && written_chunk.is_none() {
+            continue;
+        }
+        let x = source.region_x * REGION_DIMENSION as i32 + (index % REGION_DIMENSION) as i32;
+        let z = source.region_z * REGION_DIMENSION as i32 + (index / REGION_DIMENSION) as i32;
+        let mut push = |kind| mismatches.push(ChunkMismatch { index, x, z, kind });
+
+        let (source_chunk, written_chunk) = match (source_chunk, written_chunk) {
+            (Some(s), Some(w)) => (s, w),
+            (Some(_), None) => {
+                push(MismatchKind::Missing);
+                continue;
+            }
+            _ => {
+                push(MismatchKind::Unexpected);
+                continue;
+            }
+        };
+
+        if source_chunk.as_slice() != written_chunk.as_slice() {
+            push(MismatchKind::PayloadDiffers {
+                source_size: source_chunk.size(),
+                written_size: written_chunk.size(),
+            });
+        }
+
+        if source.timestamps[index] != written.timestamps[index] {
+            push(MismatchKind::TimestampDiffers {
+                source: source.timestamps[index],
+                written: written.timestamps[index],
+            });
+        }
+
+        if check_nbt && let Err(e) = written_chunk.parse_nbt() {
+            push(MismatchKind::InvalidNbt {
+                reason: format!("{:#}", e),
+            });
+        }
+    }
+
+    mismatches
+}
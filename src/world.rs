@@ -0,0 +1,127 @@
+use crate::Region;
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The three kinds of region folders a dimension can contain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegionKind {
+    Region,
+    Entities,
+    Poi,
+}
+
+impl RegionKind {
+    pub const ALL: [RegionKind; 3] = [RegionKind::Region, RegionKind::Entities, RegionKind::Poi];
+
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            RegionKind::Region => "region",
+            RegionKind::Entities => "entities",
+            RegionKind::Poi => "poi",
+        }
+    }
+}
+
+/// A region folder found inside a world.
+#[derive(Debug, Clone)]
+pub struct RegionDirectory {
+    /// Dimension id, e.g. `minecraft:overworld` or `mymod:mining`
+    pub dimension: String,
+    pub kind: RegionKind,
+    /// Path relative to the world root, e.g. `DIM-1/region`
+    pub relative_path: PathBuf,
+}
+
+fn push_dimension(
+    world: &Path,
+    dimension: &str,
+    dimension_path: &Path,
+    directories: &mut Vec<RegionDirectory>,
+) {
+    for kind in RegionKind::ALL {
+        let relative_path = dimension_path.join(kind.dir_name());
+        if world.join(&relative_path).is_dir() {
+            directories.push(RegionDirectory {
+                dimension: dimension.to_string(),
+                kind,
+                relative_path,
+            });
+        }
+    }
+}
+
+/// Finds the `region/`, `entities/` and `poi/` folders of every dimension in
+/// `world`: the overworld, `DIM-1`, `DIM1` and `dimensions/<namespace>/<name>/`.
+pub fn find_region_directories<P: AsRef<Path>>(world: P) -> Result<Vec<RegionDirectory>> {
+    let world = world.as_ref();
+    let mut directories = Vec::new();
+
+    push_dimension(
+        world,
+        "minecraft:overworld",
+        Path::new(""),
+        &mut directories,
+    );
+    push_dimension(
+        world,
+        "minecraft:the_nether",
+        Path::new("DIM-1"),
+        &mut directories,
+    );
+    push_dimension(
+        world,
+        "minecraft:the_end",
+        Path::new("DIM1"),
+        &mut directories,
+    );
+
+    let dimensions_root = world.join("dimensions");
+    if dimensions_root.is_dir() {
+        let mut namespaces: Vec<_> = fs::read_dir(&dimensions_root)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .collect();
+        namespaces.sort_by_key(|e| e.file_name());
+
+        for namespace in namespaces {
+            let mut names: Vec<_> = fs::read_dir(namespace.path())?
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .collect();
+            names.sort_by_key(|e| e.file_name());
+
+            for name in names {
+                let dimension = format!(
+                    "{}:{}",
+                    namespace.file_name().to_string_lossy(),
+                    name.file_name().to_string_lossy()
+                );
+                let dimension_path = Path::new("dimensions")
+                    .join(namespace.file_name())
+                    .join(name.file_name());
+                push_dimension(world, &dimension, &dimension_path, &mut directories);
+            }
+        }
+    }
+
+    Ok(directories)
+}
+
+/// Lists the `r.<x>.<z>.<extension>` files in `dir`, sorted by name.
+pub fn list_region_files<P: AsRef<Path>>(dir: P, extension: &str) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| {
+            path.is_file()
+                && path.extension().is_some_and(|ext| ext == extension)
+                && path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("r.") && Region::parse_filename(n).is_ok())
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
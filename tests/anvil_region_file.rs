@@ -0,0 +1,78 @@
+use linear_region_tools::{
+    anvil::{
+        read_anvil_region, write_anvil_region, AnvilRegionFile, AnvilWriteOptions, ChunkCompression,
+    },
+    Chunk, Region,
+};
+
+#[test]
+fn in_place_updates_preserve_untouched_chunks() {
+    let dir = std::env::temp_dir().join("lrt_anvil_region_file_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("r.0.0.mca");
+
+    let mut region = Region::new(0, 0);
+    for i in [0usize, 1, 2] {
+        region.set_chunk(i, Chunk::new(vec![i as u8; 100], i as i32, 0), 5);
+    }
+    write_anvil_region(&path, &region, 6, None).unwrap();
+
+    let mut file = AnvilRegionFile::open(&path).unwrap();
+    file.set_write_options(AnvilWriteOptions::new(ChunkCompression::None, 0));
+
+    // Grow chunk 1 past one sector, shrink it again, then delete chunk 2
+    file.write_chunk(1, &[7u8; 10_000], 42).unwrap();
+    assert_eq!(file.read_chunk(1).unwrap().unwrap().size(), 10_000);
+    file.write_chunk(1, b"tiny", 43).unwrap();
+    file.delete_chunk(2).unwrap();
+    file.write_chunk(500, b"new chunk", 44).unwrap();
+    file.flush().unwrap();
+    drop(file);
+
+    let read = read_anvil_region(&path, None).unwrap();
+    assert_eq!(read.chunk_count(), 3);
+    assert_eq!(read.get_chunk(0).unwrap().as_slice(), &[0u8; 100][..]);
+    assert_eq!(read.get_chunk(1).unwrap().as_slice(), b"tiny");
+    assert!(read.get_chunk(2).is_none());
+    assert_eq!(read.get_chunk(500).unwrap().as_slice(), b"new chunk");
+    assert_eq!(read.timestamps[1], 43);
+    assert_eq!(read.timestamps[2], 0);
+    assert_eq!(std::fs::metadata(&path).unwrap().len() % 4096, 0);
+
+    // Zero-byte files are initialised with an empty header
+    let empty_path = dir.join("r.1.1.mca");
+    std::fs::File::create(&empty_path).unwrap();
+    let mut empty = AnvilRegionFile::open(&empty_path).unwrap();
+    assert!(empty.read_chunk(0).unwrap().is_none());
+    empty.write_chunk(0, b"hello", 1).unwrap();
+    empty.flush().unwrap();
+    assert_eq!(std::fs::metadata(&empty_path).unwrap().len(), 3 * 4096);
+
+    // Indices past the last slot are rejected instead of panicking
+    assert!(!empty.has_chunk(1024));
+    assert_eq!(empty.timestamp(0), Some(1));
+    assert_eq!(empty.timestamp(1024), None);
+    assert!(empty.read_chunk(1024).unwrap().is_none());
+    assert!(empty.write_chunk(1024, b"hello", 1).is_err());
+    assert!(empty.delete_chunk(1024).is_err());
+
+    // Entries past EOF or overlapping an earlier chunk are cleared on disk when opened
+    let stale_path = dir.join("r.2.2.mca");
+    write_anvil_region(&stale_path, &region, 6, None).unwrap();
+    let mut bytes = std::fs::read(&stale_path).unwrap();
+    let first_entry = bytes[0..4].to_vec();
+    bytes[4..8].copy_from_slice(&first_entry);
+    bytes[8..12].copy_from_slice(&[0, 0, 100, 1]);
+    std::fs::write(&stale_path, &bytes).unwrap();
+    let stale = AnvilRegionFile::open(&stale_path).unwrap();
+    assert!(stale.has_chunk(0));
+    assert!(!stale.has_chunk(1));
+    assert!(!stale.has_chunk(2));
+    drop(stale);
+    let bytes = std::fs::read(&stale_path).unwrap();
+    assert_eq!(&bytes[0..4], &first_entry[..]);
+    assert_eq!(&bytes[4..12], &[0u8; 8]);
+    assert_eq!(&bytes[4096 + 4..4096 + 12], &[0u8; 8]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
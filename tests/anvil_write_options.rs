@@ -0,0 +1,53 @@
+use linear_region_tools::{
+    anvil::{
+        read_anvil_region, write_anvil_region_with_options, AnvilWriteOptions, ChunkCompression,
+    },
+    Chunk, Region,
+};
+
+#[test]
+fn every_chunk_compression_roundtrips() {
+    let dir = std::env::temp_dir().join("lrt_anvil_write_options_test");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut region = Region::new(0, 0);
+    // Spans several 64 KiB LZ4 blocks, mixing compressible and incompressible data
+    let large: Vec<u8> = (0..200_000u32)
+        .map(|i| {
+            if i < 100_000 {
+                (i % 7) as u8
+            } else {
+                (i.wrapping_mul(2654435761) >> 13) as u8
+            }
+        })
+        .collect();
+    region.set_chunk(0, Chunk::new(large, 0, 0), 10);
+    region.set_chunk(33, Chunk::new(b"small chunk".to_vec(), 1, 1), 20);
+
+    for compression in [
+        ChunkCompression::Zlib,
+        ChunkCompression::Gzip,
+        ChunkCompression::None,
+        ChunkCompression::Lz4,
+    ] {
+        let path = dir.join("r.0.0.mca");
+        let options = AnvilWriteOptions::new(compression, 6);
+        write_anvil_region_with_options(&path, &region, &options, None).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes[2 * 4096 + 4], compression.as_u8());
+
+        let read = read_anvil_region(&path, None).unwrap();
+        assert_eq!(read.chunk_count(), 2);
+        for i in [0, 33] {
+            assert_eq!(
+                read.get_chunk(i).unwrap().as_slice(),
+                region.get_chunk(i).unwrap().as_slice(),
+                "{compression:?} chunk {i}"
+            );
+        }
+        assert_eq!(read.timestamps[33], 20);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use common::palette;
+use fastnbt::{LongArray, Value};
+use linear_region_tools::{
+    nbt::{pack_longs, parse_nbt, ModernChunk, PackingLayout},
+    Chunk,
+};
+
+fn long_array(data: Vec<i64>) -> Value {
+    Value::LongArray(LongArray::new(data))
+}
+
+fn modern_chunk() -> Value {
+    // Block 1 (x 1) of the packed section is a hopper facing down
+    let mut blocks = vec![0i64; 256];
+    blocks[0] = 1 << 4;
+    // Biome cell 1 is a river, 1 bit per entry
+    let mut biomes = vec![0i64; 1];
+    biomes[0] = 1 << 1;
+    // Every column is 64 blocks high above yPos
+    let heights = vec![(0..7).fold(0i64, |long, i| long | (64 << (i * 9))); 37];
+
+    fastnbt::nbt!({
+        "DataVersion": 3955,
+        "xPos": 3,
+        "yPos": -4,
+        "zPos": -2,
+        "Status": "minecraft:full",
+        "isLightOn": 1i8,
+        "sections": [
+            {
+                "Y": -4i8,
+                "block_states": { "palette": palette(&["minecraft:deepslate"]) },
+                "biomes": { "palette": ["minecraft:plains"] },
+            },
+            {
+                "Y": 0i8,
+                "block_states": {
+                    "palette": [
+                        { "Name": "minecraft:air" },
+                        { "Name": "minecraft:hopper", "Properties": { "facing": "down" } },
+                    ],
+                    "data": long_array(blocks),
+                },
+                "biomes": {
+                    "palette": ["minecraft:plains", "minecraft:river"],
+                    "data": long_array(biomes),
+                },
+                "SkyLight": [B; 0, 1, 2],
+            },
+        ],
+        "block_entities": [{ "id": "minecraft:hopper", "x": 49, "y": 0, "z": -32 }],
+        "Heightmaps": { "MOTION_BLOCKING": long_array(heights) },
+        "structures": {
+            "starts": { "minecraft:village_plains": { "id": "INVALID" } },
+            "References": { "minecraft:village_plains": long_array(vec![7]) },
+        },
+        "PostProcessing": [[], [1i16]],
+    })
+}
+
+#[test]
+fn reads_modern_chunks_and_writes_them_back_unchanged() {
+    let nbt = modern_chunk();
+    let chunk = ModernChunk::from_chunk(&Chunk::from_nbt(&nbt, 3, -2).unwrap()).unwrap();
+
+    assert_eq!(chunk.data_version, 3955);
+    assert_eq!((chunk.x_pos, chunk.y_pos, chunk.z_pos), (3, -4, -2));
+    assert_eq!(chunk.status, "minecraft:full");
+    assert_eq!(chunk.sections.as_ref().unwrap().len(), 2);
+    assert_eq!(chunk.block_entities.as_ref().unwrap().len(), 1);
+    assert_eq!(
+        &chunk.structures.as_ref().unwrap().references["minecraft:village_plains"][..],
+        &[7]
+    );
+    assert!(chunk.other.contains_key("PostProcessing"));
+
+    let block = |x, y, z| chunk.block_state(x, y, z).unwrap().map(|s| s.name.as_str());
+    assert_eq!(block(0, -64, 0), Some("minecraft:deepslate"));
+    assert_eq!(block(15, -49, 15), Some("minecraft:deepslate"));
+    assert_eq!(block(0, 0, 0), Some("minecraft:air"));
+    assert_eq!(block(0, -20, 0), None);
+    let hopper = chunk.block_state(1, 0, 0).unwrap().unwrap();
+    assert_eq!(hopper.name, "minecraft:hopper");
+    assert_eq!(hopper.properties["facing"], "down");
+
+    let section = chunk.section(0).unwrap().get().unwrap();
+    assert_eq!(section.y, 0);
+    assert_eq!(section.biome(0, 0, 0).unwrap(), Some("minecraft:plains"));
+    assert_eq!(section.biome(1, 0, 0).unwrap(), Some("minecraft:river"));
+    assert_eq!(section.sky_light.as_ref().unwrap().len(), 3);
+    assert!(section.block_light.is_none());
+
+    let heights = chunk.heightmap("MOTION_BLOCKING").unwrap().unwrap();
+    assert_eq!(heights.len(), 256);
+    assert!(heights.iter().all(|&h| h == 0));
+    assert!(chunk.heightmap("WORLD_SURFACE").unwrap().is_none());
+
+    let written = chunk.to_chunk().unwrap();
+    assert_eq!((written.x, written.z), (3, -2));
+    assert_eq!(parse_nbt(written.as_slice()).unwrap(), nbt);
+}
+
+#[test]
+fn rejects_old_chunks_and_bad_sections_only_when_read() {
+    let legacy = fastnbt::nbt!({
+        "DataVersion": 2586,
+        "Level": { "xPos": 0, "zPos": 0, "Status": "full" },
+    });
+    let bytes = fastnbt::to_bytes(&legacy).unwrap();
+    assert!(ModernChunk::from_bytes(&bytes).is_err());
+
+    let broken = fastnbt::nbt!({
+        "DataVersion": 3955,
+        "xPos": 0,
+        "yPos": -4,
+        "zPos": 0,
+        "Status": "minecraft:full",
+        "sections": [
+            {
+                "Y": 0i8,
+                "block_states": {
+                    "palette": palette(&["minecraft:air", "minecraft:stone"]),
+                    "data": long_array(vec![0; 10]),
+                },
+            },
+            { "Y": "not a number" },
+        ],
+    });
+    let chunk = ModernChunk::from_bytes(&fastnbt::to_bytes(&broken).unwrap()).unwrap();
+    assert!(chunk.block_state(0, 0, 0).is_err());
+    assert!(chunk.sections.as_ref().unwrap()[1].get().is_err());
+    assert!(chunk.heightmap("MOTION_BLOCKING").unwrap().is_none());
+}
+
+#[test]
+fn heightmap_bit_width_follows_the_world_height() {
+    // A 640 block high world needs 10 bits per height, six to a long
+    let packed = pack_longs(&[600; 256], 10, PackingLayout::Aligned).unwrap();
+    assert_eq!(packed.len(), 43);
+    let nbt = fastnbt::nbt!({
+        "DataVersion": 3955,
+        "xPos": 0,
+        "yPos": -4,
+        "zPos": 0,
+        "Status": "minecraft:full",
+        "Heightmaps": {
+            "WORLD_SURFACE": long_array(packed),
+            "MOTION_BLOCKING": long_array(vec![0; 40]),
+        },
+    });
+    let chunk = ModernChunk::from_chunk(&Chunk::from_nbt(&nbt, 0, 0).unwrap()).unwrap();
+    let heights = chunk.heightmap("WORLD_SURFACE").unwrap().unwrap();
+    assert!(heights.iter().all(|&h| h == 536));
+    assert!(chunk.heightmap("MOTION_BLOCKING").is_err());
+}
+
+#[test]
+fn proto_chunks_without_sections_write_back_unchanged() {
+    let nbt = fastnbt::nbt!({
+        "DataVersion": 3955,
+        "xPos": -1,
+        "yPos": -4,
+        "zPos": 7,
+        "Status": "minecraft:structure_starts",
+        "structures": { "References": {} },
+    });
+    let chunk = ModernChunk::from_chunk(&Chunk::from_nbt(&nbt, -1, 7).unwrap()).unwrap();
+    assert!(chunk.sections.is_none());
+    assert!(chunk.block_entities.is_none());
+    assert!(chunk.heightmaps.is_none());
+    assert!(chunk.structures.as_ref().unwrap().starts.is_empty());
+    assert!(chunk.block_state(0, 0, 0).unwrap().is_none());
+    assert!(chunk.heightmap("WORLD_SURFACE").unwrap().is_none());
+
+    let written = chunk.to_chunk().unwrap();
+    assert_eq!(parse_nbt(written.as_slice()).unwrap(), nbt);
+
+    // Tags that are present but empty are kept
+    let empty = fastnbt::nbt!({
+        "DataVersion": 3955,
+        "xPos": 0,
+        "yPos": -4,
+        "zPos": 0,
+        "Status": "minecraft:full",
+        "sections": [],
+        "block_entities": [],
+        "Heightmaps": {},
+    });
+    let chunk = ModernChunk::from_chunk(&Chunk::from_nbt(&empty, 0, 0).unwrap()).unwrap();
+    assert_eq!(
+        parse_nbt(chunk.to_chunk().unwrap().as_slice()).unwrap(),
+        empty
+    );
+}
@@ -0,0 +1,11 @@
+use fastnbt::Value;
+
+/// A block state palette of the given block names, without properties.
+pub fn palette(names: &[&str]) -> Value {
+    Value::List(
+        names
+            .iter()
+            .map(|name| fastnbt::nbt!({ "Name": *name }))
+            .collect(),
+    )
+}
@@ -0,0 +1,79 @@
+use linear_region_tools::{
+    anvil::write_anvil_region,
+    format::{detect_region_format, DetectedFormat, FormatRegistry},
+    linear::{write_linear_region, LinearVersion},
+    Chunk, Region,
+};
+
+#[test]
+fn detects_formats_by_content() {
+    let dir = std::env::temp_dir().join("lrt_detect_format_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut region = Region::new(3, -1);
+    let nbt = fastnbt::nbt!({ "xPos": 96, "zPos": -32 });
+    region.set_chunk(0, Chunk::from_nbt(&nbt, 96, -32).unwrap(), 1_700_000_000);
+
+    // Deliberately mislabelled: the extension must not matter
+    let anvil = dir.join("r.3.-1.linear");
+    write_anvil_region(&anvil, &region, 6, None).unwrap();
+    assert_eq!(
+        detect_region_format(&anvil).unwrap(),
+        DetectedFormat::Anvil {
+            chunk_count: 1,
+            out_of_bounds_chunks: 0
+        }
+    );
+
+    let v1 = dir.join("r.3.-1.mca");
+    write_linear_region(&v1, &region, 6, LinearVersion::V1, None).unwrap();
+    let detected = detect_region_format(&v1).unwrap();
+    assert!(matches!(
+        detected,
+        DetectedFormat::LinearV1 {
+            newest_timestamp: 1_700_000_000,
+            chunk_count: 1,
+            ..
+        }
+    ));
+    assert_eq!(detected.version(), Some(1));
+
+    let v2 = dir.join("v2.bin");
+    write_linear_region(&v2, &region, 6, LinearVersion::V2, None).unwrap();
+    assert!(matches!(
+        detect_region_format(&v2).unwrap(),
+        DetectedFormat::LinearV2 {
+            grid_size: 8,
+            region_x: 3,
+            region_z: -1,
+            ..
+        }
+    ));
+
+    let junk = dir.join("junk.mca");
+    std::fs::write(&junk, vec![0xab; 8192]).unwrap();
+    assert_eq!(
+        detect_region_format(&junk).unwrap(),
+        DetectedFormat::Unknown
+    );
+
+    // Empty and truncated files have no header to sniff and go by their extension
+    let registry = FormatRegistry::default();
+    let empty = dir.join("r.0.0.mca");
+    std::fs::File::create(&empty).unwrap();
+    assert_eq!(
+        detect_region_format(&empty).unwrap(),
+        DetectedFormat::Unknown
+    );
+    assert_eq!(registry.detect_file(&empty).unwrap().unwrap().name(), "mca");
+    let truncated = dir.join("r.0.1.mca");
+    std::fs::write(&truncated, vec![0u8; 100]).unwrap();
+    assert_eq!(
+        registry.detect_file(&truncated).unwrap().unwrap().name(),
+        "mca"
+    );
+    assert!(registry.detect_file(&junk).unwrap().is_none());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,72 @@
+use linear_region_tools::{
+    anvil::{read_anvil_region_from_bytes, region_to_anvil_bytes},
+    diff::{diff_nbt, diff_regions, ChunkDiffKind},
+    linear::{read_linear_region_from_bytes, write_linear_region_to_writer, LinearVersion},
+    Chunk, Region,
+};
+
+fn chunk(x: i32, entities: i32, palette: &str) -> Chunk {
+    let nbt = fastnbt::nbt!({
+        "xPos": x,
+        "Entities": [{ "id": "minecraft:pig", "Count": entities }],
+        "sections": [
+            { "Y": 0i8, "block_states": { "palette": [{ "Name": "minecraft:stone" }] } },
+            { "Y": 1i8, "block_states": { "palette": [{ "Name": palette }] } },
+        ],
+    });
+    Chunk::from_nbt(&nbt, x, 0).unwrap()
+}
+
+#[test]
+fn diffs_anvil_against_linear_by_chunk_and_tag() {
+    let unchanged = chunk(0, 1, "minecraft:air");
+    let touched = chunk(2, 1, "minecraft:air");
+    let mut old = Region::new(0, 0);
+    old.set_chunk(0, unchanged.clone(), 100);
+    old.set_chunk(1, chunk(1, 1, "minecraft:air"), 100);
+    old.set_chunk(2, touched.clone(), 100);
+    old.set_chunk(3, chunk(3, 1, "minecraft:air"), 100);
+
+    let mut new = Region::new(0, 0);
+    new.set_chunk(0, unchanged, 100);
+    new.set_chunk(1, chunk(1, 2, "minecraft:tnt"), 160);
+    new.set_chunk(2, touched, 130);
+    new.set_chunk(4, chunk(4, 1, "minecraft:air"), 100);
+
+    let old = read_anvil_region_from_bytes(&region_to_anvil_bytes(&old, 6).unwrap(), 0, 0).unwrap();
+    let mut data = Vec::new();
+    write_linear_region_to_writer(&mut data, &new, 3, LinearVersion::V2, 8).unwrap();
+    let new = read_linear_region_from_bytes(&data, 0, 0).unwrap();
+
+    let diffs = diff_regions(&old, &new, Some(3));
+    let indices: Vec<_> = diffs.iter().map(|d| d.index).collect();
+    assert_eq!(indices, [1, 2, 3, 4]);
+    assert_eq!(
+        diffs[0].kind,
+        ChunkDiffKind::Changed {
+            changed_tags: Some(vec![
+                "Entities[0].Count".to_string(),
+                "sections[1].block_states.palette".to_string(),
+            ])
+        }
+    );
+    assert_eq!(diffs[0].timestamp_delta(), 60);
+    assert_eq!(diffs[1].kind, ChunkDiffKind::Touched);
+    assert_eq!(diffs[1].timestamp_delta(), 30);
+    assert_eq!(diffs[2].kind, ChunkDiffKind::Removed);
+    assert_eq!(diffs[3].kind, ChunkDiffKind::Added);
+
+    let diffs = diff_regions(&old, &new, None);
+    assert_eq!(diffs[0].kind, ChunkDiffKind::Changed { changed_tags: None });
+    assert_eq!(diffs[0].to_string(), "chunk (1, 0) changed, timestamp +60s");
+
+    let old_nbt = chunk(0, 1, "minecraft:air").parse_nbt().unwrap();
+    let new_nbt = chunk(0, 2, "minecraft:tnt").parse_nbt().unwrap();
+    assert_eq!(diff_nbt(&old_nbt, &new_nbt, 1), ["Entities", "sections"]);
+
+    // Coordinates come from the slot, not from the chunk stored in it
+    let mut misplaced = Region::new(1, -1);
+    misplaced.set_chunk(33, chunk(0, 1, "minecraft:air"), 100);
+    let diffs = diff_regions(&Region::new(1, -1), &misplaced, None);
+    assert_eq!((diffs[0].x, diffs[0].z), (33, -31));
+}
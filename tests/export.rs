@@ -0,0 +1,68 @@
+use linear_region_tools::{
+    anvil::write_anvil_region,
+    export::{export_region_file, plan_export, prepare_output},
+    format::{FormatRegistry, WriteOptions},
+    linear::read_linear_region,
+    selection::ChunkSelection,
+    world::RegionKind,
+    Chunk, Region,
+};
+
+fn write_region(path: &std::path::Path, region_x: i32, chunks: &[(i32, i32)]) {
+    let mut region = Region::new(region_x, 0);
+    for &(x, z) in chunks {
+        let nbt = fastnbt::nbt!({ "xPos": x, "zPos": z });
+        region
+            .insert_chunk(Chunk::from_nbt(&nbt, x, z).unwrap(), 100)
+            .unwrap();
+    }
+    write_anvil_region(path, &region, 6, None).unwrap();
+}
+
+#[test]
+fn exports_a_selection_of_every_region_folder_into_another_format() {
+    let dir = std::env::temp_dir().join("lrt_export_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    let (world, output) = (dir.join("world"), dir.join("export"));
+    for folder in ["region", "entities", "poi"] {
+        std::fs::create_dir_all(world.join(folder)).unwrap();
+        write_region(
+            &world.join(folder).join("r.0.0.mca"),
+            0,
+            &[(0, 0), (1, 1), (20, 20)],
+        );
+    }
+    write_region(&world.join("region/r.1.0.mca"), 1, &[(32, 0)]);
+    std::fs::write(world.join("level.dat"), b"level").unwrap();
+
+    let registry = FormatRegistry::default();
+    let selection: ChunkSelection = "chunks(0, 0, 1, 1)".parse().unwrap();
+    let jobs = plan_export(&world, &output, &registry, &selection, &[]).unwrap();
+    let kinds: Vec<_> = jobs.iter().map(|job| job.kind).collect();
+    assert_eq!(
+        kinds,
+        [RegionKind::Region, RegionKind::Entities, RegionKind::Poi]
+    );
+
+    prepare_output(&world, &output, &jobs).unwrap();
+    let linear = registry.get("linearv2");
+    for job in &jobs {
+        let exported =
+            export_region_file(job, &registry, linear, &selection, &WriteOptions::default())
+                .unwrap();
+        assert_eq!(exported, 2);
+    }
+
+    assert_eq!(std::fs::read(output.join("level.dat")).unwrap(), b"level");
+    for folder in ["region", "entities", "poi"] {
+        assert!(!output.join(folder).join("r.0.0.mca").exists());
+        let region = read_linear_region(output.join(folder).join("r.0.0.linear"), None).unwrap();
+        assert_eq!(region.chunk_count(), 2);
+        assert!(region.get_chunk_at(0, 0).is_some());
+        assert!(region.get_chunk_at(1, 1).is_some());
+        assert!(region.get_chunk_at(20, 20).is_none());
+    }
+    assert!(!output.join("region/r.1.0.linear").exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,110 @@
+use linear_region_tools::{
+    anvil::{
+        read_anvil_chunk_storage, read_anvil_region, write_anvil_region_with_options,
+        AnvilWriteOptions, ChunkCompression,
+    },
+    format::{convert_region_file, rewrite_region_file, FormatRegistry, WriteOptions},
+    Chunk, Region,
+};
+
+#[test]
+fn registry_converts_between_any_formats() {
+    let dir = std::env::temp_dir().join("lrt_format_registry_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let registry = FormatRegistry::default();
+    for (mode, from, to) in [
+        ("mca2linearv1", "mca", "linearv1"),
+        ("linearv2mca", "linearv2", "mca"),
+        ("linearv12linearv2", "linearv1", "linearv2"),
+    ] {
+        let (f, t) = registry.parse_conversion(mode).unwrap();
+        assert_eq!((f.name(), t.name()), (from, to));
+    }
+    assert!(registry.parse_conversion("mca2zip").is_none());
+
+    let mut region = Region::new(1, -2);
+    let nbt = fastnbt::nbt!({ "xPos": 32, "zPos": -64, "DataVersion": 3465 });
+    region.set_chunk(0, Chunk::from_nbt(&nbt, 32, -64).unwrap(), 1_700_000_000);
+
+    let options = WriteOptions::default();
+    let mca = dir.join("r.1.-2.mca");
+    registry
+        .get("mca")
+        .unwrap()
+        .write(&mca, &region, &options, None)
+        .unwrap();
+
+    let v1 = dir.join("r.1.-2.v1.linear");
+    let v2 = dir.join("r.1.-2.v2.linear");
+    let (mca_format, v1_format, v2_format) = (
+        registry.get("mca").unwrap(),
+        registry.get("linearv1").unwrap(),
+        registry.get("linearv2").unwrap(),
+    );
+    convert_region_file(mca_format, v1_format, &mca, &v1, &options).unwrap();
+    convert_region_file(v1_format, v2_format, &v1, &v2, &options).unwrap();
+
+    let data = std::fs::read(&v2).unwrap();
+    assert_eq!(registry.detect(&data).unwrap().name(), "linearv2");
+    let back = v2_format.read(&v2, None).unwrap();
+    assert_eq!(
+        back.get_chunk(0).unwrap().as_slice(),
+        region.get_chunk(0).unwrap().as_slice()
+    );
+    assert_eq!(back.timestamps[0], 1_700_000_000);
+
+    // The same region in two formats is ambiguous
+    let files = dir.join("files");
+    std::fs::create_dir_all(&files).unwrap();
+    std::fs::copy(&mca, files.join("r.1.-2.mca")).unwrap();
+    std::fs::write(files.join("notes.txt"), b"").unwrap();
+    assert_eq!(registry.region_files(&files).unwrap().len(), 1);
+    std::fs::copy(&v2, files.join("r.1.-2.linear")).unwrap();
+    assert!(registry.region_files(&files).is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn rewriting_anvil_keeps_each_chunks_compression() {
+    let dir = std::env::temp_dir().join("lrt_format_rewrite_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("r.0.0.mca");
+
+    let mut region = Region::new(0, 0);
+    for index in 0..3 {
+        region.set_chunk(
+            index,
+            Chunk::new(vec![index as u8; 64], index as i32, 0),
+            10,
+        );
+    }
+    let gzip = AnvilWriteOptions::new(ChunkCompression::Gzip, 6);
+    write_anvil_region_with_options(&path, &region, &gzip, None).unwrap();
+    std::fs::write(dir.join("c.1.0.mcc"), b"stale").unwrap();
+
+    region.remove_chunk(1);
+    region.set_chunk(2, Chunk::new(vec![9; 64], 2, 0), 20);
+    region.set_chunk(3, Chunk::new(vec![3; 64], 3, 0), 30);
+    let registry = FormatRegistry::default();
+    let options = WriteOptions::default();
+    rewrite_region_file(&path, &region, registry.get("mca").unwrap(), &[1], &options).unwrap();
+
+    let storage = read_anvil_chunk_storage(&path).unwrap();
+    let compression = |index: usize| storage[index].map(|slot| slot.compression_type);
+    assert_eq!(compression(0), Some(ChunkCompression::Gzip.as_u8()));
+    assert_eq!(compression(1), None);
+    assert_eq!(compression(2), Some(ChunkCompression::Gzip.as_u8()));
+    assert_eq!(compression(3), Some(ChunkCompression::Zlib.as_u8()));
+    assert!(!dir.join("c.1.0.mcc").exists());
+
+    let read = read_anvil_region(&path, None).unwrap();
+    assert_eq!(read.get_chunk(2).unwrap().as_slice(), &[9; 64][..]);
+    assert_eq!(read.timestamps[2], 20);
+    assert_eq!(read.get_chunk(3).unwrap().as_slice(), &[3; 64][..]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::palette;
+use fastnbt::{LongArray, Value};
+use linear_region_tools::histogram::{count_chunk_blocks, BlockHistogram};
+use std::collections::BTreeMap;
+
+fn section(y: i8, names: &[&str], data: Option<Vec<i64>>) -> Value {
+    let mut states = fastnbt::nbt!({ "palette": palette(names) });
+    if let (Value::Compound(states), Some(data)) = (&mut states, data) {
+        states.insert("data".to_string(), Value::LongArray(LongArray::new(data)));
+    }
+    fastnbt::nbt!({ "Y": y, "block_states": states })
+}
+
+/// Counts per (block, y) of `chunk`.
+fn count(chunk: &Value) -> anyhow::Result<BTreeMap<(String, i32), u64>> {
+    let mut counts = BTreeMap::new();
+    count_chunk_blocks(chunk, |y, block, count| {
+        *counts.entry((block.to_string(), y)).or_default() += count;
+    })?;
+    Ok(counts)
+}
+
+#[test]
+fn counts_single_entry_and_packed_sections() {
+    // 4 bits per block: 16 indices per long, 256 longs
+    let mut four_bits = vec![0i64; 256];
+    four_bits[0] = 1;
+    four_bits[16] = 1 << 4;
+    // 17 entries need 5 bits: 12 indices per long, the top 4 bits unused
+    let mut five_bits = vec![0i64; 342];
+    five_bits[0] = 16 << 55;
+    five_bits[1] = 16;
+
+    let names: Vec<String> = (0..17).map(|i| format!("minecraft:block_{}", i)).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let chunk = fastnbt::nbt!({
+        "DataVersion": 3955,
+        "sections": [
+            section(-4, &["minecraft:deepslate"], None),
+            section(0, &["minecraft:stone", "minecraft:ancient_debris"], Some(four_bits)),
+            section(1, &names, Some(five_bits)),
+        ],
+    });
+
+    let counts = count(&chunk).unwrap();
+    assert_eq!(counts[&("minecraft:deepslate".to_string(), -64)], 256);
+    assert_eq!(counts[&("minecraft:deepslate".to_string(), -49)], 256);
+    assert_eq!(counts[&("minecraft:ancient_debris".to_string(), 0)], 1);
+    assert_eq!(counts[&("minecraft:ancient_debris".to_string(), 1)], 1);
+    assert_eq!(counts[&("minecraft:stone".to_string(), 0)], 255);
+    assert_eq!(counts[&("minecraft:block_16".to_string(), 16)], 2);
+    assert_eq!(counts[&("minecraft:block_0".to_string(), 16)], 254);
+    assert_eq!(counts.values().sum::<u64>(), 3 * 4096);
+}
+
+#[test]
+fn counts_pre_1_18_sections_and_rejects_bad_data() {
+    let mut data = vec![0i64; 256];
+    data[255] = 1 << 60;
+    let legacy = fastnbt::nbt!({
+        "DataVersion": 2586,
+        "Level": {
+            "Sections": [
+                { "Y": -1i8, "SkyLight": [B; 0] },
+                {
+                    "Y": 2i8,
+                    "Palette": palette(&["minecraft:air", "minecraft:hopper"]),
+                    "BlockStates": Value::LongArray(LongArray::new(data)),
+                },
+            ],
+        },
+    });
+    let mut histogram = BlockHistogram::default();
+    let sections =
+        count_chunk_blocks(&legacy, |_, block, count| histogram.add(block, count)).unwrap();
+    assert_eq!(sections, 1);
+    assert_eq!(histogram.counts["minecraft:hopper"], 1);
+    assert_eq!(histogram.sorted()[0], ("minecraft:air", 4095));
+
+    // Before 1.16 indices span two longs: with 5 bits, index 12 starts at
+    // bit 60 of the first long and ends in the second
+    let names: Vec<String> = (0..17).map(|i| format!("minecraft:block_{}", i)).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let mut spanning_data = vec![0i64; 320];
+    spanning_data[1] = 1;
+    let spanning = fastnbt::nbt!({
+        "DataVersion": 1976,
+        "Level": {
+            "Sections": [
+                {
+                    "Y": 0i8,
+                    "Palette": palette(&names),
+                    "BlockStates": Value::LongArray(LongArray::new(spanning_data)),
+                },
+            ],
+        },
+    });
+    let counts = count(&spanning).unwrap();
+    assert_eq!(counts[&("minecraft:block_16".to_string(), 0)], 1);
+    assert_eq!(counts[&("minecraft:block_0".to_string(), 0)], 255);
+    assert_eq!(counts.values().sum::<u64>(), 4096);
+
+    let truncated = fastnbt::nbt!({
+        "DataVersion": 3955,
+        "sections": [section(0, &["minecraft:air", "minecraft:stone"], Some(vec![0; 10]))],
+    });
+    assert!(count(&truncated).is_err());
+
+    let missing = fastnbt::nbt!({
+        "DataVersion": 3955,
+        "sections": [section(0, &["minecraft:air", "minecraft:stone"], None)],
+    });
+    assert!(count(&missing).is_err());
+}
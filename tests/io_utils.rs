@@ -0,0 +1,37 @@
+use linear_region_tools::io_utils::{
+    atomic_write, is_region_temp_file, remove_temp_files, temp_path_for,
+};
+
+#[test]
+fn temp_names_do_not_collide_across_extensions() {
+    let dir = std::env::temp_dir().join("lrt_io_utils_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let (mca, linear) = (dir.join("r.0.0.mca"), dir.join("r.0.0.linear"));
+
+    let (mca_temp, linear_temp) = (temp_path_for(&mca), temp_path_for(&linear));
+    assert_ne!(mca_temp, linear_temp);
+    assert_ne!(mca_temp, temp_path_for(&mca));
+    assert!(mca_temp.to_string_lossy().ends_with(".tmp"));
+
+    atomic_write(&mca, b"anvil").unwrap();
+    atomic_write(&linear, b"linear").unwrap();
+    assert_eq!(std::fs::read(&mca).unwrap(), b"anvil");
+    assert_eq!(std::fs::read(&linear).unwrap(), b"linear");
+
+    std::fs::write(&mca_temp, b"interrupted").unwrap();
+    let unrelated = [
+        dir.join("notes.tmp"),
+        dir.join("r.0.0.mca.tmp"),
+        dir.join("r.0.0.mca.0123456789abcdef0123456789abcdeg.tmp"),
+    ];
+    for path in &unrelated {
+        std::fs::write(path, b"keep").unwrap();
+    }
+    assert!(is_region_temp_file(&mca_temp));
+    assert_eq!(remove_temp_files(&dir).unwrap(), 1);
+    assert!(mca.exists() && linear.exists());
+    assert!(unrelated.iter().all(|path| path.exists()));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
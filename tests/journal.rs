@@ -0,0 +1,48 @@
+use linear_region_tools::journal::{JobStatus, Journal};
+use std::io::Write;
+
+#[test]
+fn journal_survives_reopen_and_detects_changes() {
+    let dir = std::env::temp_dir().join("lrt_journal_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let journal_path = dir.join(".conversion_journal");
+    let (a, a_out) = (dir.join("r.0.0.mca"), dir.join("r.0.0.linear"));
+    let (b, b_out) = (dir.join("r.1.0.mca"), dir.join("r.1.0.linear"));
+    for path in [&a, &a_out, &b] {
+        std::fs::write(path, b"data").unwrap();
+    }
+
+    {
+        let journal = Journal::open(&journal_path, false).unwrap();
+        journal.mark_in_progress(&a).unwrap();
+        journal.mark_completed(&a, &a_out).unwrap();
+        journal.mark_in_progress(&b).unwrap();
+    }
+    // A record cut short by a crash
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&journal_path)
+        .unwrap();
+    file.write_all(b"completed\t4\t").unwrap();
+    drop(file);
+
+    let journal = Journal::open(&journal_path, true).unwrap();
+    assert!(journal.is_complete(&a, &a_out));
+    assert_eq!(journal.entry(&b).unwrap().status, JobStatus::InProgress);
+    assert!(!journal.is_complete(&b, &b_out));
+
+    journal.mark_failed(&b).unwrap();
+    std::fs::write(&a_out, b"changed").unwrap();
+    assert!(!journal.is_complete(&a, &a_out));
+    drop(journal);
+
+    let journal = Journal::open(&journal_path, true).unwrap();
+    assert_eq!(journal.entry(&b).unwrap().status, JobStatus::Failed);
+
+    // Starting without --resume forgets everything
+    let journal = Journal::open(&journal_path, false).unwrap();
+    assert!(journal.entry(&a).is_none());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,74 @@
+use linear_region_tools::{
+    anvil::{read_anvil_region, read_anvil_region_with_report, write_anvil_region},
+    Chunk, ChunkIssueReason, ReadMode, Region,
+};
+
+#[test]
+fn lenient_read_reports_damaged_chunks() {
+    let dir = std::env::temp_dir().join("lrt_lenient_read_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("r.0.0.mca");
+
+    let mut region = Region::new(0, 0);
+    for i in [0usize, 1, 2] {
+        region.set_chunk(i, Chunk::new(vec![i as u8; 64], i as i32, 0), 9);
+    }
+    write_anvil_region(&path, &region, 6, None).unwrap();
+
+    // Chunks are laid out in index order from sector 2; smash chunk 1's zlib stream
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[3 * 4096 + 5..3 * 4096 + 15].fill(0xff);
+    // Point chunk 2 past the end of the file
+    bytes[8..11].copy_from_slice(&[0, 0, 200]);
+    std::fs::write(&path, &bytes).unwrap();
+
+    let (read, report) = read_anvil_region_with_report(&path, ReadMode::Lenient, None).unwrap();
+    assert_eq!(read.chunk_count(), 1);
+    assert_eq!(report.chunks_loaded, 1);
+    assert_eq!(report.issues.len(), 2);
+    assert_eq!((report.issues[0].x, report.issues[0].z), (1, 0));
+    assert!(matches!(
+        report.issues[0].reason,
+        ChunkIssueReason::DecompressionFailed { .. }
+    ));
+    assert!(matches!(
+        report.issues[1].reason,
+        ChunkIssueReason::SectorOutOfBounds { .. }
+    ));
+
+    assert!(read_anvil_region_with_report(&path, ReadMode::Strict, None).is_err());
+    assert!(read_anvil_region(&path, None).is_err());
+
+    // A length field past the allocated sectors fails strict reads and is clamped and reported when lenient
+    let long_path = dir.join("r.1.0.mca");
+    let mut region = Region::new(1, 0);
+    region.set_chunk(0, Chunk::new(vec![7u8; 64], 32, 0), 9);
+    write_anvil_region(&long_path, &region, 6, None).unwrap();
+    let mut bytes = std::fs::read(&long_path).unwrap();
+    bytes[2 * 4096..2 * 4096 + 4].copy_from_slice(&100_000u32.to_be_bytes());
+    std::fs::write(&long_path, &bytes).unwrap();
+    let strict = read_anvil_region(&long_path, None).unwrap();
+    assert_eq!(strict.get_chunk(0).unwrap().as_slice(), &[7u8; 64][..]);
+    assert!(read_anvil_region_with_report(&long_path, ReadMode::Strict, None).is_err());
+    let (lenient, report) =
+        read_anvil_region_with_report(&long_path, ReadMode::Lenient, None).unwrap();
+    assert_eq!(lenient.get_chunk(0).unwrap().as_slice(), &[7u8; 64][..]);
+    assert_eq!(report.chunks_loaded, 1);
+    assert!(matches!(
+        report.issues[0].reason,
+        ChunkIssueReason::LengthClamped {
+            length: 100_000,
+            available: 4092
+        }
+    ));
+
+    let empty_path = dir.join("r.5.5.mca");
+    std::fs::File::create(&empty_path).unwrap();
+    let (empty, report) =
+        read_anvil_region_with_report(&empty_path, ReadMode::Lenient, None).unwrap();
+    assert_eq!(empty.chunk_count(), 0);
+    assert!(report.truncated_header);
+    assert!(read_anvil_region_with_report(&empty_path, ReadMode::Strict, None).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,34 @@
+use linear_region_tools::{
+    linear::{write_linear_region, LinearRegionReader, LinearVersion},
+    Chunk, Region,
+};
+
+#[test]
+fn random_access_matches_written_chunks() {
+    let dir = std::env::temp_dir().join("lrt_linear_reader_test");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut region = Region::new(0, 1);
+    for &(x, z) in &[(0, 32), (5, 40), (31, 63)] {
+        let data = format!("chunk {x} {z}").repeat(x as usize + 1).into_bytes();
+        region.set_chunk_at(x, z, Chunk::new(data, x, z), 1);
+    }
+
+    for version in [LinearVersion::V1, LinearVersion::V2] {
+        let path = dir.join("r.0.1.linear");
+        write_linear_region(&path, &region, 3, version, None).unwrap();
+
+        let reader = LinearRegionReader::open(&path).unwrap();
+        for &(x, z) in &[(0, 32), (5, 40), (31, 63)] {
+            let chunk = reader.get_chunk_at(x, z).unwrap().unwrap();
+            assert_eq!(
+                chunk.as_slice(),
+                region.get_chunk_at(x, z).unwrap().as_slice()
+            );
+            assert_eq!((chunk.x, chunk.z), (x, z));
+        }
+        assert!(reader.get_chunk_at(1, 32).unwrap().is_none());
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
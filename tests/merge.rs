@@ -0,0 +1,71 @@
+use linear_region_tools::{
+    merge::{merge_regions, merge_regions_with_report, MergePolicy},
+    Chunk, Region,
+};
+
+fn region(chunks: &[(usize, u8, u32)]) -> Region {
+    let mut region = Region::new(0, 0);
+    for &(index, byte, timestamp) in chunks {
+        region.set_chunk(index, Chunk::new(vec![byte], index as i32, 0), timestamp);
+    }
+    region
+}
+
+fn payload(region: &Region, index: usize) -> Option<u8> {
+    region.get_chunk(index).map(|chunk| chunk.as_slice()[0])
+}
+
+#[test]
+fn merge_policies_pick_chunks_per_slot() {
+    // Slot 0 is newer on the left, slot 1 newer on the right, slot 2 tied,
+    // slots 3 and 4 only exist on one side
+    let left = region(&[(0, 1, 200), (1, 1, 100), (2, 1, 100), (3, 1, 100)]);
+    let right = region(&[(0, 2, 100), (1, 2, 200), (2, 2, 100), (4, 2, 100)]);
+
+    let expected = [
+        (MergePolicy::Newest, [1, 2, 1]),
+        (MergePolicy::LeftWins, [1, 1, 1]),
+        (MergePolicy::RightWins, [2, 2, 2]),
+        (MergePolicy::FillEmpty, [1, 1, 1]),
+    ];
+    for (policy, conflicts) in expected {
+        let (merged, report) = merge_regions_with_report(&left, &right, policy).unwrap();
+        assert_eq!(merged.chunk_count(), 5, "{:?}", policy);
+        for (index, byte) in conflicts.into_iter().enumerate() {
+            assert_eq!(
+                payload(&merged, index),
+                Some(byte),
+                "{:?} slot {}",
+                policy,
+                index
+            );
+        }
+        assert_eq!(payload(&merged, 3), Some(1));
+        assert_eq!(payload(&merged, 4), Some(2));
+        assert_eq!(report.conflicts, 3);
+        assert_eq!(report.from_left + report.from_right, 5);
+    }
+
+    let merged = merge_regions(&left, &right, MergePolicy::Newest).unwrap();
+    assert_eq!(merged.timestamps[0], 200);
+    assert_eq!(merged.timestamps[1], 200);
+    assert_eq!(merged.timestamps[4], 100);
+
+    // FillEmpty keeps the left mtime and timestamps that LeftWins recomputes
+    let mut left = left;
+    left.mtime = 10;
+    left.timestamps[7] = 55;
+    let mut right = right;
+    right.mtime = 20;
+    let left_wins = merge_regions(&left, &right, MergePolicy::LeftWins).unwrap();
+    assert_eq!(left_wins.mtime, 20);
+    assert_eq!(left_wins.timestamps[7], 0);
+    let filled = merge_regions(&left, &right, MergePolicy::FillEmpty).unwrap();
+    assert_eq!(filled.mtime, 10);
+    assert_eq!(filled.timestamps[7], 55);
+    assert_eq!(filled.timestamps[4], 100);
+    assert_eq!(payload(&filled, 4), Some(2));
+
+    let elsewhere = Region::new(1, 0);
+    assert!(merge_regions(&left, &elsewhere, MergePolicy::Newest).is_err());
+}
@@ -0,0 +1,166 @@
+use fastnbt::{LongArray, Value};
+use linear_region_tools::{
+    histogram::count_chunk_blocks,
+    nbt::{
+        bits_per_entry, pack_longs, unpack_longs, ModernChunk, PackingLayout, PalettedValues,
+        BIOME_MIN_BITS, BLOCK_STATE_MIN_BITS,
+    },
+    render::{render_chunk, ColorTable},
+};
+
+#[test]
+fn packs_and_unpacks_both_layouts() {
+    assert_eq!(bits_per_entry(1, BLOCK_STATE_MIN_BITS), 4);
+    assert_eq!(bits_per_entry(17, BLOCK_STATE_MIN_BITS), 5);
+    assert_eq!(bits_per_entry(2, BIOME_MIN_BITS), 1);
+    assert_eq!(bits_per_entry(1, 0), 0);
+    assert_eq!(PackingLayout::Aligned.packed_len(5, 4096), 342);
+    assert_eq!(PackingLayout::Spanning.packed_len(5, 4096), 320);
+    assert_eq!(PackingLayout::Aligned.packed_len(9, 256), 37);
+    assert_eq!(PackingLayout::Spanning.packed_len(9, 256), 36);
+    assert_eq!(
+        PackingLayout::for_data_version(Some(2528)),
+        PackingLayout::Spanning
+    );
+    assert_eq!(
+        PackingLayout::for_data_version(Some(2529)),
+        PackingLayout::Aligned
+    );
+
+    for layout in [PackingLayout::Spanning, PackingLayout::Aligned] {
+        for bits in 1..=16 {
+            let values: Vec<u16> = (0..4096u32)
+                .map(|i| (i.wrapping_mul(2654435761) >> 7) as u16 & ((1u32 << bits) - 1) as u16)
+                .collect();
+            let data = pack_longs(&values, bits, layout).unwrap();
+            assert_eq!(data.len(), layout.packed_len(bits, values.len()));
+            assert_eq!(
+                unpack_longs(&data, bits, values.len(), layout).unwrap(),
+                values
+            );
+        }
+    }
+
+    // The 13th 5-bit value starts at bit 60 and continues in the next long
+    let mut values = vec![0u16; 13];
+    values[12] = 0b10011;
+    assert_eq!(
+        pack_longs(&values, 5, PackingLayout::Spanning).unwrap(),
+        vec![0b0011 << 60, 0b1]
+    );
+    assert_eq!(
+        pack_longs(&values, 5, PackingLayout::Aligned).unwrap(),
+        vec![0, 0b10011]
+    );
+
+    assert!(unpack_longs(&[0; 3], 5, 13, PackingLayout::Aligned).is_err());
+    assert!(unpack_longs(&[0; 2], 17, 1, PackingLayout::Aligned).is_err());
+    assert!(pack_longs(&values, 0, PackingLayout::Spanning).is_err());
+}
+
+#[test]
+fn grows_and_shrinks_palettes() {
+    let mut blocks = PalettedValues::filled("minecraft:air".to_string(), 4096);
+    assert_eq!(blocks.len(), 4096);
+    assert_eq!(
+        blocks
+            .pack(BLOCK_STATE_MIN_BITS, PackingLayout::Aligned)
+            .unwrap(),
+        vec![0; 256]
+    );
+
+    for i in 0..20 {
+        blocks.set(i, format!("minecraft:block_{}", i));
+    }
+    blocks.set(20, "minecraft:block_0".to_string());
+    assert_eq!(blocks.palette.len(), 21);
+    assert_eq!(blocks.get(20), "minecraft:block_0");
+
+    let packed = blocks
+        .pack(BLOCK_STATE_MIN_BITS, PackingLayout::Spanning)
+        .unwrap();
+    assert_eq!(packed.len(), 320);
+    let unpacked = PalettedValues::unpack(
+        blocks.palette.clone(),
+        Some(&packed),
+        4096,
+        BLOCK_STATE_MIN_BITS,
+        PackingLayout::Spanning,
+    )
+    .unwrap();
+    assert_eq!(unpacked, blocks);
+
+    // Overwriting all but five blocks leaves unused palette entries
+    for i in 5..21 {
+        blocks.set(i, "minecraft:air".to_string());
+    }
+    assert_eq!(blocks.remove_unused(), 15);
+    assert_eq!(blocks.palette.len(), 6);
+    assert_eq!(blocks.palette[0], "minecraft:air");
+    assert_eq!(blocks.get(4), "minecraft:block_4");
+    assert_eq!(blocks.get(5), "minecraft:air");
+    assert_eq!(blocks.remove_unused(), 0);
+    assert_eq!(
+        blocks
+            .pack(BLOCK_STATE_MIN_BITS, PackingLayout::Aligned)
+            .unwrap()
+            .len(),
+        256
+    );
+
+    let biomes = PalettedValues::unpack(
+        vec!["minecraft:plains".to_string()],
+        None,
+        64,
+        BIOME_MIN_BITS,
+        PackingLayout::Aligned,
+    )
+    .unwrap();
+    assert_eq!(biomes.get(63), "minecraft:plains");
+
+    let palette = vec!["a", "b", "c"];
+    let out_of_range = pack_longs(&[3; 64], 2, PackingLayout::Aligned).unwrap();
+    assert!(PalettedValues::unpack(
+        palette.clone(),
+        Some(&out_of_range),
+        64,
+        1,
+        PackingLayout::Aligned
+    )
+    .is_err());
+    assert!(PalettedValues::unpack(palette, None, 64, 1, PackingLayout::Aligned).is_err());
+    assert!(
+        PalettedValues::<&str>::unpack(Vec::new(), None, 64, 1, PackingLayout::Aligned).is_err()
+    );
+}
+
+#[test]
+fn every_reader_rejects_the_same_bad_sections() {
+    let chunk_with = |block_states: Value| {
+        fastnbt::nbt!({
+            "DataVersion": 3955,
+            "xPos": 0,
+            "yPos": -4,
+            "zPos": 0,
+            "Status": "minecraft:full",
+            "sections": [{ "Y": 0i8, "block_states": block_states }],
+        })
+    };
+    let two = || fastnbt::nbt!([{ "Name": "minecraft:air" }, { "Name": "minecraft:stone" }]);
+    // Index 5 with two palette entries
+    let mut data = vec![0i64; 256];
+    data[0] = 5;
+    let bad = [
+        fastnbt::nbt!({ "palette": [] }),
+        fastnbt::nbt!({ "palette": two() }),
+        fastnbt::nbt!({ "palette": two(), "data": Value::LongArray(LongArray::new(data)) }),
+    ];
+
+    for block_states in bad {
+        let chunk = chunk_with(block_states);
+        let model = ModernChunk::from_bytes(&fastnbt::to_bytes(&chunk).unwrap()).unwrap();
+        assert!(model.block_state(0, 0, 0).is_err());
+        assert!(count_chunk_blocks(&chunk, |_, _, _| {}).is_err());
+        assert!(render_chunk(&chunk, &ColorTable::default()).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use fastnbt::{IntArray, Value};
+use linear_region_tools::{
+    relocate::{relocate_chunk, relocate_nbt},
+    Chunk, Region,
+};
+
+fn get<'a>(value: &'a Value, path: &[&str]) -> &'a Value {
+    path.iter().fold(value, |value, key| match value {
+        Value::Compound(compound) => &compound[*key],
+        _ => panic!("{} is not in a compound", key),
+    })
+}
+
+fn first(value: &Value) -> &Value {
+    match value {
+        Value::List(list) => &list[0],
+        _ => panic!("not a list"),
+    }
+}
+
+#[test]
+fn relocates_modern_chunk_coordinates() {
+    let nbt = fastnbt::nbt!({
+        "xPos": 3,
+        "zPos": -2,
+        "yPos": -4,
+        "block_entities": [{ "id": "minecraft:chest", "x": 50, "y": 64, "z": -20 }],
+        "block_ticks": [{ "i": "minecraft:water", "x": 49, "y": 60, "z": -30, "t": 1 }],
+    });
+    let chunk = Chunk::from_nbt(&nbt, 3, -2).unwrap();
+
+    let moved = relocate_chunk(&chunk, 40, 5).unwrap();
+    assert_eq!((moved.x, moved.z), (40, 5));
+
+    let nbt = moved.parse_nbt().unwrap();
+    assert_eq!(get(&nbt, &["xPos"]), &Value::Int(40));
+    assert_eq!(get(&nbt, &["zPos"]), &Value::Int(5));
+    assert_eq!(get(&nbt, &["yPos"]), &Value::Int(-4));
+    let chest = first(get(&nbt, &["block_entities"]));
+    assert_eq!(get(chest, &["x"]), &Value::Int(50 + 37 * 16));
+    assert_eq!(get(chest, &["y"]), &Value::Int(64));
+    assert_eq!(get(chest, &["z"]), &Value::Int(-20 + 7 * 16));
+    let tick = first(get(&nbt, &["block_ticks"]));
+    assert_eq!(get(tick, &["x"]), &Value::Int(49 + 37 * 16));
+    assert_eq!(get(tick, &["z"]), &Value::Int(-30 + 7 * 16));
+}
+
+#[test]
+fn relocates_legacy_chunk_and_entities() {
+    let mut nbt = fastnbt::nbt!({
+        "Level": {
+            "xPos": 0,
+            "zPos": 0,
+            "TileEntities": [{ "id": "minecraft:sign", "x": 1, "y": 70, "z": 2 }],
+            "Entities": [{
+                "id": "minecraft:boat",
+                "Pos": [1.5, 70.0, 2.5],
+                "Passengers": [{ "id": "minecraft:pig", "Pos": [1.5, 70.5, 2.5] }],
+            }],
+        },
+    });
+    relocate_nbt(&mut nbt, -1, 2).unwrap();
+
+    assert_eq!(get(&nbt, &["Level", "xPos"]), &Value::Int(-1));
+    assert_eq!(get(&nbt, &["Level", "zPos"]), &Value::Int(2));
+    let sign = first(get(&nbt, &["Level", "TileEntities"]));
+    assert_eq!(get(sign, &["x"]), &Value::Int(-15));
+    assert_eq!(get(sign, &["z"]), &Value::Int(34));
+
+    let boat = first(get(&nbt, &["Level", "Entities"]));
+    let expected_pos = |y: f64| {
+        Value::List(vec![
+            Value::Double(-14.5),
+            Value::Double(y),
+            Value::Double(34.5),
+        ])
+    };
+    assert_eq!(get(boat, &["Pos"]), &expected_pos(70.0));
+    let pig = first(get(boat, &["Passengers"]));
+    assert_eq!(get(pig, &["Pos"]), &expected_pos(70.5));
+}
+
+#[test]
+fn relocates_entity_and_poi_chunks() {
+    let mut entities = fastnbt::nbt!({
+        "Position": [I; 2, 3],
+        "Entities": [{
+            "id": "minecraft:item_frame",
+            "Pos": [32.5, 64.0, 48.5],
+            "TileX": 32,
+            "TileY": 64,
+            "TileZ": 48,
+        }],
+    });
+    relocate_nbt(&mut entities, 1, -1).unwrap();
+    assert_eq!(
+        get(&entities, &["Position"]),
+        &Value::IntArray(IntArray::new(vec![3, 2]))
+    );
+    let frame = first(get(&entities, &["Entities"]));
+    assert_eq!(get(frame, &["TileX"]), &Value::Int(48));
+    assert_eq!(get(frame, &["TileY"]), &Value::Int(64));
+    assert_eq!(get(frame, &["TileZ"]), &Value::Int(32));
+
+    let mut poi = fastnbt::nbt!({
+        "Sections": {
+            "4": { "Valid": 1i8, "Records": [{ "type": "minecraft:home", "pos": [I; 5, 70, 6] }] },
+        },
+    });
+    relocate_nbt(&mut poi, 2, 0).unwrap();
+    let record = first(get(&poi, &["Sections", "4", "Records"]));
+    assert_eq!(
+        get(record, &["pos"]),
+        &Value::IntArray(IntArray::new(vec![37, 70, 6]))
+    );
+
+    // Positions that would leave the i32 range are an error, not a wrap
+    let mut far = fastnbt::nbt!({ "block_entities": [{ "x": i32::MAX - 8, "z": 0 }] });
+    assert!(relocate_nbt(&mut far, 1, 0).is_err());
+    assert!(relocate_nbt(&mut far, i32::MAX, 0).is_err());
+}
+
+#[test]
+fn insert_chunk_rejects_other_regions() {
+    let mut region = Region::new(-1, 0);
+    region.insert_chunk(Chunk::new(vec![1], -1, 31), 7).unwrap();
+    assert!(region.get_chunk(31 * 32 + 31).is_some());
+    assert_eq!(region.timestamps[31 * 32 + 31], 7);
+
+    let err = region
+        .insert_chunk(Chunk::new(vec![1], 0, 0), 7)
+        .unwrap_err();
+    assert!(err.to_string().contains("outside region (-1, 0)"));
+    assert_eq!(region.chunk_count(), 1);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::palette;
+use fastnbt::{LongArray, Value};
+use linear_region_tools::{
+    render::{render_region, ColorTable, TILE_SIZE, TRANSPARENT, UNREADABLE_COLOR},
+    Chunk, Region,
+};
+
+/// Packs 9-bit heights seven to a long, as 1.16+ heightmaps do.
+fn heightmap(heights: &[u16; 256]) -> Value {
+    let mut data = vec![0i64; 37];
+    for (i, &height) in heights.iter().enumerate() {
+        data[i / 7] |= (height as i64) << ((i % 7) * 9);
+    }
+    Value::LongArray(LongArray::new(data))
+}
+
+#[test]
+fn parses_color_tables() {
+    let mut colors = ColorTable::default();
+    colors
+        .extend_from_str("# comment\n\nminecraft:stone = 010203\nminecraft:glass=#10203040\n")
+        .unwrap();
+    assert_eq!(colors.color("minecraft:stone"), [1, 2, 3, 255]);
+    assert_eq!(colors.color("minecraft:glass"), [0x10, 0x20, 0x30, 0x40]);
+    // Unlisted blocks get a colour from their name
+    assert_eq!(
+        colors.color("mod:pink_leaves"),
+        colors.color("minecraft:some_leaves")
+    );
+
+    assert!(colors.extend_from_str("minecraft:stone 010203").is_err());
+    assert!(colors.extend_from_str("minecraft:stone = 0102").is_err());
+    assert!(colors.extend_from_str("minecraft:stone = zzzzzz").is_err());
+}
+
+#[test]
+fn renders_surface_blocks_with_shading() {
+    let mut colors = ColorTable::default();
+    colors
+        .extend_from_str("minecraft:stone = 808080\nminecraft:grass_block = 00c000")
+        .unwrap();
+
+    // Stone fills y 0..16 and grass covers it at y 16, except in the first
+    // row of columns where the heightmap ends at the stone
+    let mut data = vec![0i64; 256];
+    for word in &mut data[..16] {
+        *word = 0x1111_1111_1111_1111;
+    }
+    let mut heights = [17u16; 256];
+    heights[..16].fill(16);
+    let chunk = fastnbt::nbt!({
+        "DataVersion": 3955,
+        "xPos": 0,
+        "yPos": 0,
+        "zPos": 0,
+        "sections": [
+            {
+                "Y": 0i8,
+                "block_states": { "palette": palette(&["minecraft:stone"]) },
+            },
+            {
+                "Y": 1i8,
+                "block_states": {
+                    "palette": palette(&["minecraft:air", "minecraft:grass_block"]),
+                    "data": Value::LongArray(LongArray::new(data)),
+                },
+            },
+        ],
+        "Heightmaps": { "MOTION_BLOCKING": heightmap(&heights) },
+    });
+    // Without a heightmap the top of the column is found by searching down
+    let bare = fastnbt::nbt!({
+        "DataVersion": 3955,
+        "xPos": 1,
+        "zPos": 0,
+        "sections": [{ "Y": 0i8, "block_states": { "palette": palette(&["minecraft:stone"]) } }],
+    });
+
+    let mut region = Region::new(0, 0);
+    region.set_chunk(0, Chunk::from_nbt(&chunk, 0, 0).unwrap(), 0);
+    region.set_chunk(1, Chunk::from_nbt(&bare, 1, 0).unwrap(), 0);
+    region.set_chunk(32, Chunk::new(vec![0xff; 10], 0, 1), 0);
+
+    let map = render_region(&region, &colors);
+    assert_eq!(map.pixels.len(), TILE_SIZE * TILE_SIZE);
+    assert_eq!(map.chunks_rendered, 2);
+    assert_eq!(map.chunks_unreadable, 1);
+
+    let pixel = |x: usize, z: usize| map.pixels[z * TILE_SIZE + x];
+    assert_eq!(pixel(0, 0), [128, 128, 128, 255]);
+    // Higher than the block to the north
+    assert_eq!(pixel(0, 1), [0, 220, 0, 255]);
+    assert_eq!(pixel(0, 2), [0, 192, 0, 255]);
+    assert_eq!(pixel(20, 5), [128, 128, 128, 255]);
+    assert_eq!(pixel(5, 20), UNREADABLE_COLOR);
+    assert_eq!(pixel(40, 40), TRANSPARENT);
+}
@@ -1,6 +1,8 @@
 use linear_region_tools::{
     anvil::{read_anvil_region, write_anvil_region},
-    linear::{read_linear_region, write_linear_region, LinearVersion},
+    linear::{
+        read_linear_region, write_linear_region, write_linear_region_with_grid, LinearVersion,
+    },
     Chunk, Region,
 };
 
@@ -39,10 +41,54 @@ fn mca_linear_roundtrip() {
     for &(x, z) in &[(-32, 64), (-17, 70), (-1, 95)] {
         let orig = region.get_chunk_at(x, z).unwrap();
         let round = from_linear.get_chunk_at(x, z).unwrap();
-        assert_eq!(orig.as_slice(), round.as_slice(), "chunk ({x},{z}) data mismatch");
+        assert_eq!(
+            orig.as_slice(),
+            round.as_slice(),
+            "chunk ({x},{z}) data mismatch"
+        );
         let idx = ((z & 31) as usize) * 32 + ((x & 31) as usize);
         assert_eq!(from_linear.timestamps[idx], 12345);
     }
 
     std::fs::remove_dir_all(&dir).unwrap();
 }
+
+#[test]
+fn linear_v2_roundtrip() {
+    let dir = std::env::temp_dir().join("lrt_linear_v2_test");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut region = Region::new(3, -4);
+    for &(x, z) in &[(96, -128), (97, -128), (127, -97), (110, -110)] {
+        region.set_chunk_at(x, z, fake_nbt_chunk(x, z), 777);
+    }
+
+    for grid_size in [1u8, 4, 8, 32] {
+        let path = dir.join("r.3.-4.linear");
+        write_linear_region_with_grid(&path, &region, 6, LinearVersion::V2, grid_size, None)
+            .unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes[8], 2, "version byte");
+        assert_eq!(bytes[17], grid_size, "grid size byte");
+
+        let from_v2 = read_linear_region(&path, None).unwrap();
+        assert_eq!(from_v2.chunk_count(), 4);
+        for &(x, z) in &[(96, -128), (97, -128), (127, -97), (110, -110)] {
+            let orig = region.get_chunk_at(x, z).unwrap();
+            let round = from_v2.get_chunk_at(x, z).unwrap();
+            assert_eq!(
+                orig.as_slice(),
+                round.as_slice(),
+                "grid {grid_size} chunk ({x},{z})"
+            );
+            let idx = ((z & 31) as usize) * 32 + ((x & 31) as usize);
+            assert_eq!(from_v2.timestamps[idx], 777);
+        }
+    }
+
+    let path = dir.join("r.3.-4.linear");
+    assert!(write_linear_region_with_grid(&path, &region, 6, LinearVersion::V2, 3, None).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
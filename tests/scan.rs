@@ -0,0 +1,94 @@
+use linear_region_tools::{
+    anvil::{write_anvil_region_with_options, AnvilWriteOptions, ChunkCompression},
+    format::FormatRegistry,
+    linear::{write_linear_region, LinearVersion},
+    scan::scan_region_file,
+    Chunk, Region,
+};
+
+#[test]
+fn scans_chunk_metadata_from_anvil_and_linear_files() {
+    let dir = std::env::temp_dir().join("lrt_scan_test");
+    let entities_dir = dir.join("entities");
+    std::fs::create_dir_all(&entities_dir).unwrap();
+
+    let mut region = Region::new(-1, 0);
+    let modern = fastnbt::nbt!({
+        "DataVersion": 3955,
+        "xPos": -32,
+        "zPos": 0,
+        "Status": "minecraft:full",
+        "InhabitedTime": 1234i64,
+        "block_entities": [{ "id": "minecraft:chest" }, { "id": "minecraft:sign" }],
+    });
+    let legacy = fastnbt::nbt!({
+        "DataVersion": 1343,
+        "Level": {
+            "xPos": -31,
+            "zPos": 0,
+            "Status": "postprocessed",
+            "InhabitedTime": 5i64,
+            "Entities": [{ "id": "minecraft:cow" }],
+            "TileEntities": [],
+        },
+    });
+    region.set_chunk(0, Chunk::from_nbt(&modern, -32, 0).unwrap(), 100);
+    region.set_chunk(1, Chunk::from_nbt(&legacy, -31, 0).unwrap(), 200);
+    region.set_chunk(32, Chunk::new(vec![0xff; 10], -32, 1), 300);
+
+    let registry = FormatRegistry::default();
+    let anvil_path = dir.join("r.-1.0.mca");
+    let options = AnvilWriteOptions::new(ChunkCompression::Lz4, 6);
+    write_anvil_region_with_options(&anvil_path, &region, &options, None).unwrap();
+
+    let records = scan_region_file(&registry, &anvil_path, None).unwrap();
+    assert_eq!(records.len(), 3);
+
+    let first = &records[0];
+    assert_eq!((first.region_x, first.region_z), (-1, 0));
+    assert_eq!((first.index, first.x, first.z), (0, -32, 0));
+    assert_eq!(first.timestamp, 100);
+    assert_eq!(first.size, region.get_chunk(0).unwrap().size());
+    assert!(first.compressed_size.is_some_and(|size| size > 0));
+    assert_eq!(first.compression, "lz4");
+    assert!(!first.external);
+    assert_eq!(first.data_version, Some(3955));
+    assert_eq!(first.status.as_deref(), Some("minecraft:full"));
+    assert_eq!(first.inhabited_time, Some(1234));
+    assert_eq!(first.entities, None);
+    assert_eq!(first.block_entities, Some(2));
+
+    let second = &records[1];
+    assert_eq!(second.data_version, Some(1343));
+    assert_eq!(second.status.as_deref(), Some("postprocessed"));
+    assert_eq!(second.inhabited_time, Some(5));
+    assert_eq!(second.entities, Some(1));
+    assert_eq!(second.block_entities, Some(0));
+
+    // Chunks whose NBT does not parse still get a row
+    let broken = &records[2];
+    assert_eq!((broken.index, broken.x, broken.z), (32, -32, 1));
+    assert_eq!(broken.size, 10);
+    assert_eq!(broken.data_version, None);
+    assert_eq!(broken.inhabited_time, None);
+
+    // Entity counts come from the entities folder when one is given
+    let mut entity_region = Region::new(-1, 0);
+    let entities = fastnbt::nbt!({
+        "Position": [I; -32, 0],
+        "Entities": [{ "id": "minecraft:pig" }, { "id": "minecraft:pig" }, { "id": "minecraft:pig" }],
+    });
+    entity_region.set_chunk(0, Chunk::from_nbt(&entities, -32, 0).unwrap(), 100);
+    let linear_path = entities_dir.join("r.-1.0.linear");
+    write_linear_region(&linear_path, &entity_region, 6, LinearVersion::V2, None).unwrap();
+
+    let records = scan_region_file(&registry, &anvil_path, Some(&linear_path)).unwrap();
+    assert_eq!(records[0].entities, Some(3));
+    assert_eq!(records[1].entities, Some(0));
+
+    let records = scan_region_file(&registry, &linear_path, None).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].compression, "zstd");
+    assert_eq!(records[0].compressed_size, None);
+    assert_eq!(records[0].entities, Some(3));
+}
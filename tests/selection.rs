@@ -0,0 +1,81 @@
+use linear_region_tools::selection::{ChunkBox, ChunkSelection, Overlap};
+
+#[test]
+fn parses_and_evaluates_selections() {
+    let selection: ChunkSelection =
+        "blocks(-16, -16, 31, 47) | (circle(0, 0, 40) & !chunks(0, 0, 0, 0))"
+            .parse()
+            .unwrap();
+    // blocks() covers chunks -1..=1 by -1..=2
+    assert!(selection.contains(-1, 2));
+    assert!(!selection.contains(2, 0));
+    // circle() keeps chunks whose centre is within 40 blocks; chunk (2, 0) has its
+    // centre at (40, 8), just outside
+    let circle: ChunkSelection = "circle(0, 0, 40) & !chunks(0, 0, 0, 0)".parse().unwrap();
+    assert!(circle.contains(-2, 0));
+    assert!(!circle.contains(0, 0));
+    assert!(!circle.contains(2, 0));
+
+    let reparsed: ChunkSelection = selection.to_string().parse().unwrap();
+    assert_eq!(reparsed, selection);
+
+    let regions: ChunkSelection = "regions(0, 0, -1, 2)".parse().unwrap();
+    assert!(regions.contains(31, 31));
+    assert!(regions.contains(-1, 64));
+    assert!(!regions.contains(32, 0));
+    assert_eq!(regions.region_overlap(-1, 2), Overlap::Full);
+    assert_eq!(regions.region_overlap(1, 0), Overlap::None);
+
+    assert_eq!(selection.region_overlap(0, 0), Overlap::Partial);
+    assert_eq!(selection.region_overlap(5, 5), Overlap::None);
+    let everything_but: ChunkSelection = "!chunks(0, 0, 31, 31)".parse().unwrap();
+    assert_eq!(everything_but.region_overlap(0, 0), Overlap::None);
+    assert_eq!(everything_but.region_overlap(-1, 0), Overlap::Full);
+    let big_circle = ChunkSelection::Circle {
+        center_x: 256,
+        center_z: 256,
+        radius: 600,
+    };
+    assert_eq!(big_circle.region_overlap(0, 0), Overlap::Full);
+    assert_eq!(big_circle.region_overlap(1, 0), Overlap::Partial);
+    assert_eq!(big_circle.region_overlap(4, 4), Overlap::None);
+
+    assert_eq!(
+        "chunks(3, 4, 1, 2)".parse::<ChunkSelection>().unwrap(),
+        ChunkSelection::Box(ChunkBox::new(1, 2, 3, 4))
+    );
+    assert!("all".parse::<ChunkSelection>().unwrap().contains(9, 9));
+    assert!(!"none".parse::<ChunkSelection>().unwrap().contains(9, 9));
+
+    let error = "chunks(1, 2, 3) | all"
+        .parse::<ChunkSelection>()
+        .unwrap_err();
+    assert_eq!(error.column, 1);
+    assert!("circle(0, 0, -5)".parse::<ChunkSelection>().is_err());
+    assert!("chunks(0, 0, 1, 1) all".parse::<ChunkSelection>().is_err());
+    assert!("squares(1)".parse::<ChunkSelection>().is_err());
+}
+
+#[test]
+fn reads_selection_files() {
+    let dir = std::env::temp_dir().join("lrt_selection_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("spawn.txt");
+    std::fs::write(
+        &path,
+        "# spawn\nchunks(0, 0, 1, 1)\n\nregions(5, 5) # shops\n",
+    )
+    .unwrap();
+
+    let selection = ChunkSelection::from_file(&path).unwrap();
+    assert!(selection.contains(1, 1));
+    assert!(selection.contains(170, 170));
+    assert!(!selection.contains(2, 2));
+
+    std::fs::write(&path, "chunks(0, 0, 1, 1)\nchunks(0)\n").unwrap();
+    let error = ChunkSelection::from_file(&path).unwrap_err();
+    assert!(format!("{:#}", error).contains("spawn.txt:2"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,95 @@
+use fastnbt::{ByteArray, IntArray, LongArray, Value};
+use linear_region_tools::snbt::{from_snbt, to_snbt};
+
+#[test]
+fn round_trips_every_tag_type() {
+    let value = fastnbt::nbt!({
+        "DataVersion": 3955,
+        "byte": -3i8,
+        "short": 300i16,
+        "long": -9_000_000_000i64,
+        "float": 0.1f32,
+        "double": -1.5e-300f64,
+        "whole": 2.0f64,
+        "nan": f32::NAN,
+        "inf": f64::NEG_INFINITY,
+        "quoted key": "line\nbreak \"quoted\" \\ 'single' ✓",
+        "": "empty key",
+        "bytes": Value::ByteArray(ByteArray::new(vec![-128, 0, 127])),
+        "ints": Value::IntArray(IntArray::new(vec![i32::MIN, 7])),
+        "longs": Value::LongArray(LongArray::new(vec![i64::MAX, -1])),
+        "empty_longs": Value::LongArray(LongArray::new(vec![])),
+        "empty_list": Value::List(vec![]),
+        "empty_compound": {},
+        "nested": [[1i16, 2i16], [], [3i16]],
+        "entities": [
+            { "id": "minecraft:pig", "Pos": [1.5f64, 64.0f64, -2.25f64] },
+            { "id": "minecraft:cow", "Tags": ["a", "b"] },
+        ],
+    });
+
+    let text = to_snbt(&value);
+    let parsed = from_snbt(&text).unwrap();
+    // NaN never equals itself, so compare it separately
+    let (Value::Compound(mut parsed), Value::Compound(mut expected)) = (parsed, value) else {
+        unreachable!()
+    };
+    assert!(matches!(parsed.remove("nan"), Some(Value::Float(f)) if f.is_nan()));
+    expected.remove("nan");
+    assert_eq!(parsed, expected);
+
+    // Keys are sorted, so the same NBT always gives the same text
+    assert!(text.starts_with("{\n    \"\": \"empty key\",\n    DataVersion: 3955,\n"));
+    assert!(text.contains("bytes: [B; -128b, 0b, 127b],"));
+    assert!(text.contains("float: 0.1f,"));
+    assert!(text.contains("inf: -Infinityd,"));
+    assert!(text.contains("Pos: [1.5d, 64.0d, -2.25d]"));
+    assert!(text.contains("\"quoted key\": \"line\\nbreak \\\"quoted\\\" \\\\ 'single' ✓\""));
+}
+
+#[test]
+fn parses_minecraft_style_snbt() {
+    let parsed = from_snbt(
+        "{Count: 1B, id: 'minecraft:stone', tag: {Unbreakable: true, Damage: 3s, Name: 'it\\'s'},
+          Motion: [0.0, -0.08, 1.], Age: 12L, Health: 20f, Scale: 1.5D, Bare: hello_world,
+          Version: +5, Exp: 1e5, Longs: [L;], Ints: [I; 1, -2]}",
+    )
+    .unwrap();
+    let expected = fastnbt::nbt!({
+        "Count": 1i8,
+        "id": "minecraft:stone",
+        "tag": { "Unbreakable": 1i8, "Damage": 3i16, "Name": "it's" },
+        "Motion": [0.0f64, -0.08f64, 1.0f64],
+        "Age": 12i64,
+        "Health": 20.0f32,
+        "Scale": 1.5f64,
+        "Bare": "hello_world",
+        "Version": 5,
+        "Exp": "1e5",
+        "Longs": Value::LongArray(LongArray::new(vec![])),
+        "Ints": [I; 1, -2],
+    });
+    assert_eq!(parsed, expected);
+
+    assert_eq!(from_snbt("  [] ").unwrap(), Value::List(vec![]));
+    assert_eq!(from_snbt("1-2").unwrap(), Value::String("1-2".to_string()));
+}
+
+#[test]
+fn reports_invalid_snbt_with_its_position() {
+    let error = |text: &str| format!("{:#}", from_snbt(text).unwrap_err());
+
+    assert_eq!(
+        error("{a: [1, 2b]}"),
+        "List elements must all have the same type at line 1, column 11"
+    );
+    assert!(error("{a: 1,\n b: 128b}").ends_with("at line 2, column 5"));
+    assert!(error("{a: 1, a: 2}").starts_with("Duplicate key `a`"));
+    assert!(error("{a: 1} {}").starts_with("Unexpected text after the value"));
+    assert!(error("{a: \"open}").starts_with("Unterminated string"));
+    assert!(error("{a: 3000000000}").starts_with("Number `3000000000` is out of range"));
+    assert!(error("[B; 1, 2]").starts_with("Array element of the wrong type"));
+    assert!(error("{a 1}").starts_with("Expected `:`"));
+    assert!(error("{a: 1").starts_with("Expected `,`"));
+    assert!(error("").starts_with("Unexpected end of text"));
+}
@@ -0,0 +1,39 @@
+use linear_region_tools::{
+    anvil::{read_anvil_region_from_reader, write_anvil_region_to_writer, AnvilWriteOptions},
+    linear::{read_linear_region_from_bytes, write_linear_region_to_writer, LinearVersion},
+    Chunk, Region,
+};
+use std::io::Cursor;
+
+#[test]
+fn regions_roundtrip_through_memory() {
+    let mut region = Region::new(-4, 7);
+    for (i, index) in [0usize, 33, 1023].into_iter().enumerate() {
+        let x = -128 + (index % 32) as i32;
+        let z = 224 + (index / 32) as i32;
+        let nbt = fastnbt::nbt!({ "xPos": x, "zPos": z, "Status": "minecraft:full" });
+        region.set_chunk(
+            index,
+            Chunk::from_nbt(&nbt, x, z).unwrap(),
+            1_600_000_000 + i as u32,
+        );
+    }
+
+    let mut anvil = Vec::new();
+    write_anvil_region_to_writer(&mut anvil, &region, &AnvilWriteOptions::default()).unwrap();
+    let from_anvil = read_anvil_region_from_reader(Cursor::new(&anvil), -4, 7).unwrap();
+
+    let mut linear = Vec::new();
+    write_linear_region_to_writer(&mut linear, &from_anvil, 3, LinearVersion::V2, 4).unwrap();
+    let back = read_linear_region_from_bytes(&linear, -4, 7).unwrap();
+
+    assert_eq!(back.chunk_count(), 3);
+    for index in [0, 33, 1023] {
+        assert_eq!(
+            back.get_chunk(index).unwrap().as_slice(),
+            region.get_chunk(index).unwrap().as_slice()
+        );
+        assert_eq!(back.timestamps[index], region.timestamps[index]);
+    }
+    assert_eq!(back.get_chunk(1023).unwrap().x, -97);
+}
@@ -0,0 +1,50 @@
+use linear_region_tools::{
+    nbt::inhabited_time,
+    selection::{ChunkBox, ChunkSelection},
+    trim::{trim_region, TrimOptions},
+    Chunk, Region,
+};
+
+#[test]
+fn trims_by_inhabited_time_in_both_chunk_layouts() {
+    let mut region = Region::new(0, 0);
+    let modern = |x: i32, ticks: i64| {
+        let nbt = fastnbt::nbt!({ "xPos": x, "InhabitedTime": ticks });
+        Chunk::from_nbt(&nbt, x, 0).unwrap()
+    };
+    let legacy = |x: i32, ticks: i64| {
+        let nbt = fastnbt::nbt!({ "Level": { "xPos": x, "InhabitedTime": ticks } });
+        Chunk::from_nbt(&nbt, x, 0).unwrap()
+    };
+    region.set_chunk(0, modern(0, 10), 1);
+    region.set_chunk(1, modern(1, 5000), 1);
+    region.set_chunk(2, legacy(2, 10), 1);
+    region.set_chunk(3, legacy(3, 5000), 1);
+    region.set_chunk(4, modern(4, 0), 1);
+    region.set_chunk(5, Chunk::new(vec![0xff, 0x00], 5, 0), 1);
+
+    assert_eq!(
+        inhabited_time(&modern(0, 10).parse_nbt().unwrap()),
+        Some(10)
+    );
+    assert_eq!(
+        inhabited_time(&legacy(0, 20).parse_nbt().unwrap()),
+        Some(20)
+    );
+
+    let options = TrimOptions {
+        min_inhabited_time: 1200,
+        protected: ChunkSelection::Box(ChunkBox::new(4, -1, 4, 1)),
+    };
+    let removed_bytes = region.get_chunk(0).unwrap().size() + region.get_chunk(2).unwrap().size();
+    let report = trim_region(&mut region, &options);
+
+    assert_eq!(report.removed, [0, 2]);
+    assert_eq!(report.kept, 4);
+    assert_eq!(report.protected, 1);
+    assert_eq!(report.unreadable, 1);
+    assert_eq!(report.bytes_removed, removed_bytes as u64);
+    assert_eq!(region.chunk_count(), 4);
+    assert!(region.get_chunk(0).is_none());
+    assert_eq!(region.timestamps[2], 0);
+}
@@ -0,0 +1,102 @@
+use linear_region_tools::{
+    anvil::{validate_anvil_file, write_anvil_region},
+    linear::{validate_linear_file, write_linear_region, LinearVersion},
+    Chunk, Region, ValidationCheck,
+};
+
+fn sample_region() -> Region {
+    let mut region = Region::new(0, 0);
+    for index in [0usize, 1, 2] {
+        let nbt = fastnbt::nbt!({ "xPos": index as i32, "zPos": 0 });
+        region.set_chunk(index, Chunk::from_nbt(&nbt, index as i32, 0).unwrap(), 10);
+    }
+    region
+}
+
+#[test]
+fn validators_locate_structural_damage() {
+    let dir = std::env::temp_dir().join("lrt_validate_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let region = sample_region();
+
+    let v1 = dir.join("r.0.0.linear");
+    write_linear_region(&v1, &region, 3, LinearVersion::V1, None).unwrap();
+    assert!(validate_linear_file(&v1).unwrap().is_valid());
+
+    // chunk_count is at byte 18, compressed_size at byte 20
+    let mut data = std::fs::read(&v1).unwrap();
+    data[19] = 7;
+    data[23] ^= 1;
+    std::fs::write(&v1, &data).unwrap();
+    let report = validate_linear_file(&v1).unwrap();
+    let offsets: Vec<_> = report.issues.iter().map(|i| i.offset).collect();
+    assert_eq!(offsets, [20, 18]);
+    assert_eq!(
+        report.issues[1].check,
+        ValidationCheck::ChunkCountMismatch {
+            declared: 7,
+            actual: 3
+        }
+    );
+
+    let v2 = dir.join("r.0.1.linear");
+    write_linear_region(&v2, &region, 3, LinearVersion::V2, None).unwrap();
+    let mut data = std::fs::read(&v2).unwrap();
+    data[26] &= !0x80; // clear chunk 0 in the bitmap
+    data[167] ^= 0xff; // checksum of bucket 0, after the empty feature list
+    std::fs::write(&v2, &data).unwrap();
+    let report = validate_linear_file(&v2).unwrap();
+    assert!(matches!(
+        report.issues[0].check,
+        ValidationCheck::ChecksumMismatch { bucket: 0, .. }
+    ));
+    let bitmap_issue = report.issues.last().unwrap();
+    assert_eq!((bitmap_issue.offset, bitmap_issue.chunk), (26, Some(0)));
+
+    let mca = dir.join("r.0.0.mca");
+    write_anvil_region(&mca, &region, 6, None).unwrap();
+    assert!(validate_anvil_file(&mca).unwrap().is_valid());
+
+    let mut data = std::fs::read(&mca).unwrap();
+    data[4..8].copy_from_slice(&[0, 0, 2, 1]); // chunk 1 now shares sector 2 with chunk 0
+    data[8..12].copy_from_slice(&[0, 1, 0, 1]); // chunk 2 points past the end
+    data[2 * 4096 + 4] = 9; // chunk 0 compression type
+    std::fs::write(&mca, &data).unwrap();
+    let report = validate_anvil_file(&mca).unwrap();
+    let found: Vec<_> = report
+        .issues
+        .iter()
+        .map(|i| (i.offset, i.chunk, i.check.clone()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (
+                8196,
+                Some(0),
+                ValidationCheck::UnsupportedCompression {
+                    compression_type: 9
+                }
+            ),
+            (4, Some(1), ValidationCheck::SectorOverlap { other: 0 }),
+            (
+                8196,
+                Some(1),
+                ValidationCheck::UnsupportedCompression {
+                    compression_type: 9
+                }
+            ),
+            (
+                8,
+                Some(2),
+                ValidationCheck::SectorOutOfBounds {
+                    sector_offset: 256,
+                    sector_count: 1
+                }
+            ),
+        ]
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
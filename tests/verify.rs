@@ -0,0 +1,49 @@
+use linear_region_tools::{
+    linear::{read_linear_region_from_bytes, write_linear_region_to_writer, LinearVersion},
+    verify::{compare_regions, MismatchKind},
+    Chunk, Region,
+};
+
+#[test]
+fn reports_every_kind_of_chunk_mismatch() {
+    let mut source = Region::new(0, 0);
+    for index in 0..4 {
+        let nbt = fastnbt::nbt!({ "xPos": index as i32, "zPos": 0 });
+        source.set_chunk(index, Chunk::from_nbt(&nbt, index as i32, 0).unwrap(), 100);
+    }
+
+    let mut data = Vec::new();
+    write_linear_region_to_writer(&mut data, &source, 3, LinearVersion::V1, 0).unwrap();
+    let mut written = read_linear_region_from_bytes(&data, 0, 0).unwrap();
+    assert!(compare_regions(&source, &written, true).is_empty());
+
+    written.remove_chunk(0);
+    written.set_chunk(1, Chunk::new(vec![1, 2, 3], 1, 0), 100);
+    written.timestamps[2] = 200;
+    written.set_chunk(5, source.get_chunk(3).unwrap().clone(), 100);
+
+    let mismatches = compare_regions(&source, &written, true);
+    let indices: Vec<_> = mismatches.iter().map(|m| m.index).collect();
+    assert_eq!(indices, [0, 1, 1, 2, 5]);
+    assert_eq!(mismatches[0].kind, MismatchKind::Missing);
+    assert!(matches!(
+        mismatches[1].kind,
+        MismatchKind::PayloadDiffers {
+            written_size: 3,
+            ..
+        }
+    ));
+    assert!(matches!(
+        mismatches[2].kind,
+        MismatchKind::InvalidNbt { .. }
+    ));
+    assert_eq!(
+        mismatches[3].kind,
+        MismatchKind::TimestampDiffers {
+            source: 100,
+            written: 200
+        }
+    );
+    assert_eq!(mismatches[4].kind, MismatchKind::Unexpected);
+    assert_eq!((mismatches[4].x, mismatches[4].z), (5, 0));
+}
@@ -0,0 +1,64 @@
+use linear_region_tools::world::{find_region_directories, list_region_files, RegionKind};
+use std::path::Path;
+
+#[test]
+fn finds_region_folders_of_every_dimension() {
+    let world = std::env::temp_dir().join("lrt_world_discovery_test");
+    let _ = std::fs::remove_dir_all(&world);
+    for dir in [
+        "region",
+        "entities",
+        "poi",
+        "DIM-1/region",
+        "DIM1/entities",
+        "dimensions/mymod/mining/region",
+        "playerdata",
+    ] {
+        std::fs::create_dir_all(world.join(dir)).unwrap();
+    }
+    std::fs::write(world.join("region/r.0.-1.mca"), b"").unwrap();
+    std::fs::write(world.join("region/r.0.-1.mca.backup"), b"").unwrap();
+    std::fs::write(world.join("region/c.0.0.mcc"), b"").unwrap();
+
+    let found = find_region_directories(&world).unwrap();
+    let summary: Vec<_> = found
+        .iter()
+        .map(|d| (d.dimension.as_str(), d.kind, d.relative_path.as_path()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                "minecraft:overworld",
+                RegionKind::Region,
+                Path::new("region")
+            ),
+            (
+                "minecraft:overworld",
+                RegionKind::Entities,
+                Path::new("entities")
+            ),
+            ("minecraft:overworld", RegionKind::Poi, Path::new("poi")),
+            (
+                "minecraft:the_nether",
+                RegionKind::Region,
+                Path::new("DIM-1/region")
+            ),
+            (
+                "minecraft:the_end",
+                RegionKind::Entities,
+                Path::new("DIM1/entities")
+            ),
+            (
+                "mymod:mining",
+                RegionKind::Region,
+                Path::new("dimensions/mymod/mining/region")
+            ),
+        ]
+    );
+
+    let files = list_region_files(world.join("region"), "mca").unwrap();
+    assert_eq!(files, vec![world.join("region/r.0.-1.mca")]);
+
+    std::fs::remove_dir_all(&world).unwrap();
+}